@@ -1,18 +1,37 @@
+use crate::audit::{self, AuditEntry};
+use crate::buffer::LineBuffer;
+use crate::escalation::Escalation;
+use crate::export;
+use crate::filter::Query;
+use crate::hit::{Hit, HitMap, ScrollTarget};
+use crate::jobs::{JobState, Jobs, PendingOp};
+use crate::keymap::{Action, Keymap};
 use crate::limine::Limine;
-use crate::snapper::{Config, Snapper, Snapshot};
+use crate::palette;
+use crate::rollback::{self, Probe, Step, Wizard};
+use crate::snapper::{Config, DeletePlan, Snapper, Snapshot, StatusSummary};
 use crate::state::State as PersistedState;
+use crate::system::{self, Preview, ProcHandle, Runner};
+use crate::theme::{self, Theme};
+use crate::vi::{self, Feed, Vi};
+use crate::view::{self, Column, Sort, View};
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use crossterm::terminal;
-use std::collections::HashMap;
+use ratatui::layout::Rect;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::Path;
 use std::sync::mpsc::{self, Receiver};
 use std::thread;
 use std::time::{Duration, Instant};
 
+// Two clicks on the same row within this time open its status
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
 #[derive(Debug, Default, Clone)]
 pub struct ListState {
     pub selected: Option<usize>,
+    // First visible row, kept between frames so clicks don't make the list jump
+    pub offset: usize,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -20,13 +39,19 @@ pub enum Mode {
     #[default]
     Normal,
     Input(InputKind),
-    ConfirmDelete(u64),
-    ConfirmRollback(u64),
+    ConfirmDelete(DeletePlan),
+    Rollback,
     ConfirmCleanup(String),
+    // job id, and the mode to return to
+    ConfirmCancel(u64, Box<Mode>),
     Help,
     Details,
-    Loading,
+    Jobs,
+    History,
     ConfigForm,
+    Views,
+    Columns,
+    Palette,
 }
 
 #[derive(Debug, Clone)]
@@ -37,20 +62,26 @@ pub enum InputKind {
     DetailsSearch,
     ConfigFieldEdit(usize),
     Filter,
+    // masked; the text is handed to sudo and never stored
+    SudoPassword,
+    // Snapshot around a command: the command line, then a description for the pair
+    AroundCommand,
+    AroundDescription(String),
+    // Destination file; the extension picks CSV, JSON or Markdown
+    ExportPath,
+    // Name to save the current table settings under
+    ViewName,
+    // Vi / and ? in the snapshot table
+    TableSearch,
+    // Vi : command line, typed over the table or the details view
+    Command(vi::Target),
 }
 
+/// What to run again once sudo credentials are cached.
 #[derive(Debug, Clone)]
-pub enum PendingOp {
-    Status { from: u64, to: u64 },
-    Diff { from: u64, to: u64 },
-    Mount { id: u64 },
-    Umount { id: u64 },
-    Rollback { id: u64 },
-    Cleanup { algorithm: String },
-    SetupQuota,
-    SetConfig,
-    GetConfigForEdit,
-    LimineSync { id: u64, name: String },
+pub enum AuthRetry {
+    Job(u64),
+    Refresh,
 }
 
 #[derive(Default)]
@@ -65,25 +96,76 @@ pub struct App {
     pub snaps_state: ListState,
     pub mode: Mode,
     pub input: String,
-    pub use_sudo: bool,
+    pub escalation: Escalation,
+    // Preview mutating commands instead of running them
+    pub dry_run: bool,
+    // Effective read-only mode (CLI flag or saved setting) and the saved setting itself,
+    // so a one-off --read-only session doesn't get persisted
+    pub read_only: bool,
+    pub read_only_saved: bool,
+    // What gets persisted; differs from `escalation` after --no-sudo
+    pub escalation_saved: Escalation,
     pub input_cursor: usize, // cursor position in chars within input
     // Details overlay state
-    pub details_text: String,
-    pub details_scroll: u16,
-    pub details_lines: u16,
+    pub details: LineBuffer,
+    pub details_scroll: usize,
     pub details_page_lines: u16,
+    // When set, the details view shows this job's (possibly still growing) output
+    pub details_job: Option<u64>,
+    // Panel to return to when the details view closes (jobs, history)
+    pub details_return: Option<Mode>,
+    // Keep the view pinned to the last page while output grows
+    pub details_follow: bool,
     // View options (fullscreen removed)
     pub filter_text: String,
+    // What gets persisted; differs from `filter_text` after --filter
+    pub filter_saved: String,
+    pub sort: Sort,
+    pub columns: Vec<Column>,
+    // Named views per config, the one each config has selected, and the settings of the
+    // unnamed default view (what the table shows when no named view is selected)
+    pub views: BTreeMap<String, Vec<View>>,
+    pub active_view: BTreeMap<String, String>,
+    pub default_view: View,
+    // Config whose view settings are loaded into the table
+    pub view_config: String,
+    // Views picker: 0 is the default view, then the config's named views
+    pub views_sel: Option<usize>,
+    // Column editor cursor, indexing `column_choices()`
+    pub columns_sel: Option<usize>,
+    // Command palette cursor, indexing the matches of the typed query (in `input`)
+    pub palette_sel: Option<usize>,
     // Animation / background work
     pub tick: u64,
-    pub status_rx: Option<Receiver<Result<String>>>,
     pub snaps_rx: Option<Receiver<Result<Vec<Snapshot>>>>,
     pub snaps_loading_for: Option<String>,
     // cache snapshots with a freshness timestamp
     pub snaps_cache: HashMap<String, (Vec<Snapshot>, Instant)>,
     pub snaps_cache_ttl: Duration,
-    pub pending: Option<PendingOp>,
-    pub loading_message: String,
+    // Background operations (status/diff streams, cleanup, rollback, Limine sync, ...)
+    pub jobs: Jobs,
+    pub jobs_sel: Option<usize>,
+    // Audit log viewer: entries oldest first, plus config/op filters
+    pub history: Vec<AuditEntry>,
+    pub history_sel: Option<usize>,
+    pub history_config: Option<String>,
+    pub history_op: Option<String>,
+    // In-flight `sudo -v` check and the action waiting for it
+    pub auth_rx: Option<Receiver<Result<()>>>,
+    pub auth_retry: Option<AuthRetry>,
+    pub auth_failures: u32,
+    // Rollback wizard, and the probe of the default subvolume/kernel for its target
+    pub rollback: Option<Wizard>,
+    pub rollback_probe_rx: Option<Receiver<(u64, Probe)>>,
+    // Snapshots marked with Space for bulk delete, and the config they belong to
+    pub marked: BTreeSet<u64>,
+    pub marked_config: String,
+    pub mark_anchor: Option<u64>,
+    pub startup: Option<Startup>,
+    // Waiting for the main loop to run it on the real terminal
+    pub run_around: Option<AroundCommand>,
+    // Per-operation timeouts in seconds (keyed by PendingOp::name)
+    pub op_timeouts: BTreeMap<String, u64>,
     pub details_title: String,
     pub details_query: String,
     // Config form editor state
@@ -91,20 +173,82 @@ pub struct App {
     pub cfg_field_idx: Option<usize>,
     // Layout toggles resembling SnapperGUI bottom bar
     pub show_userdata: bool,
+    // Right-hand pane with the change list of the selected snapshot
+    pub show_preview: bool,
     // Derived metadata for selected snapshot (for bottom Userdata panel)
     pub selected_mount_point: Option<String>,
     pub selected_diff_range: Option<(u64, u64)>,
-    pub userdata_summary: Option<String>,
-    pub userdata_rx: Option<Receiver<Result<String>>>,
+    // Status of the selected snapshot against its compare base, for the userdata panel and
+    // the preview pane; results carry the sequence number they were started for
+    pub userdata_summary: Option<std::result::Result<StatusSummary, String>>,
+    pub userdata_rx: Option<Receiver<(u64, Result<StatusSummary>)>>,
     // Debounce/background control for userdata summary
     pub userdata_summary_seq: u64,
     pub userdata_fetch_scheduled_at: Option<Instant>,
     pub userdata_planned_cfg: Option<String>,
     pub userdata_planned_from_to: Option<(u64, u64)>,
     pub userdata_inflight_seq: Option<u64>,
+    // Processes of the in-flight summary fetch, killed once the selection moves on
+    pub userdata_handle: Option<ProcHandle>,
     // Help modal scroll state
     pub help_scroll: u16,
+    // Rows the snapshot table and the help text showed in the last frame; PgUp/PgDn step by them
+    pub snaps_page_lines: u16,
+    pub help_page_lines: u16,
+    // Clickable regions of the last frame, the scrollbar being dragged and the last click
+    // (for double-click detection)
+    pub hits: HitMap,
+    pub dragging: Option<ScrollTarget>,
+    pub last_click: Option<(Instant, Hit)>,
+    // Main-view key bindings, and what was wrong with keymap.toml if it was rejected
+    pub keymap: Keymap,
+    pub keymap_problems: Vec<String>,
+    // Set by the quit action; the main loop exits
+    pub should_quit: bool,
+    // Vi keys layered over the keymap in the table and details view, and their state
+    pub vi_keys: bool,
+    pub vi: Vi,
+    // Colors the UI is drawn with, the name they were loaded from, and the saved choice
+    // (NO_COLOR changes the colors of a session without touching the saved theme)
+    pub theme: Theme,
+    pub theme_name: String,
+    pub theme_saved: Option<String>,
+}
+/// Command-line settings for this session; none of them is saved.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub dry_run: bool,
+    pub read_only: bool,
+    pub config: Option<String>,
+    pub filter: Option<String>,
+    pub no_sudo: bool,
+    pub startup: Startup,
+}
+
+/// What to open once the first snapshot list arrives (from startup flags).
+#[derive(Debug, Clone, Default)]
+pub struct Startup {
+    pub snapshot: Option<u64>,
+    pub view: Option<StartupView>,
+    pub range: Option<(u64, u64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StartupView {
+    Status,
+    Diff,
 }
+
+/// A command to run between a pre and a post snapshot. Set by the app once the pre snapshot
+/// exists; the main loop suspends the TUI, runs it and hands it back to `finish_around`.
+#[derive(Debug, Clone)]
+pub struct AroundCommand {
+    pub config: String,
+    pub pre: u64,
+    pub command: String,
+    pub description: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct ConfigField {
     pub key: String,
@@ -114,31 +258,85 @@ pub struct ConfigField {
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(opts: &Options) -> Self {
         let mut s = Self::default();
         // load persisted state
         let persisted = PersistedState::load();
-        s.use_sudo = persisted.use_sudo;
+        // A method that was uninstalled since last run falls back to off
+        s.escalation = if persisted.escalation.is_available() {
+            persisted.escalation
+        } else {
+            Escalation::Off
+        };
+        s.escalation_saved = s.escalation;
+        if opts.no_sudo {
+            s.escalation = Escalation::Off;
+        }
+        s.dry_run = opts.dry_run;
         s.input_cursor = 0;
         s.details_scroll = 0;
-        s.details_lines = 0;
         s.details_page_lines = 0;
+        s.details_job = None;
+        s.details_follow = false;
         s.tick = 0;
-        s.status_rx = None;
         s.snaps_rx = None;
         s.snaps_loading_for = None;
         // no deferred scheduling to keep navigation snappy
         s.snaps_cache = HashMap::new();
         s.snaps_cache_ttl = Duration::from_secs(3);
-        s.pending = None;
-        s.loading_message.clear();
+        s.jobs = Jobs::default();
+        s.jobs_sel = None;
+        s.auth_rx = None;
+        s.auth_retry = None;
+        s.auth_failures = 0;
+        s.op_timeouts = persisted.op_timeouts.clone();
         s.details_title = String::from("Snapshot status");
         s.details_query = String::new();
         s.cfg_fields = Vec::new();
         s.cfg_field_idx = None;
         s.filtered_snaps = Vec::new();
-        s.filter_text = persisted.filter.unwrap_or_default();
-        s.show_userdata = persisted.show_userdata;
+        s.default_view = View {
+            name: String::new(),
+            filter: persisted.filter.clone().unwrap_or_default(),
+            sort: persisted.sort,
+            columns: if persisted.columns.is_empty() {
+                view::default_columns()
+            } else {
+                persisted.columns.clone()
+            },
+            show_userdata: persisted.show_userdata,
+        };
+        s.views = persisted.views.clone();
+        s.active_view = persisted.active_view.clone();
+        s.set_view_settings(&s.default_view.clone());
+        if let Some(filter) = &opts.filter {
+            s.filter_text = filter.clone();
+        }
+        // The flag can only tighten a saved setting, never lift it
+        s.read_only = persisted.read_only || opts.read_only;
+        s.read_only_saved = persisted.read_only;
+        s.vi_keys = persisted.vi_keys;
+        s.show_preview = persisted.show_preview;
+        // NO_COLOR wins over the saved theme; switching themes afterwards is still allowed
+        s.theme_saved = persisted.theme.clone();
+        let theme_problem = if theme::no_color_requested() {
+            s.theme_name = "no-color".into();
+            s.theme = Theme::no_color();
+            None
+        } else {
+            s.theme_name = persisted.theme.clone().unwrap_or_else(|| "dark".into());
+            match theme::load(&s.theme_name) {
+                Ok(t) => {
+                    s.theme = t;
+                    None
+                }
+                Err(e) => {
+                    s.theme_name = "dark".into();
+                    s.theme = Theme::dark();
+                    Some(e)
+                }
+            }
+        };
         s.selected_mount_point = None;
         s.selected_diff_range = None;
         s.userdata_summary = None;
@@ -148,109 +346,218 @@ impl App {
         s.userdata_planned_cfg = None;
         s.userdata_planned_from_to = None;
         s.userdata_inflight_seq = None;
+        s.userdata_handle = None;
         s.help_scroll = 0;
+        (s.keymap, s.keymap_problems) = Keymap::load(s.vi_keys);
+        if opts.startup.snapshot.is_some() || opts.startup.view.is_some() {
+            s.startup = Some(opts.startup.clone());
+        }
+        // Select the config to open (--config, else the last one) before the first listing,
+        // so only that one is loaded
+        if let (Some(name), Ok(configs)) = (
+            opts.config.as_ref().or(persisted.last_config.as_ref()),
+            Snapper::list_configs(),
+        ) {
+            s.configs_state.selected = configs.iter().position(|c| &c.name == name);
+        }
         s.refresh_all();
-        // try to restore last selected config
-        if let Some(last) = persisted.last_config {
-            if let Some(idx) = s.configs.iter().position(|c| c.name == last) {
-                s.configs_state.selected = Some(idx);
-                s.load_snapshots_for_selected();
-            }
+        if let Some(problem) = theme_problem {
+            s.status = format!("Theme ignored: {problem}");
+        }
+        if let Some(problem) = s.keymap_problems.first() {
+            s.status = format!("Keymap ignored, using default keys: {problem} (? for all)");
         }
         s
     }
 
+    /// Run a main-view action; the read-only check lives here so every way in is covered.
+    pub fn run_action(&mut self, action: Action) {
+        if self.read_only && action.changes_state() {
+            self.status = format!("Read-only mode: {} is disabled", action.label());
+            return;
+        }
+        match action {
+            Action::Quit => self.should_quit = true,
+            Action::Refresh => self.refresh_all(),
+            Action::Create => self.start_create(),
+            Action::Edit => self.start_edit(),
+            Action::Delete => self.start_delete_confirm(),
+            Action::EditConfig => self.start_config_edit(),
+            Action::Help => {
+                self.help_scroll = 0;
+                self.mode = Mode::Help;
+            }
+            // Show status for selected snapshot
+            Action::Status => self.on_enter(),
+            Action::Filter => self.start_filter_input(),
+            Action::Diff => self.on_diff(),
+            Action::Mount => self.on_mount(),
+            Action::Umount => self.on_umount(),
+            Action::Rollback => self.start_rollback_wizard(),
+            Action::MakePermanent => self.make_booted_permanent(),
+            Action::ToggleMark => self.toggle_mark(),
+            Action::Around => self.start_around(),
+            Action::Export => self.start_export(),
+            Action::MarkRange => self.mark_range(),
+            Action::ClearMarks => {
+                self.marked.clear();
+                self.status = "Marks cleared".into();
+            }
+            Action::Cleanup => self.start_cleanup_input(),
+            Action::ViewConfig => self.view_config(),
+            Action::SetupQuota => self.setup_quota(),
+            Action::LimineSync => self.sync_limine_for_selected(),
+            Action::Jobs => self.open_jobs_panel(),
+            Action::History => self.open_history(),
+            Action::DryRun => {
+                self.dry_run = !self.dry_run;
+                self.status = if self.dry_run {
+                    "Dry run on: changes are previewed, not executed".into()
+                } else {
+                    "Dry run off".into()
+                };
+            }
+            Action::ToggleUserdata => {
+                self.show_userdata = !self.show_userdata;
+                self.persist_state();
+            }
+            Action::Preview => {
+                self.show_preview = !self.show_preview;
+                // The panel summary stops after a few lines; the pane needs the whole list
+                self.update_selected_snapshot_meta();
+                self.persist_state();
+            }
+            Action::Views => self.open_views_picker(),
+            Action::NextView => self.cycle_view(),
+            Action::SortNext => self.cycle_sort_column(),
+            Action::SortReverse => {
+                self.sort.descending = !self.sort.descending;
+                self.table_layout_changed();
+            }
+            Action::Columns => {
+                self.columns_sel = Some(0);
+                self.mode = Mode::Columns;
+            }
+            Action::Palette => {
+                self.input.clear();
+                self.input_cursor = 0;
+                self.palette_sel = Some(0);
+                self.mode = Mode::Palette;
+            }
+            Action::PrevConfig => self.select_prev_config(),
+            Action::NextConfig => self.select_next_config(),
+            Action::Escalation => {
+                self.escalation = self.escalation.next_available();
+                self.escalation_saved = self.escalation;
+                self.status = format!("escalation: {}", self.escalation.name());
+                self.persist_state();
+                self.snaps_cache.clear();
+                self.refresh_all();
+            }
+            Action::Theme => self.next_theme(),
+            Action::ViKeys => {
+                self.vi_keys = !self.vi_keys;
+                self.vi.reset();
+                self.keymap.set_vi(self.vi_keys);
+                let shadowed = self.keymap.vi_conflicts();
+                self.status = if !self.vi_keys {
+                    "Vi keys off".into()
+                } else if let Some(problem) = shadowed.first() {
+                    format!("Vi keys on; {problem}")
+                } else {
+                    "Vi keys on: j/k gg/G Ctrl-d/u, / ? n N, counts, : commands, m/' marks".into()
+                };
+                self.persist_state();
+            }
+            Action::Up => self.on_up(),
+            Action::Down => self.on_down(),
+            Action::PageUp => self.on_page_up(),
+            Action::PageDown => self.on_page_down(),
+            Action::First => self.on_home(),
+            Action::Last => self.on_end(),
+        }
+    }
+
+    /// Why `action` can't run right now, e.g. "no snapshot selected"; None when it can.
+    pub fn action_unavailable(&self, action: Action) -> Option<&'static str> {
+        if self.read_only && action.changes_state() {
+            return Some("read-only mode");
+        }
+        let has_config = self.selected_config_name().is_some();
+        let has_snapshot = self
+            .snaps_state
+            .selected
+            .is_some_and(|i| i < self.filtered_snaps.len());
+        match action {
+            Action::Create
+            | Action::Cleanup
+            | Action::ViewConfig
+            | Action::EditConfig
+            | Action::SetupQuota
+            | Action::Around
+            | Action::Export
+                if !has_config =>
+            {
+                Some("no config selected")
+            }
+            Action::Edit
+            | Action::Delete
+            | Action::Status
+            | Action::Diff
+            | Action::Mount
+            | Action::Umount
+            | Action::Rollback
+            | Action::LimineSync
+            | Action::ToggleMark
+            | Action::MarkRange
+                if !has_snapshot =>
+            {
+                Some("no snapshot selected")
+            }
+            Action::Export
+            | Action::Up
+            | Action::Down
+            | Action::PageUp
+            | Action::PageDown
+            | Action::First
+            | Action::Last
+                if self.filtered_snaps.is_empty() =>
+            {
+                Some("no snapshots shown")
+            }
+            Action::MakePermanent if self.booted_snapshot().is_none() => {
+                Some("not booted from a snapshot")
+            }
+            Action::ClearMarks if self.marked.is_empty() => Some("nothing marked"),
+            Action::NextView if self.config_views().is_empty() => Some("no saved views"),
+            Action::NextConfig | Action::PrevConfig if self.configs.len() < 2 => {
+                Some("only one config")
+            }
+            _ => None,
+        }
+    }
+
     pub fn on_key(&mut self, key: KeyEvent) {
+        // Vi keys come first in the table and the details view; keys they don't use fall
+        // through to the usual bindings below
+        if self.vi_keys {
+            let target = match self.mode {
+                Mode::Normal => Some(vi::Target::Table),
+                Mode::Details => Some(vi::Target::Details),
+                _ => None,
+            };
+            if let Some(target) = target {
+                match self.vi.feed(&key) {
+                    Feed::Command(cmd) => return self.run_vi(cmd, target),
+                    Feed::Consumed => return,
+                    Feed::Unhandled => {}
+                }
+            }
+        }
         match &mut self.mode {
             Mode::Normal => {
-                match key.code {
-                    KeyCode::Tab => {
-                        if key.modifiers.contains(KeyModifiers::SHIFT) {
-                            self.select_prev_config();
-                        } else {
-                            self.select_next_config();
-                        }
-                    }
-                    KeyCode::Up => self.on_up(),
-                    KeyCode::Down => self.on_down(),
-                    KeyCode::PageUp => self.on_page_up(),
-                    KeyCode::PageDown => self.on_page_down(),
-                    KeyCode::Home => self.on_home(),
-                    KeyCode::End => self.on_end(),
-                    KeyCode::Char('r') => self.refresh_all(),
-                    KeyCode::Char('c') => self.start_create(),
-                    KeyCode::Char('e') => self.start_edit(),
-                    KeyCode::Char('d') => self.start_delete_confirm(),
-                    KeyCode::Char('g') => {
-                        self.start_config_edit();
-                    }
-                    KeyCode::Char('?') => {
-                        self.help_scroll = 0;
-                        self.mode = Mode::Help;
-                    }
-                    KeyCode::Enter => {
-                        // Show status for selected snapshot
-                        self.on_enter();
-                    }
-                    KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        self.start_filter_input();
-                    }
-                    KeyCode::Char('F') => {
-                        // Fullscreen removed; reuse Shift+f as filter input shortcut
-                        self.start_filter_input();
-                    }
-                    // Fullscreen removed
-                    KeyCode::Char('x') => {
-                        // Reset details scroll to avoid stale positions before opening
-                        self.details_scroll = 0;
-                        self.on_diff();
-                        self.details_scroll = u16::MAX; // draw clamps to last page (ensures thumb bottom)
-                    }
-                    KeyCode::Char('m') => {
-                        self.on_mount();
-                    }
-                    KeyCode::Char('U') => {
-                        self.on_umount();
-                    }
-                    KeyCode::Char('R') => {
-                        self.start_rollback_confirm();
-                    }
-                    KeyCode::Char('K') => {
-                        self.start_cleanup_input();
-                    }
-                    KeyCode::Char('C') => {
-                        self.view_config();
-                    }
-                    KeyCode::Char('Q') => {
-                        self.setup_quota();
-                    }
-                    KeyCode::Char('Y') => {
-                        self.sync_limine_for_selected();
-                    }
-                    KeyCode::Char('u') => {
-                        self.show_userdata = !self.show_userdata;
-                        self.persist_state();
-                    }
-                    KeyCode::Char('[') => {
-                        self.select_prev_config();
-                    }
-                    KeyCode::Char(']') => {
-                        self.select_next_config();
-                    }
-                    KeyCode::Left => {
-                        self.select_prev_config();
-                    }
-                    KeyCode::Right => {
-                        self.select_next_config();
-                    }
-                    KeyCode::Char('S') => {
-                        self.use_sudo = !self.use_sudo;
-                        self.status = format!("sudo: {}", if self.use_sudo { "on" } else { "off" });
-                        self.persist_state();
-                        self.snaps_cache.clear();
-                        self.refresh_all();
-                    }
-                    _ => {}
+                if let Some(action) = self.keymap.action_for(&key) {
+                    self.run_action(action);
                 }
             }
             Mode::Input(kind) => {
@@ -263,15 +570,32 @@ impl App {
                                 self.input.clear();
                                 // keep status unchanged; no pending op to cancel here
                             }
+                            InputKind::ViewName => {
+                                self.mode = Mode::Views;
+                                self.input.clear();
+                            }
+                            InputKind::DetailsSearch | InputKind::Command(vi::Target::Details) => {
+                                self.mode = Mode::Details;
+                                self.input.clear();
+                            }
+                            InputKind::SudoPassword => {
+                                self.mode = Mode::Normal;
+                                self.input.clear();
+                                self.input_cursor = 0;
+                                self.auth_retry = None;
+                                self.status = "sudo authentication cancelled".into();
+                            }
                             _ => {
                                 self.mode = Mode::Normal;
                                 self.input.clear();
                                 self.status = "Cancelled".into();
-                                self.status_rx = None;
-                                self.pending = None;
                             }
                         }
                     }
+                    // Passwords are used verbatim, never trimmed or copied
+                    KeyCode::Enter if matches!(kind, InputKind::SudoPassword) => {
+                        self.finish_sudo_password();
+                    }
                     KeyCode::Enter => {
                         let text = self.input.trim().to_string();
                         match kind.clone() {
@@ -282,8 +606,23 @@ impl App {
                             InputKind::ConfigFieldEdit(idx) => {
                                 self.finish_config_field_edit(idx, &text)
                             }
+                            InputKind::SudoPassword => {}
+                            InputKind::AroundCommand => self.finish_around_command(&text),
+                            InputKind::ExportPath => self.finish_export(&text),
+                            InputKind::ViewName => self.save_view(&text),
+                            InputKind::TableSearch => self.finish_table_search(&text),
+                            InputKind::Command(target) => self.finish_command(target, &text),
+                            InputKind::AroundDescription(command) => {
+                                self.finish_around_description(&command, &text)
+                            }
                             InputKind::Filter => {
-                                self.filter_text = text;
+                                // Keep the prompt open; the error is shown under the input
+                                if let Err(e) = Query::parse(&text) {
+                                    self.status = format!("Filter: {e}");
+                                    return;
+                                }
+                                self.filter_text = text.clone();
+                                self.filter_saved = text;
                                 self.apply_filter();
                                 self.snaps_state.selected = if self.filtered_snaps.is_empty() {
                                     None
@@ -293,8 +632,10 @@ impl App {
                                 self.persist_state();
                                 self.mode = Mode::Normal;
                                 // Clamp any open details scroll to the new content length
-                                if self.details_scroll > self.details_lines.saturating_sub(1) {
-                                    self.details_scroll = self.details_lines.saturating_sub(1);
+                                if self.details_scroll > self.details_buf().len().saturating_sub(1)
+                                {
+                                    self.details_scroll =
+                                        self.details_buf().len().saturating_sub(1);
                                 }
                             }
                         }
@@ -317,46 +658,39 @@ impl App {
                     KeyCode::End => {
                         self.input_move_end();
                     }
-                    KeyCode::Char(c) => {
-                        if key.modifiers.is_empty() || key.modifiers == KeyModifiers::SHIFT {
-                            self.input_insert_char(c);
-                        }
+                    KeyCode::Char(c)
+                        if key.modifiers.is_empty() || key.modifiers == KeyModifiers::SHIFT =>
+                    {
+                        self.input_insert_char(c);
                     }
                     _ => {}
                 }
             }
-            Mode::ConfirmDelete(id) => match key.code {
+            Mode::ConfirmDelete(plan) => match key.code {
                 KeyCode::Esc => {
                     self.mode = Mode::Normal;
                     self.status = "Delete cancelled".into();
                 }
-                KeyCode::Char('y') => {
-                    let id = *id;
-                    self.mode = Mode::Normal;
-                    self.on_delete_confirmed(id);
-                }
-                KeyCode::Char('n') => {
-                    self.mode = Mode::Normal;
-                    self.status = "Delete cancelled".into();
-                }
-                _ => {}
-            },
-            Mode::ConfirmRollback(id) => match key.code {
-                KeyCode::Esc => {
-                    self.mode = Mode::Normal;
-                    self.status = "Rollback cancelled".into();
+                KeyCode::Char('y') if !plan.important.is_empty() && !plan.important_acked => {
+                    plan.important_acked = true;
                 }
                 KeyCode::Char('y') => {
-                    let id = *id;
+                    let ids = plan.ids.clone();
                     self.mode = Mode::Normal;
-                    self.on_rollback_confirmed(id);
+                    self.on_delete_confirmed(ids);
+                }
+                KeyCode::Char('p') if !plan.orphaned_posts.is_empty() => {
+                    let mut ids = plan.ids.clone();
+                    ids.extend(plan.orphaned_posts.iter().copied());
+                    self.start_delete_plan(&ids);
                 }
                 KeyCode::Char('n') => {
                     self.mode = Mode::Normal;
-                    self.status = "Rollback cancelled".into();
+                    self.status = "Delete cancelled".into();
                 }
                 _ => {}
             },
+            Mode::Rollback => self.on_rollback_key(key.code),
             Mode::ConfirmCleanup(alg) => match key.code {
                 KeyCode::Esc => {
                     self.mode = Mode::Normal;
@@ -384,10 +718,10 @@ impl App {
                     self.help_scroll = self.help_scroll.saturating_add(1);
                 }
                 KeyCode::PageUp => {
-                    self.help_scroll = self.help_scroll.saturating_sub(10);
+                    self.help_scroll = self.help_scroll.saturating_sub(self.help_page_lines.max(1));
                 }
                 KeyCode::PageDown => {
-                    self.help_scroll = self.help_scroll.saturating_add(10);
+                    self.help_scroll = self.help_scroll.saturating_add(self.help_page_lines.max(1));
                 }
                 KeyCode::Home => {
                     self.help_scroll = 0;
@@ -399,39 +733,36 @@ impl App {
             },
             Mode::Details => match key.code {
                 KeyCode::Esc | KeyCode::Char('q') => {
-                    self.mode = Mode::Normal;
+                    self.close_details();
                 }
                 KeyCode::Up => {
+                    self.details_follow = false;
                     self.details_scroll = self.details_scroll.saturating_sub(1);
                 }
                 KeyCode::Down => {
                     self.details_scroll = self.details_scroll.saturating_add(1);
                 }
                 KeyCode::PageUp => {
-                    // Step by the current visible page height; UI clamps
-                    let page = if self.details_page_lines > 0 {
-                        self.details_page_lines
-                    } else {
-                        self.estimate_details_page_lines()
-                    };
+                    // Step by the page height of the last frame; UI clamps
+                    let page = usize::from(self.details_page_lines.max(1));
+                    self.details_follow = false;
                     self.details_scroll = self.details_scroll.saturating_sub(page);
                 }
                 KeyCode::PageDown => {
-                    let page = if self.details_page_lines > 0 {
-                        self.details_page_lines
-                    } else {
-                        self.estimate_details_page_lines()
-                    };
+                    let page = usize::from(self.details_page_lines.max(1));
                     self.details_scroll = self.details_scroll.saturating_add(page);
                 }
                 KeyCode::Home => {
+                    self.details_follow = false;
                     self.details_scroll = 0;
                 }
                 KeyCode::End => {
-                    self.details_scroll = u16::MAX; // draw clamps to content
+                    // Pin to the bottom; keeps following while output is still streaming
+                    self.details_follow = true;
+                    self.details_scroll = usize::MAX; // draw clamps to content
                 }
                 KeyCode::Char('/') => {
-                    self.start_details_search();
+                    self.start_details_search(false);
                 }
                 KeyCode::Char('n') => {
                     self.find_next();
@@ -442,20 +773,97 @@ impl App {
                 KeyCode::Char('e') => {
                     self.start_config_edit();
                 }
+                KeyCode::Char('x') => {
+                    if let Some(id) = self.details_job {
+                        self.request_cancel(id);
+                    }
+                }
                 _ => {}
             },
-            Mode::Loading => {
-                // Allow cancel while loading
+            Mode::Jobs => {
+                let ids = self.jobs_view_ids();
+                let last = ids.len().saturating_sub(1);
+                match key.code {
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('J') => {
+                        self.mode = Mode::Normal;
+                    }
+                    KeyCode::Up => {
+                        self.jobs_sel = Some(self.jobs_sel.unwrap_or(0).saturating_sub(1));
+                    }
+                    KeyCode::Down => {
+                        self.jobs_sel = Some((self.jobs_sel.unwrap_or(0) + 1).min(last));
+                    }
+                    KeyCode::Home => {
+                        self.jobs_sel = Some(0);
+                    }
+                    KeyCode::End => {
+                        self.jobs_sel = Some(last);
+                    }
+                    KeyCode::Enter => {
+                        if let Some(&id) = self.jobs_sel.and_then(|i| ids.get(i)) {
+                            let follow = self.jobs.get(id).is_some_and(|j| j.is_running());
+                            self.open_job_output(id, follow);
+                            self.details_return = Some(Mode::Jobs);
+                        }
+                    }
+                    KeyCode::Char('x') | KeyCode::Delete => {
+                        if let Some(&id) = self.jobs_sel.and_then(|i| ids.get(i)) {
+                            self.request_cancel(id);
+                        }
+                    }
+                    KeyCode::Char('c') => {
+                        self.jobs.clear_finished();
+                        self.jobs_sel = if self.jobs.list.is_empty() {
+                            None
+                        } else {
+                            Some(0)
+                        };
+                    }
+                    _ => {}
+                }
+            }
+            Mode::Views => self.on_views_key(key),
+            Mode::Columns => self.on_columns_key(key),
+            Mode::Palette => self.on_palette_key(key),
+            Mode::History => {
+                let last = self.history_view().len().saturating_sub(1);
+                let sel = self.history_sel.unwrap_or(0);
                 match key.code {
-                    KeyCode::Esc | KeyCode::Char('q') => {
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('H') => {
                         self.mode = Mode::Normal;
-                        self.status_rx = None;
-                        self.pending = None;
-                        self.status = "Cancelled".into();
                     }
+                    KeyCode::Up => self.history_sel = Some(sel.saturating_sub(1)),
+                    KeyCode::Down => self.history_sel = Some((sel + 1).min(last)),
+                    KeyCode::PageUp => self.history_sel = Some(sel.saturating_sub(10)),
+                    KeyCode::PageDown => self.history_sel = Some((sel + 10).min(last)),
+                    KeyCode::Home => self.history_sel = Some(0),
+                    KeyCode::End => self.history_sel = Some(last),
+                    KeyCode::Char('c') => {
+                        let values = self.history.iter().map(|e| e.config.clone()).collect();
+                        self.history_config = cycle_filter(&self.history_config, values);
+                        self.history_sel = Some(0);
+                    }
+                    KeyCode::Char('o') => {
+                        let values = self.history.iter().map(|e| e.op.clone()).collect();
+                        self.history_op = cycle_filter(&self.history_op, values);
+                        self.history_sel = Some(0);
+                    }
+                    KeyCode::Char('r') => self.open_history(),
+                    KeyCode::Enter => self.show_history_entry(),
                     _ => {}
                 }
             }
+            Mode::ConfirmCancel(id, back) => match key.code {
+                KeyCode::Char('y') => {
+                    let id = *id;
+                    self.mode = (**back).clone();
+                    self.cancel_job(id);
+                }
+                KeyCode::Esc | KeyCode::Char('n') => {
+                    self.mode = (**back).clone();
+                }
+                _ => {}
+            },
             Mode::ConfigForm => match key.code {
                 KeyCode::Esc => {
                     self.mode = Mode::Normal;
@@ -476,10 +884,8 @@ impl App {
                         self.cfg_field_idx = Some(0);
                     }
                 }
-                KeyCode::Home => {
-                    if !self.cfg_fields.is_empty() {
-                        self.cfg_field_idx = Some(0);
-                    }
+                KeyCode::Home if !self.cfg_fields.is_empty() => {
+                    self.cfg_field_idx = Some(0);
                 }
                 KeyCode::End => {
                     let last = self.cfg_fields.len().saturating_sub(1);
@@ -505,7 +911,7 @@ impl App {
     }
 
     fn sync_limine_for_selected(&mut self) {
-        let Some(cfg) = self.selected_config_name() else {
+        let Some(cfg) = self.selected_config_name().map(str::to_string) else {
             self.status = "Select a config first".into();
             return;
         };
@@ -517,35 +923,238 @@ impl App {
             return;
         };
         let id = s.id;
+        self.sync_limine(&cfg, id);
+    }
+
+    fn sync_limine(&mut self, cfg: &str, id: u64) {
         let name = format!("{}-{}", cfg, id);
-        // Spawn background thread calling Limine logic
-        let (tx, rx) = mpsc::channel::<Result<String>>();
-        let use_sudo = self.use_sudo;
+        // Run Limine logic as a background job
         let name_clone = name.clone();
-        thread::spawn(move || {
-            let res = Limine::sync_snapshot_to_limine(id, &name_clone, use_sudo);
-            let _ = tx.send(res);
-        });
-        self.status_rx = Some(rx);
-        self.pending = Some(PendingOp::LimineSync {
-            id,
-            name: name.clone(),
-        });
-        self.loading_message = format!("Syncing snapshot #{} to Limine…", id);
-        self.details_title = format!("Limine sync for {}", name);
-        self.mode = Mode::Loading;
-        self.status.clear();
+        let cfg_owned = cfg.to_string();
+        self.spawn_job_for(
+            cfg.to_string(),
+            PendingOp::LimineSync {
+                id,
+                name: name.clone(),
+            },
+            format!("Limine sync for {}", name),
+            move |runner, _| Limine::sync_snapshot_to_limine(&cfg_owned, id, &name_clone, runner),
+        );
+    }
+
+    fn runner_for(&self, op: &str) -> Runner {
+        let timeout = self
+            .op_timeouts
+            .get(op)
+            .filter(|secs| **secs > 0)
+            .map(|secs| Duration::from_secs(*secs));
+        let mut runner = Runner::new(self.escalation, timeout);
+        runner.read_only = self.read_only;
+        if self.dry_run {
+            runner.preview = Some(Preview::default());
+        }
+        runner
+    }
+
+    /// Start `work` as a background job with a fresh cancellable runner.
+    /// Mutating operations are serialized per config so they can't race each other.
+    fn spawn_job<F>(&mut self, op: PendingOp, label: String, work: F) -> Option<u64>
+    where
+        F: Fn(&Runner, &mut dyn FnMut(String) -> bool) -> Result<String> + Send + Sync + 'static,
+    {
+        let config = self.selected_config_name().unwrap_or("").to_string();
+        self.spawn_job_for(config, op, label, work)
+    }
+
+    /// Like `spawn_job`, for a config that may no longer be the selected one.
+    fn spawn_job_for<F>(
+        &mut self,
+        config: String,
+        op: PendingOp,
+        label: String,
+        work: F,
+    ) -> Option<u64>
+    where
+        F: Fn(&Runner, &mut dyn FnMut(String) -> bool) -> Result<String> + Send + Sync + 'static,
+    {
+        if self.read_only && !op.allowed_read_only() {
+            self.status = format!("Read-only mode: {} is disabled", op.name());
+            return None;
+        }
+        if op.is_mutating() {
+            if let Some(other) = self
+                .jobs
+                .running()
+                .find(|j| j.op.is_mutating() && j.config == config)
+            {
+                self.status = format!(
+                    "#{} {} is still running on {}; wait for it or cancel it (J)",
+                    other.id,
+                    other.op.name(),
+                    config
+                );
+                return None;
+            }
+        }
+        let runner = self.runner_for(op.name());
+        let id = self.jobs.spawn(op, config, label.clone(), runner, work);
+        self.status = format!("Started #{id}: {label}");
+        Some(id)
+    }
+
+    fn open_job_output(&mut self, id: u64, follow: bool) {
+        let Some(job) = self.jobs.get(id) else {
+            return;
+        };
+        self.details_return = None;
+        self.details_title = job.label.clone();
+        self.details_job = Some(id);
+        self.details_scroll = if follow { usize::MAX } else { 0 };
+        self.details_follow = follow;
+        self.mode = Mode::Details;
+    }
+
+    fn open_history(&mut self) {
+        self.history = audit::load();
+        self.history_sel = Some(0);
+        self.mode = Mode::History;
+        if self.history.is_empty() {
+            self.status = format!("No audit entries yet ({})", audit::log_path().display());
+        }
+    }
+
+    /// Audit entries matching the history filters, newest first.
+    pub fn history_view(&self) -> Vec<&AuditEntry> {
+        self.history
+            .iter()
+            .rev()
+            .filter(|e| self.history_config.as_ref().is_none_or(|c| &e.config == c))
+            .filter(|e| self.history_op.as_ref().is_none_or(|o| &e.op == o))
+            .collect()
+    }
+
+    fn show_history_entry(&mut self) {
+        let view = self.history_view();
+        let Some(e) = self.history_sel.and_then(|i| view.get(i)) else {
+            return;
+        };
+        let outcome = match (&e.error, e.exit_code) {
+            (Some(err), _) => format!("error: {err}"),
+            (None, Some(code)) => format!("exit code {code}"),
+            (None, None) => "no exit code".to_string(),
+        };
+        let mut text = format!(
+            "Time:       {}\nUser:       {}\nEscalation: {}\nConfig:     {}\nOperation:  {}\nOutcome:    {}\n\nCommand:\n  {}\n",
+            e.timestamp,
+            e.user,
+            e.escalation,
+            if e.config.is_empty() { "-" } else { &e.config },
+            e.op,
+            outcome,
+            e.command_line()
+        );
+        if !e.stderr.is_empty() {
+            text.push_str("\nStderr:\n");
+            for line in e.stderr.lines() {
+                text.push_str(&format!("  {line}\n"));
+            }
+        }
+        let title = format!("History: {} {}", e.op, e.timestamp);
+        self.show_details_text(title, &text);
+        self.details_return = Some(Mode::History);
+    }
+
+    /// Show what a dry-run `runner` collected instead of the operation's result.
+    fn show_details_text(&mut self, title: String, text: &str) {
+        self.details = LineBuffer::from_text(text);
+        self.details_job = None;
+        self.details_title = title;
+        self.details_scroll = 0;
+        self.details_follow = false;
+        self.mode = Mode::Details;
+    }
+
+    fn open_jobs_panel(&mut self) {
+        let n = self.jobs.list.len();
+        self.jobs_sel = match self.jobs_sel {
+            _ if n == 0 => None,
+            Some(i) => Some(i.min(n - 1)),
+            None => Some(0),
+        };
+        self.mode = Mode::Jobs;
+    }
+
+    /// Job ids in panel order (newest first).
+    pub fn jobs_view_ids(&self) -> Vec<u64> {
+        self.jobs.list.iter().rev().map(|j| j.id).collect()
+    }
+
+    fn request_cancel(&mut self, id: u64) {
+        let Some(job) = self.jobs.get(id) else {
+            return;
+        };
+        if !job.is_running() {
+            self.status = format!("#{} already finished", id);
+            return;
+        }
+        if job.op.is_dangerous() {
+            self.mode = Mode::ConfirmCancel(id, Box::new(self.mode.clone()));
+        } else {
+            self.cancel_job(id);
+        }
+    }
+
+    fn cancel_job(&mut self, id: u64) {
+        if let Some(job) = self.jobs.get_mut(id) {
+            // The worker reports back once its processes are actually gone
+            job.cancel();
+            self.status = format!("Cancelling #{}: {}…", id, job.label);
+        }
+    }
+
+    /// Buffer shown by the details view: a job's output or static text.
+    pub fn details_buf(&self) -> &LineBuffer {
+        self.details_job
+            .and_then(|id| self.jobs.get(id))
+            .map(|j| &j.output)
+            .unwrap_or(&self.details)
+    }
+
+    pub fn details_job_running(&self) -> bool {
+        self.details_job
+            .and_then(|id| self.jobs.get(id))
+            .is_some_and(|j| j.is_running())
     }
+
     fn persist_state(&self) {
+        // Changes made while a named view is selected stay unsaved until the view is saved
+        let base = if self.active_view_name().is_none() {
+            View {
+                filter: self.filter_saved.clone(),
+                ..self.current_view("")
+            }
+        } else {
+            self.default_view.clone()
+        };
         let st = PersistedState {
-            use_sudo: self.use_sudo,
+            escalation: self.escalation_saved,
+            use_sudo: None,
             last_config: self.selected_config_name().map(|s| s.to_string()),
-            filter: if self.filter_text.trim().is_empty() {
+            filter: if base.filter.trim().is_empty() {
                 None
             } else {
-                Some(self.filter_text.clone())
+                Some(base.filter)
             },
-            show_userdata: self.show_userdata,
+            show_userdata: base.show_userdata,
+            sort: base.sort,
+            columns: base.columns,
+            views: self.views.clone(),
+            active_view: self.active_view.clone(),
+            read_only: self.read_only_saved,
+            vi_keys: self.vi_keys,
+            show_preview: self.show_preview,
+            theme: self.theme_saved.clone(),
+            op_timeouts: self.op_timeouts.clone(),
         };
         st.save();
     }
@@ -581,17 +1190,12 @@ impl App {
             self.mode = Mode::Normal;
             return;
         }
-        let (tx, rx) = mpsc::channel::<Result<String>>();
-        let use_sudo = self.use_sudo;
-        thread::spawn(move || {
-            let res = Snapper::set_config(&cfg_name, &pairs, use_sudo);
-            let _ = tx.send(res);
-        });
-        self.status_rx = Some(rx);
-        self.pending = Some(PendingOp::SetConfig);
-        self.loading_message = String::from("Applying config changes…");
-        self.status.clear();
-        self.mode = Mode::Loading;
+        self.mode = Mode::Normal;
+        self.spawn_job(
+            PendingOp::SetConfig,
+            format!("Set config {}", cfg_name),
+            move |runner, _| Snapper::set_config(&cfg_name, &pairs, runner),
+        );
     }
 
     fn on_up(&mut self) {
@@ -624,7 +1228,7 @@ impl App {
             return;
         }
         let idx = self.snaps_state.selected.unwrap_or(0);
-        let new = idx.saturating_sub(10);
+        let new = idx.saturating_sub(usize::from(self.snaps_page_lines.max(1)));
         self.snaps_state.selected = Some(new);
         self.update_selected_snapshot_meta();
     }
@@ -635,7 +1239,7 @@ impl App {
             return;
         }
         let idx = self.snaps_state.selected.unwrap_or(0);
-        let new = (idx + 10).min(len.saturating_sub(1));
+        let new = (idx + usize::from(self.snaps_page_lines.max(1))).min(len.saturating_sub(1));
         self.snaps_state.selected = Some(new);
         self.update_selected_snapshot_meta();
     }
@@ -704,6 +1308,16 @@ impl App {
             return;
         };
         let cfg_name = cfg.name.clone();
+        // Each config tab shows its own selected view
+        if self.view_config != cfg_name {
+            self.enter_config_view(&cfg_name);
+        }
+        // Marks are snapshot numbers, which only mean something within one config
+        if self.marked_config != cfg_name {
+            self.marked.clear();
+            self.mark_anchor = None;
+            self.marked_config = cfg_name.clone();
+        }
         // apply cache immediately for responsiveness
         if let Some((cached, seen_at)) = self.snaps_cache.get(&cfg_name).cloned() {
             self.snapshots = cached;
@@ -734,11 +1348,20 @@ impl App {
         // start background refresh; drop previous receiver if any
         self.snaps_rx = None;
         self.snaps_loading_for = None;
-        let use_sudo = self.use_sudo;
+        let runner = self.runner_for("list");
         let (tx, rx) = mpsc::channel::<Result<Vec<Snapshot>>>();
         let cfg_name_for_thread = cfg_name.clone();
+        let with_size = self.wants_size();
         thread::spawn(move || {
-            let res = Snapper::list_snapshots(&cfg_name_for_thread, use_sudo);
+            let mut res = Snapper::list_snapshots(&cfg_name_for_thread, &runner);
+            // Sizes are best effort: without quota the column just stays empty
+            if let (Ok(snaps), true) = (&mut res, with_size) {
+                if let Ok(sizes) = Snapper::used_space(&cfg_name_for_thread, &runner) {
+                    for s in snaps.iter_mut() {
+                        s.used_space = sizes.get(&s.id).copied();
+                    }
+                }
+            }
             let _ = tx.send(res);
         });
         self.snaps_rx = Some(rx);
@@ -753,13 +1376,10 @@ impl App {
 
     fn update_selected_snapshot_meta(&mut self) {
         // precompute simple diff range and mountpoint candidates; schedule a debounced summary fetch
+        self.cancel_userdata_fetch();
         if let Some(sel) = self.snaps_state.selected {
             if let Some(curr) = self.filtered_snaps.get(sel) {
-                let from = if sel > 0 {
-                    self.filtered_snaps.get(sel - 1).map(|p| p.id).unwrap_or(0)
-                } else {
-                    0
-                };
+                let from = self.compare_base(curr);
                 self.selected_diff_range = Some((from, curr.id));
                 let cfg_owned_name = self.selected_config_name().unwrap_or("").to_string();
                 let c = cfg_owned_name.clone();
@@ -786,12 +1406,21 @@ impl App {
         self.selected_diff_range = None;
         self.selected_mount_point = None;
         self.userdata_summary = None;
-        self.userdata_rx = None;
         self.userdata_planned_cfg = None;
         self.userdata_planned_from_to = None;
         self.userdata_fetch_scheduled_at = None;
     }
 
+    /// Kill the summary fetch for a previous selection so the next one starts right away
+    /// instead of queueing behind a full `snapper status` run.
+    fn cancel_userdata_fetch(&mut self) {
+        if let Some(h) = self.userdata_handle.take() {
+            h.cancel();
+        }
+        self.userdata_rx = None;
+        self.userdata_inflight_seq = None;
+    }
+
     fn selected_config_name(&self) -> Option<&str> {
         let idx = self.configs_state.selected?;
         Some(self.configs.get(idx)?.name.as_str())
@@ -807,32 +1436,161 @@ impl App {
         self.mode = Mode::Input(InputKind::Create);
     }
 
-    fn finish_create(&mut self, desc: &str) {
+    /// Select the `--snapshot` given at launch, then open the `--view` for `--range`, or for
+    /// the selection when no range was given. Stops if the snapshot isn't in the list.
+    fn apply_startup(&mut self, startup: Startup) {
+        if let Some(id) = startup.snapshot {
+            match self.filtered_snaps.iter().position(|s| s.id == id) {
+                Some(idx) => {
+                    self.snaps_state.selected = Some(idx);
+                    self.update_selected_snapshot_meta();
+                }
+                None => {
+                    self.status = format!("Snapshot #{id} not found (or hidden by the filter)");
+                    return;
+                }
+            }
+        }
+        let Some(cfg) = self.selected_config_name().map(str::to_string) else {
+            return;
+        };
+        match (startup.view, startup.range) {
+            (Some(StartupView::Status), Some((from, to))) => self.start_status(&cfg, from, to),
+            (Some(StartupView::Diff), Some((from, to))) => self.start_diff(&cfg, from, to),
+            // Without a range, the same default range as Enter / x on the selection
+            (Some(StartupView::Status), None) => self.on_enter(),
+            (Some(StartupView::Diff), None) => self.on_diff(),
+            (None, _) => {}
+        }
+    }
+
+    fn start_export(&mut self) {
         let Some(cfg) = self.selected_config_name() else {
             self.status = "Select a config first".into();
             return;
         };
-        match Snapper::create(
-            cfg,
-            if desc.is_empty() {
-                "Created via snapper-tui"
-            } else {
-                desc
-            },
-            self.use_sudo,
-        ) {
-            Ok(_) => {
-                self.status = format!("Created snapshot in {cfg}");
-                self.mode = Mode::Normal;
-                self.input.clear();
-                self.snaps_cache.clear();
-                self.load_snapshots_for_selected();
-            }
-            Err(e) => {
-                self.status = format!("Create failed: {e}");
-                self.mode = Mode::Normal;
-            }
+        self.input = format!("snapshots-{cfg}.csv");
+        self.input_cursor = self.input.chars().count();
+        self.mode = Mode::Input(InputKind::ExportPath);
+    }
+
+    // Exports what the table shows: the filtered list, in the table's columns
+    fn finish_export(&mut self, path: &str) {
+        self.mode = Mode::Normal;
+        self.input.clear();
+        if path.is_empty() {
+            self.status = "Cancelled".into();
+            return;
+        }
+        let path = export::expand_home(path);
+        self.status = match export::write(&self.filtered_snaps, &self.columns, &path) {
+            Ok(_) => format!(
+                "Exported {} snapshot(s) to {}",
+                self.filtered_snaps.len(),
+                path.display()
+            ),
+            Err(e) => format!("Export failed: {e:#}"),
+        };
+    }
+
+    fn start_around(&mut self) {
+        if self.selected_config_name().is_none() {
+            self.status = "Select a config first".into();
+            return;
+        }
+        self.input.clear();
+        self.input_cursor = 0;
+        self.mode = Mode::Input(InputKind::AroundCommand);
+    }
+
+    fn finish_around_command(&mut self, command: &str) {
+        if command.is_empty() {
+            self.mode = Mode::Normal;
+            self.status = "Cancelled".into();
+            return;
+        }
+        self.input.clear();
+        self.input_cursor = 0;
+        self.mode = Mode::Input(InputKind::AroundDescription(command.to_string()));
+    }
+
+    fn finish_around_description(&mut self, command: &str, desc: &str) {
+        if self.selected_config_name().is_none() {
+            self.status = "Select a config first".into();
+            return;
         }
+        let desc = if desc.is_empty() { command } else { desc };
+        self.input.clear();
+        self.mode = Mode::Normal;
+        let cfg = self.selected_config_name().unwrap_or("").to_string();
+        let description = desc.to_string();
+        self.spawn_job(
+            PendingOp::AroundPre {
+                command: command.to_string(),
+                description: description.clone(),
+            },
+            format!("Pre snapshot for `{command}`"),
+            move |runner, _| {
+                let pre = Snapper::create_pre(&cfg, &description, runner)?;
+                // The command itself is never run in a dry run
+                if runner.preview.is_some() {
+                    Snapper::create_post(&cfg, pre, &description, runner)?;
+                }
+                Ok(pre.to_string())
+            },
+        );
+    }
+
+    /// Called by the main loop once the command has run; takes the post snapshot and
+    /// opens the status of the pair.
+    pub fn finish_around(&mut self, req: AroundCommand, result: Result<std::process::ExitStatus>) {
+        let outcome = match &result {
+            Ok(status) if status.success() => "succeeded".to_string(),
+            Ok(status) => match status.code() {
+                Some(code) => format!("exited with {code}"),
+                None => "was killed by a signal".to_string(),
+            },
+            Err(e) => format!("could not start: {e}"),
+        };
+        // Like `snapper create --command`, the post snapshot is taken whatever the outcome
+        let AroundCommand {
+            config,
+            pre,
+            command,
+            description,
+        } = req;
+        let cfg = config.clone();
+        self.spawn_job_for(
+            config,
+            PendingOp::AroundPost {
+                pre,
+                command: command.clone(),
+                outcome,
+            },
+            format!("Post snapshot for `{command}`"),
+            move |runner, _| {
+                Snapper::create_post(&cfg, pre, &description, runner).map(|post| post.to_string())
+            },
+        );
+    }
+
+    fn finish_create(&mut self, desc: &str) {
+        let Some(cfg) = self.selected_config_name().map(str::to_string) else {
+            self.status = "Select a config first".into();
+            return;
+        };
+        self.mode = Mode::Normal;
+        self.input.clear();
+        let desc = if desc.is_empty() {
+            "Created via snapper-tui".to_string()
+        } else {
+            desc.to_string()
+        };
+        self.spawn_job(
+            PendingOp::Create,
+            "Create snapshot".to_string(),
+            move |runner, _| Snapper::create(&cfg, &desc, runner).map(|id| id.to_string()),
+        );
     }
 
     fn start_edit(&mut self) {
@@ -853,24 +1611,19 @@ impl App {
     }
 
     fn finish_edit(&mut self, id: u64, desc: &str) {
-        let Some(cfg) = self.selected_config_name() else {
+        let Some(cfg) = self.selected_config_name().map(str::to_string) else {
             self.status = "Select a config first".into();
             return;
         };
-        match Snapper::modify(cfg, id, desc, self.use_sudo) {
-            Ok(_) => {
-                self.status = format!("Edited snapshot #{}", id);
-                self.mode = Mode::Normal;
-                self.input.clear();
-                self.input_cursor = 0;
-                self.snaps_cache.clear();
-                self.load_snapshots_for_selected();
-            }
-            Err(e) => {
-                self.status = format!("Edit failed: {e}");
-                self.mode = Mode::Normal;
-            }
-        }
+        self.mode = Mode::Normal;
+        self.input.clear();
+        self.input_cursor = 0;
+        let desc = desc.to_string();
+        self.spawn_job(
+            PendingOp::Modify { id },
+            format!("Edit #{id}"),
+            move |runner, _| Snapper::modify(&cfg, id, &desc, runner).map(|_| String::new()),
+        );
     }
 
     fn start_delete_confirm(&mut self) {
@@ -878,25 +1631,85 @@ impl App {
             self.status = "Select a snapshot to delete".into();
             return;
         };
-        let Some(s) = self.filtered_snaps.get(sidx) else {
+        // Marked snapshots take precedence over the cursor
+        let ids: Vec<u64> = if self.marked.is_empty() {
+            match self.filtered_snaps.get(sidx) {
+                Some(s) => vec![s.id],
+                None => return,
+            }
+        } else {
+            self.marked.iter().copied().collect()
+        };
+        self.start_delete_plan(&ids);
+    }
+
+    fn start_delete_plan(&mut self, ids: &[u64]) {
+        let Some(cfg) = self.selected_config_name().map(str::to_string) else {
+            self.status = "Select a config first".into();
             return;
         };
-        self.mode = Mode::ConfirmDelete(s.id);
+        let plan = Snapper::plan_delete(&cfg, &self.snapshots, ids);
+        if plan.ids.is_empty() {
+            self.mode = Mode::Normal;
+            self.status = format!("Refusing to delete: {}", plan.skipped.join("; "));
+            return;
+        }
+        self.mode = Mode::ConfirmDelete(plan);
     }
 
-    fn on_delete_confirmed(&mut self, id: u64) {
-        let Some(cfg) = self.selected_config_name() else {
+    fn on_delete_confirmed(&mut self, ids: Vec<u64>) {
+        let Some(cfg) = self.selected_config_name().map(str::to_string) else {
             self.status = "Select a config first".into();
             return;
         };
-        match Snapper::delete(cfg, id, self.use_sudo) {
-            Ok(_) => {
-                self.status = format!("Deleted snapshot #{}", id);
-                self.snaps_cache.clear();
-                self.load_snapshots_for_selected();
-            }
-            Err(e) => self.status = format!("Delete failed: {e}"),
+        let label = ids
+            .iter()
+            .map(|id| format!("#{id}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let numbers = ids.clone();
+        self.spawn_job(
+            PendingOp::Delete { ids },
+            format!("Delete {label}"),
+            move |runner, _| Snapper::delete(&cfg, &numbers, runner).map(|_| String::new()),
+        );
+    }
+
+    fn toggle_mark(&mut self) {
+        let Some(id) = self.selected_snapshot_id() else {
+            return;
+        };
+        if !self.marked.remove(&id) {
+            self.marked.insert(id);
         }
+        self.mark_anchor = Some(id);
+        self.on_down();
+        self.status = format!("{} marked (d deletes them)", self.marked.len());
+    }
+
+    // Mark everything between the last toggled snapshot and the cursor, as shown
+    fn mark_range(&mut self) {
+        let (Some(anchor), Some(id)) = (self.mark_anchor, self.selected_snapshot_id()) else {
+            self.status = "Mark a snapshot with Space first".into();
+            return;
+        };
+        let pos = |id| self.filtered_snaps.iter().position(|s| s.id == id);
+        let (Some(a), Some(b)) = (pos(anchor), pos(id)) else {
+            return;
+        };
+        let range: Vec<u64> = self.filtered_snaps[a.min(b)..=a.max(b)]
+            .iter()
+            .map(|s| s.id)
+            .collect();
+        self.marked.extend(range);
+        self.status = format!("{} marked (d deletes them)", self.marked.len());
+    }
+
+    fn selected_snapshot_id(&self) -> Option<u64> {
+        self.snaps_state
+            .selected
+            .and_then(|i| self.filtered_snaps.get(i))
+            .map(|s| s.id)
     }
 
     fn on_enter(&mut self) {
@@ -912,310 +1725,449 @@ impl App {
             return;
         };
         // Choose a sensible comparison range: previous -> current if possible, otherwise 0..current
-        let (from, to) = if sidx > 0 {
-            let prev = self.filtered_snaps.get(sidx - 1).map(|p| p.id).unwrap_or(0);
-            (prev, s.id)
-        } else {
-            (0, s.id)
-        };
-        // Spawn background job to fetch status so UI can show a loading indicator
-        let (tx, rx) = mpsc::channel::<Result<String>>();
+        let (from, to) = (self.predecessor_id(s.id), s.id);
+        let cfg = cfg.to_string();
+        self.start_status(&cfg, from, to);
+    }
+
+    // Stream status into a job; the details view stays usable while lines arrive
+    fn start_status(&mut self, cfg: &str, from: u64, to: u64) {
         let cfg_owned = cfg.to_string();
-        let use_sudo = self.use_sudo;
+        if let Some(id) = self.spawn_job(
+            PendingOp::Status { from, to },
+            format!("Status {}..{}", from, to),
+            move |runner, sink| {
+                Snapper::snapshot_status(&cfg_owned, from, to, runner, sink).map(|_| String::new())
+            },
+        ) {
+            self.open_job_output(id, false);
+        }
+    }
+
+    fn on_job_finished(&mut self, id: u64) {
+        let Some(job) = self.jobs.get_mut(id) else {
+            return;
+        };
+        let op = job.op.clone();
+        let label = job.label.clone();
+        let state = job.state.clone();
+        let late_cancel = job.cancel_requested;
+        let preview = job.preview.clone().filter(|_| job.op.is_mutating());
+        let needs_auth = job.needs_auth;
+        let text = job.result_text.take().unwrap_or_default();
+        if matches!(op, PendingOp::Status { .. } | PendingOp::Diff { .. }) && job.output.is_empty()
+        {
+            job.output.push_line("(no output)");
+        }
+        // Only a real, successful rollback moves the wizard on; anything else closes it
+        let wizard_job = self
+            .rollback
+            .as_ref()
+            .is_some_and(|w| w.step == Step::Running(id));
+        if wizard_job && (state != JobState::Succeeded || preview.is_some()) {
+            self.rollback = None;
+            if matches!(self.mode, Mode::Rollback) {
+                self.mode = Mode::Normal;
+            }
+        }
+        let viewing = self.details_job == Some(id) && matches!(self.mode, Mode::Details);
+        // Results only pop up when the user isn't busy with something else
+        let idle = matches!(self.mode, Mode::Normal) || viewing;
+        match state {
+            JobState::Running => {}
+            JobState::Failed(e) => {
+                self.status = match &op {
+                    PendingOp::AroundPost {
+                        pre,
+                        command,
+                        outcome,
+                    } => format!(
+                        "`{command}` {outcome}, but the post snapshot for #{pre} failed: {e}"
+                    ),
+                    _ => format!("{label} failed: {e}"),
+                };
+                if needs_auth {
+                    self.prompt_sudo_password(AuthRetry::Job(id));
+                }
+            }
+            JobState::Interrupted(why) => {
+                // Report what the interrupted operation may have left behind
+                let mut report = vec![
+                    String::new(),
+                    format!("{} {why}.", op.name()),
+                    format!("State: {}.", op.cancel_outcome()),
+                ];
+                if op.is_dangerous() {
+                    report.push(
+                        "The snapshot list was refreshed; review it before continuing.".into(),
+                    );
+                }
+                if op.is_mutating() {
+                    self.snaps_cache.clear();
+                    self.refresh_all();
+                }
+                if let Some(job) = self.jobs.get_mut(id) {
+                    for line in &report {
+                        job.output.push_line(line);
+                    }
+                }
+                if idle {
+                    self.open_job_output(id, true);
+                }
+                self.status = format!("#{id} {label}: {why}");
+            }
+            JobState::Succeeded if preview.is_some() => {
+                let lines = preview.map(|p| p.lines()).unwrap_or_default();
+                if let Some(job) = self.jobs.get_mut(id) {
+                    for line in preview_text(&lines).lines() {
+                        job.output.push_line(line);
+                    }
+                }
+                if idle {
+                    self.open_job_output(id, false);
+                    self.details_title = format!("Dry run · {label}");
+                }
+                self.status = format!("Dry run: {label} would run {} command(s)", lines.len());
+            }
+            JobState::Succeeded => {
+                self.on_job_succeeded(id, op, &text, idle, viewing);
+                if late_cancel {
+                    self.status = format!(
+                        "#{id} {label} completed before cancel took effect. {}",
+                        self.status
+                    );
+                }
+            }
+        }
+    }
+
+    fn on_job_succeeded(&mut self, id: u64, op: PendingOp, text: &str, idle: bool, viewing: bool) {
+        let first = text.lines().next().unwrap_or("");
+        let config = self
+            .jobs
+            .get(id)
+            .map(|j| j.config.clone())
+            .unwrap_or_default();
+        match op {
+            PendingOp::Create => {
+                self.status = format!("Created snapshot #{} in {config}", text.trim());
+                self.snaps_cache.clear();
+                self.load_snapshots_for_selected();
+            }
+            PendingOp::Modify { id: snap } => {
+                self.status = format!("Edited snapshot #{snap}");
+                self.snaps_cache.clear();
+                self.load_snapshots_for_selected();
+            }
+            PendingOp::Delete { ids } => {
+                for snap in &ids {
+                    self.marked.remove(snap);
+                }
+                let label = ids
+                    .iter()
+                    .map(|id| format!("#{id}"))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                self.status = format!("Deleted snapshot {label}");
+                self.snaps_cache.clear();
+                self.load_snapshots_for_selected();
+            }
+            PendingOp::AroundPre {
+                command,
+                description,
+            } => {
+                let Ok(pre) = text.trim().parse() else {
+                    return;
+                };
+                // The main loop hands over the terminal as soon as this is set
+                self.status = format!("Created pre snapshot #{pre}; running `{command}`…");
+                self.run_around = Some(AroundCommand {
+                    config,
+                    pre,
+                    command,
+                    description,
+                });
+            }
+            PendingOp::AroundPost {
+                pre,
+                command,
+                outcome,
+            } => {
+                self.snaps_cache.clear();
+                self.load_snapshots_for_selected();
+                let post = text.trim();
+                if let Ok(post) = post.parse() {
+                    self.start_status(&config, pre, post);
+                }
+                self.status = format!("`{command}` {outcome}; snapshots #{pre}..#{post}");
+            }
+            PendingOp::Status { from, to } | PendingOp::Diff { from, to } => {
+                if !viewing {
+                    self.status = format!("#{id} {} {from}..{to} finished (J to view)", op.name());
+                }
+            }
+            PendingOp::Cleanup { algorithm } => {
+                // cleanup deletes snapshots; reload the list in the background
+                self.snaps_cache.clear();
+                self.refresh_all();
+                if idle {
+                    self.open_job_output(id, false);
+                } else {
+                    self.status = format!("#{id} cleanup {algorithm} finished (J to view)");
+                }
+            }
+            PendingOp::LimineSync { id: snap, name } => {
+                if idle {
+                    self.open_job_output(id, false);
+                } else {
+                    self.status = format!("#{id} synced #{snap} of {name} to Limine (J to view)");
+                }
+            }
+            PendingOp::ViewConfig => {
+                if idle {
+                    self.open_job_output(id, false);
+                } else {
+                    self.status = format!("#{id} config loaded (J to view)");
+                }
+            }
+            PendingOp::Mount { id: snap } => {
+                self.status = if first.trim().is_empty() {
+                    format!("Mounted #{}", snap)
+                } else {
+                    format!("Mounted #{}: {}", snap, first)
+                };
+            }
+            PendingOp::Umount { id: snap } => {
+                self.status = if first.trim().is_empty() {
+                    format!("Unmounted #{}", snap)
+                } else {
+                    format!("Unmounted #{}: {}", snap, first)
+                };
+            }
+            PendingOp::SetupQuota => {
+                self.status = if first.trim().is_empty() {
+                    "Quota setup completed".into()
+                } else {
+                    format!("Quota: {}", first)
+                };
+                self.snaps_cache.clear();
+                self.refresh_all();
+            }
+            PendingOp::Rollback { id: snap } => {
+                let outcome = rollback::parse_output(text);
+                self.status = match (outcome.new_default, outcome.backup) {
+                    (Some(new), Some(backup)) => format!(
+                        "Rolled back to #{snap}: new default #{new}, backup of the old system #{backup}. Reboot to use it"
+                    ),
+                    (Some(new), None) => format!("Rolled back to #{snap}: new default #{new}. Reboot to use it"),
+                    _ if first.trim().is_empty() => format!("Rollback to #{} completed", snap),
+                    _ => format!("Rollback #{}: {}", snap, first),
+                };
+                if let Some(w) = self
+                    .rollback
+                    .as_mut()
+                    .filter(|w| w.step == Step::Running(id))
+                {
+                    w.step = Step::Done(outcome);
+                    if idle || matches!(self.mode, Mode::Rollback) {
+                        self.mode = Mode::Rollback;
+                    }
+                }
+                self.snaps_cache.clear();
+                self.refresh_all();
+            }
+            PendingOp::SetConfig => {
+                self.status = if first.trim().is_empty() {
+                    "Config updated".into()
+                } else {
+                    format!("Set-config: {}", first)
+                };
+                // config change may influence listing; keep conservative
+                self.snaps_cache.clear();
+            }
+            PendingOp::GetConfigForEdit => {
+                if !idle {
+                    self.status = "Config loaded while busy; press g again to edit".into();
+                    return;
+                }
+                self.cfg_fields = parse_config_fields(text);
+                self.cfg_field_idx = if self.cfg_fields.is_empty() {
+                    None
+                } else {
+                    Some(0)
+                };
+                self.mode = Mode::ConfigForm;
+                self.status.clear();
+            }
+        }
+    }
+
+    /// sudo needs a password: ask for it, then run `retry` once credentials are cached.
+    fn prompt_sudo_password(&mut self, retry: AuthRetry) {
+        if !self.escalation.can_prompt() {
+            self.status = format!("{} ({})", self.status, self.escalation.auth_hint());
+            return;
+        }
+        // Don't pile prompts on top of other dialogs or a check already in flight
+        if self.auth_rx.is_some() || !matches!(self.mode, Mode::Normal | Mode::Details) {
+            self.status = format!("{} (sudo needs a password)", self.status);
+            return;
+        }
+        self.auth_retry = Some(retry);
+        self.input.clear();
+        self.input_cursor = 0;
+        self.mode = Mode::Input(InputKind::SudoPassword);
+    }
+
+    fn finish_sudo_password(&mut self) {
+        let password = std::mem::take(&mut self.input);
+        self.input_cursor = 0;
+        self.mode = Mode::Normal;
+        self.status = "Authenticating with sudo…".into();
+        let (tx, rx) = mpsc::channel();
         thread::spawn(move || {
-            let res = Snapper::snapshot_status(&cfg_owned, from, to, use_sudo);
-            let _ = tx.send(res);
+            let _ = tx.send(system::sudo_authenticate(&password));
         });
-        self.status_rx = Some(rx);
-        self.pending = Some(PendingOp::Status { from, to });
-        self.loading_message = format!("Fetching status {}..{}", from, to);
-        self.details_title = format!("Status {}..{}", from, to);
-        self.details_text.clear();
-        self.details_lines = 0;
-        self.details_scroll = 0;
-        self.mode = Mode::Loading;
-        self.status.clear();
+        self.auth_rx = Some(rx);
+    }
+
+    fn on_sudo_auth(&mut self, res: Result<()>) {
+        if let Err(e) = res {
+            self.auth_failures += 1;
+            self.status = format!(
+                "sudo authentication failed ({} failed attempt{}): {e}",
+                self.auth_failures,
+                if self.auth_failures == 1 { "" } else { "s" }
+            );
+            // Retrying is pointless when the account may not use sudo at all
+            let msg = e.to_string();
+            if msg.contains("sudoers") || msg.contains("not allowed") {
+                self.auth_retry = None;
+            } else if let Some(retry) = self.auth_retry.take() {
+                self.prompt_sudo_password(retry);
+            }
+            return;
+        }
+        self.auth_failures = 0;
+        self.status = "sudo: authenticated".into();
+        match self.auth_retry.take() {
+            Some(AuthRetry::Job(id)) => {
+                let Some(op) = self.jobs.get(id).map(|j| j.op.clone()) else {
+                    return;
+                };
+                let runner = self.runner_for(op.name());
+                if let Some(new_id) = self.jobs.retry(id, runner) {
+                    if self.details_job == Some(id) {
+                        let follow = matches!(op, PendingOp::Diff { .. });
+                        self.open_job_output(new_id, follow);
+                    }
+                    self.status = format!("sudo: authenticated · retrying as #{new_id}");
+                }
+            }
+            Some(AuthRetry::Refresh) => {
+                self.snaps_cache.clear();
+                self.refresh_all();
+            }
+            None => {}
+        }
+    }
+
+    fn close_details(&mut self) {
+        if let Some(job) = self.details_job.and_then(|id| self.jobs.get(id)) {
+            if job.is_running() {
+                self.status = format!(
+                    "#{} {} continues in the background (J for jobs)",
+                    job.id, job.label
+                );
+            }
+        }
+        self.vi.details_marks.clear();
+        self.mode = self.details_return.take().unwrap_or(Mode::Normal);
     }
 
     pub fn on_tick(&mut self) {
         // advance animations (throttle)
         self.tick = self.tick.wrapping_add(1);
-        // check background status work
-        if let Some(rx) = &self.status_rx {
+        // collect background job output and completions
+        for id in self.jobs.poll() {
+            self.on_job_finished(id);
+        }
+        if let Some(rx) = &self.auth_rx {
             match rx.try_recv() {
-                Ok(Ok(text)) => {
-                    match self.pending.clone() {
-                        Some(PendingOp::Status { from, to }) => {
-                            self.details_title = format!("Status {}..{}", from, to);
-                            self.details_lines = text.lines().count() as u16;
-                            self.details_text = text;
-                            self.details_scroll = 0;
-                            self.mode = Mode::Details;
-                        }
-                        Some(PendingOp::Diff { from, to }) => {
-                            self.details_title = format!("Diff {}..{}", from, to);
-                            self.details_lines = text.lines().count() as u16;
-                            self.details_text = text;
-                            self.details_scroll = 0;
-                            self.mode = Mode::Details;
-                        }
-                        Some(PendingOp::Cleanup { algorithm }) => {
-                            self.details_title = format!("Cleanup: {}", algorithm);
-                            self.details_lines = text.lines().count() as u16;
-                            self.details_text = text;
-                            self.details_scroll = 0;
-                            self.mode = Mode::Details;
-                        }
-                        Some(PendingOp::Mount { id }) => {
-                            self.status = if text.trim().is_empty() {
-                                format!("Mounted #{}", id)
-                            } else {
-                                format!("Mounted #{}: {}", id, text.lines().next().unwrap_or(""))
-                            };
-                            self.mode = Mode::Normal;
-                        }
-                        Some(PendingOp::Umount { id }) => {
-                            self.status = if text.trim().is_empty() {
-                                format!("Unmounted #{}", id)
-                            } else {
-                                format!("Unmounted #{}: {}", id, text.lines().next().unwrap_or(""))
-                            };
-                            self.mode = Mode::Normal;
-                        }
-                        Some(PendingOp::SetupQuota) => {
-                            self.status = if text.trim().is_empty() {
-                                "Quota setup completed".into()
-                            } else {
-                                format!("Quota: {}", text.lines().next().unwrap_or(""))
-                            };
-                            self.mode = Mode::Normal;
-                            self.snaps_cache.clear();
-                            self.refresh_all();
-                        }
-                        Some(PendingOp::Rollback { id }) => {
-                            self.status = if text.trim().is_empty() {
-                                format!("Rollback to #{} completed", id)
-                            } else {
-                                format!("Rollback #{}: {}", id, text.lines().next().unwrap_or(""))
-                            };
-                            self.mode = Mode::Normal;
-                            self.snaps_cache.clear();
-                            self.refresh_all();
-                        }
-                        Some(PendingOp::LimineSync { id, name }) => {
-                            self.details_title =
-                                format!("Limine sync for {} (#{}): result", name, id);
-                            self.details_lines = text.lines().count() as u16;
-                            self.details_text = text;
-                            self.details_scroll = 0;
-                            self.mode = Mode::Details;
-                        }
-                        Some(PendingOp::SetConfig) => {
-                            self.status = if text.trim().is_empty() {
-                                "Config updated".into()
-                            } else {
-                                format!("Set-config: {}", text.lines().next().unwrap_or(""))
-                            };
-                            self.mode = Mode::Normal;
-                            // config change may influence listing; keep conservative
-                            self.snaps_cache.clear();
-                        }
-                        Some(PendingOp::GetConfigForEdit) => {
-                            // Build form fields from get-config; accept multiple formats
-                            // Formats seen: 'Key | Value', 'key=value', 'Key: Value', 'Key<TAB>Value', or aligned with 2+ spaces
-                            self.cfg_fields.clear();
-                            for line in text.lines() {
-                                let raw = line.trim();
-                                if raw.is_empty() {
-                                    continue;
-                                }
-                                let lower = raw.to_ascii_lowercase();
-                                if raw.starts_with('#')
-                                    || lower.starts_with("key")
-                                    || lower.starts_with("config")
-                                {
-                                    continue;
-                                }
-                                if raw.chars().all(|c| {
-                                    c == '-'
-                                        || c == '+'
-                                        || c == '|'
-                                        || c == '┼'
-                                        || c == '─'
-                                        || c.is_whitespace()
-                                }) {
-                                    continue;
-                                }
-                                // Normalize various vertical bars
-                                let t = raw.replace(['│', '┃', '┆', '¦'], "|");
-
-                                let mut key: Option<String> = None;
-                                let mut val: Option<String> = None;
-
-                                // 1) Table with '|'
-                                if key.is_none() && t.contains('|') {
-                                    let mut it = t.splitn(2, '|');
-                                    let k = it.next().unwrap_or("").trim();
-                                    let v = it.next().unwrap_or("").trim();
-                                    if !k.is_empty() {
-                                        key = Some(k.to_string());
-                                        val = Some(v.to_string());
-                                    }
-                                }
-                                // 2) key=value
-                                if key.is_none() && t.contains('=') {
-                                    if let Some((k, v)) = t.split_once('=') {
-                                        key = Some(k.trim().trim_end_matches(':').to_string());
-                                        val = Some(v.trim().to_string());
-                                    }
-                                }
-                                // 3) key: value
-                                if key.is_none() && t.contains(':') {
-                                    if let Some((k, v)) = t.split_once(':') {
-                                        // avoid capturing leading 'Config: ...' lines
-                                        if !k.trim().eq_ignore_ascii_case("config") {
-                                            key = Some(k.trim().to_string());
-                                            val = Some(v.trim().to_string());
-                                        }
-                                    }
-                                }
-                                // 4) key<TAB>value
-                                if key.is_none() && t.contains('\t') {
-                                    let mut it = t.splitn(2, '\t');
-                                    let k = it.next().unwrap_or("").trim();
-                                    let v = it.next().unwrap_or("").trim();
-                                    if !k.is_empty() {
-                                        key = Some(k.to_string());
-                                        val = Some(v.to_string());
-                                    }
-                                }
-                                // 5) key  value  (2+ spaces as delimiter)
-                                if key.is_none() {
-                                    let bytes = t.as_bytes();
-                                    let mut i = 0usize;
-                                    while i + 1 < bytes.len() {
-                                        if bytes[i] == b' ' && bytes[i + 1] == b' ' {
-                                            // advance to end of this run of spaces
-                                            let mut j = i + 2;
-                                            while j < bytes.len() && bytes[j] == b' ' {
-                                                j += 1;
-                                            }
-                                            let k = t[..i].trim();
-                                            let v = t[j..].trim();
-                                            if !k.is_empty() && !v.is_empty() {
-                                                key = Some(k.to_string());
-                                                val = Some(v.to_string());
-                                            }
-                                            break;
-                                        }
-                                        i += 1;
-                                    }
-                                }
-
-                                if let (Some(mut k), Some(v)) = (key, val) {
-                                    if k.ends_with(':') {
-                                        k.pop();
-                                    }
-                                    if !k.is_empty() {
-                                        self.cfg_fields.push(ConfigField {
-                                            key: k,
-                                            value: v.clone(),
-                                            original: v,
-                                            modified: false,
-                                        });
-                                    }
-                                }
-                            }
-                            self.cfg_field_idx = if self.cfg_fields.is_empty() {
-                                None
-                            } else {
-                                Some(0)
-                            };
-                            self.mode = Mode::ConfigForm;
-                            self.status.clear();
-                        }
-                        None => {
-                            self.mode = Mode::Normal;
-                        }
-                    }
-                    self.status_rx = None;
-                    self.pending = None;
+                Ok(res) => {
+                    self.auth_rx = None;
+                    self.on_sudo_auth(res);
                 }
-                Ok(Err(e)) => {
-                    self.status = format!("Operation failed: {e}");
-                    self.mode = Mode::Normal;
-                    self.status_rx = None;
-                    self.pending = None;
-                }
-                Err(mpsc::TryRecvError::Empty) => { /* still loading */ }
+                Err(mpsc::TryRecvError::Empty) => {}
                 Err(mpsc::TryRecvError::Disconnected) => {
-                    self.status = "Status failed (disconnected)".into();
-                    self.mode = Mode::Normal;
-                    self.status_rx = None;
-                    self.pending = None;
+                    self.auth_rx = None;
+                    self.auth_retry = None;
+                    self.status = "sudo authentication failed (disconnected)".into();
                 }
             }
         }
+        if let Some(rx) = &self.rollback_probe_rx {
+            match rx.try_recv() {
+                Ok((target, probe)) => {
+                    self.rollback_probe_rx = None;
+                    if let Some(w) = self.rollback.as_mut().filter(|w| w.target.id == target) {
+                        w.probe = Some(probe);
+                    }
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => self.rollback_probe_rx = None,
+            }
+        }
         // Debounced spawn for lightweight userdata summary (after ~200ms of stability)
         if let (Some(sched_at), Some(cfg), Some((from, to))) = (
             self.userdata_fetch_scheduled_at,
             self.userdata_planned_cfg.clone(),
             self.userdata_planned_from_to,
         ) {
-            // Only if nothing is inflight and delay passed
+            // Only once the delay passed; a stale fetch was already cancelled
             if self.userdata_rx.is_none() && sched_at.elapsed() >= Duration::from_millis(200) {
-                let (tx, rx) = mpsc::channel::<Result<(u64, String)>>();
-                let use_sudo = self.use_sudo;
+                let (tx, rx) = mpsc::channel::<(u64, Result<StatusSummary>)>();
+                let runner = self.runner_for("status");
+                self.userdata_handle = Some(runner.handle.clone());
                 let seq = self.userdata_summary_seq;
+                // The panel shows a few lines, so snapper can stop there; the preview pane
+                // counts every line and keeps as many as it could show
+                let (keep, count_all) = if self.show_preview {
+                    (500, true)
+                } else {
+                    (6, false)
+                };
                 thread::spawn(move || {
-                    let res = Snapper::snapshot_status(&cfg, from, to, use_sudo).map(|s| {
-                        let mut lines = s.lines();
-                        let mut out = String::new();
-                        for _ in 0..6 {
-                            if let Some(l) = lines.next() {
-                                out.push_str(l);
-                                out.push('\n');
-                            } else {
-                                break;
-                            }
-                        }
-                        (seq, out)
-                    });
-                    let _ = tx.send(res);
-                });
-                // store a mapping receiver by adapting types via an adapter thread (keep consistent with field type)
-                let (bridge_tx, bridge_rx) = mpsc::channel::<Result<String>>();
-                thread::spawn(move || {
-                    match rx.recv() {
-                        Ok(Ok((_seq, text))) => {
-                            let _ = bridge_tx.send(Ok(text));
-                        }
-                        Ok(Err(e)) => {
-                            let _ = bridge_tx.send(Err(e));
-                        }
-                        Err(_) => { /* drop */ }
-                    }
+                    let mut summary = StatusSummary::default();
+                    let res = Snapper::snapshot_status(&cfg, from, to, &runner, |l| {
+                        summary.add(l, keep);
+                        count_all || summary.total < keep
+                    })
+                    .map(|_| summary);
+                    let _ = tx.send((seq, res));
                 });
                 self.userdata_inflight_seq = Some(seq);
-                self.userdata_rx = Some(bridge_rx);
+                self.userdata_rx = Some(rx);
                 self.userdata_fetch_scheduled_at = None; // consumed
             }
         }
-        // poll lightweight userdata summary results
+        // poll the summary; a result for an earlier selection is dropped (a newer fetch is
+        // already scheduled for the current one)
         if let Some(rx) = &self.userdata_rx {
             match rx.try_recv() {
-                Ok(Ok(text)) => {
-                    // accept text only if this corresponds to the current selection context; sequence already advanced
-                    self.userdata_summary = Some(text);
-                    self.userdata_rx = None;
-                }
-                Ok(Err(e)) => {
-                    self.userdata_summary = Some(format!("Summary error: {e}"));
+                Ok((seq, res)) => {
                     self.userdata_rx = None;
+                    self.userdata_inflight_seq = None;
+                    self.userdata_handle = None;
+                    if seq == self.userdata_summary_seq {
+                        self.userdata_summary = Some(res.map_err(|e| format!("{e:#}")));
+                    }
                 }
                 Err(mpsc::TryRecvError::Empty) => {}
                 Err(mpsc::TryRecvError::Disconnected) => {
                     self.userdata_rx = None;
+                    self.userdata_inflight_seq = None;
+                    self.userdata_handle = None;
                 }
             }
         }
@@ -1230,6 +2182,8 @@ impl App {
                         // only apply to UI if the loaded config is still selected
                         if self.selected_config_name() == Some(cfg_name.as_str()) {
                             self.snapshots = snaps;
+                            let snaps = &self.snapshots;
+                            self.marked.retain(|id| snaps.iter().any(|s| s.id == *id));
                             self.apply_filter();
                             self.snaps_state.selected = if self.filtered_snaps.is_empty() {
                                 None
@@ -1238,6 +2192,9 @@ impl App {
                             };
                             self.update_selected_snapshot_meta();
                             self.status.clear();
+                            if let Some(startup) = self.startup.take() {
+                                self.apply_startup(startup);
+                            }
                         }
                     }
                     self.snaps_rx = None;
@@ -1259,6 +2216,9 @@ impl App {
                     self.snaps_state.selected = None;
                     self.snaps_rx = None;
                     self.snaps_loading_for = None;
+                    if system::auth_required(&e) {
+                        self.prompt_sudo_password(AuthRetry::Refresh);
+                    }
                 }
                 Err(mpsc::TryRecvError::Empty) => {}
                 Err(mpsc::TryRecvError::Disconnected) => {
@@ -1283,27 +2243,23 @@ impl App {
         let Some(s) = self.filtered_snaps.get(sidx) else {
             return;
         };
-        let (from, to) = if sidx > 0 {
-            (
-                self.filtered_snaps.get(sidx - 1).map(|p| p.id).unwrap_or(0),
-                s.id,
-            )
-        } else {
-            (0, s.id)
-        };
-        let (tx, rx) = mpsc::channel::<Result<String>>();
+        let (from, to) = (self.predecessor_id(s.id), s.id);
+        let cfg = cfg.to_string();
+        self.start_diff(&cfg, from, to);
+    }
+
+    fn start_diff(&mut self, cfg: &str, from: u64, to: u64) {
         let cfg_owned = cfg.to_string();
-        let use_sudo = self.use_sudo;
-        thread::spawn(move || {
-            let res = Snapper::diff(&cfg_owned, from, to, use_sudo);
-            let _ = tx.send(res);
-        });
-        self.status_rx = Some(rx);
-        self.pending = Some(PendingOp::Diff { from, to });
-        self.loading_message = format!("Fetching diff {}..{}", from, to);
-        self.details_title = format!("Diff {}..{}", from, to);
-        self.mode = Mode::Loading;
-        self.status.clear();
+        // Diffs open pinned to the end and follow new output, like before streaming
+        if let Some(id) = self.spawn_job(
+            PendingOp::Diff { from, to },
+            format!("Diff {}..{}", from, to),
+            move |runner, sink| {
+                Snapper::diff(&cfg_owned, from, to, runner, sink).map(|_| String::new())
+            },
+        ) {
+            self.open_job_output(id, true);
+        }
     }
 
     pub fn on_mount(&mut self) {
@@ -1319,18 +2275,12 @@ impl App {
             return;
         };
         let id = s.id;
-        let (tx, rx) = mpsc::channel::<Result<String>>();
         let cfg_owned = cfg.to_string();
-        let use_sudo = self.use_sudo;
-        thread::spawn(move || {
-            let res = Snapper::mount(&cfg_owned, id, use_sudo);
-            let _ = tx.send(res);
-        });
-        self.status_rx = Some(rx);
-        self.pending = Some(PendingOp::Mount { id });
-        self.loading_message = format!("Mounting #{}", id);
-        self.mode = Mode::Loading;
-        self.status.clear();
+        self.spawn_job(
+            PendingOp::Mount { id },
+            format!("Mount #{}", id),
+            move |runner, _| Snapper::mount(&cfg_owned, id, runner),
+        );
     }
 
     pub fn on_umount(&mut self) {
@@ -1346,48 +2296,139 @@ impl App {
             return;
         };
         let id = s.id;
-        let (tx, rx) = mpsc::channel::<Result<String>>();
         let cfg_owned = cfg.to_string();
-        let use_sudo = self.use_sudo;
-        thread::spawn(move || {
-            let res = Snapper::umount(&cfg_owned, id, use_sudo).map(|_| String::new());
-            let _ = tx.send(res);
-        });
-        self.status_rx = Some(rx);
-        self.pending = Some(PendingOp::Umount { id });
-        self.loading_message = format!("Unmounting #{}", id);
-        self.mode = Mode::Loading;
-        self.status.clear();
+        self.spawn_job(
+            PendingOp::Umount { id },
+            format!("Unmount #{}", id),
+            move |runner, _| Snapper::umount(&cfg_owned, id, runner).map(|_| String::new()),
+        );
     }
 
-    fn start_rollback_confirm(&mut self) {
+    fn start_rollback_wizard(&mut self) {
+        let Some(cfg) = self.selected_config_name().map(str::to_string) else {
+            self.status = "Select a config first".into();
+            return;
+        };
         let Some(sidx) = self.snaps_state.selected else {
             self.status = "Select a snapshot to rollback".into();
             return;
         };
-        let Some(s) = self.filtered_snaps.get(sidx) else {
+        let Some(s) = self.filtered_snaps.get(sidx).cloned() else {
             return;
         };
-        self.mode = Mode::ConfirmRollback(s.id);
+        self.open_rollback_wizard(&cfg, s);
     }
 
-    fn on_rollback_confirmed(&mut self, id: u64) {
-        let Some(cfg) = self.selected_config_name() else {
-            self.status = "Select a config first".into();
+    /// Snapshot the system is running from when that isn't the default, i.e. a snapshot boot.
+    pub fn booted_snapshot(&self) -> Option<(String, &Snapshot)> {
+        let current = self
+            .selected_config_name()
+            .map(|cfg| (cfg, self.snapshots.as_slice()));
+        let cached = self
+            .snaps_cache
+            .iter()
+            .map(|(cfg, (snaps, _))| (cfg.as_str(), snaps.as_slice()));
+        current.into_iter().chain(cached).find_map(|(cfg, snaps)| {
+            snaps
+                .iter()
+                .find(|s| s.active && !s.default)
+                .map(|s| (cfg.to_string(), s))
+        })
+    }
+
+    // Rolling back to the booted snapshot makes a writable copy of it the new default
+    fn make_booted_permanent(&mut self) {
+        let Some((cfg, s)) = self.booted_snapshot().map(|(c, s)| (c, s.clone())) else {
+            self.status = "Not booted from a snapshot".into();
             return;
         };
-        let (tx, rx) = mpsc::channel::<Result<String>>();
-        let cfg_owned = cfg.to_string();
-        let use_sudo = self.use_sudo;
+        self.open_rollback_wizard(&cfg, s);
+    }
+
+    fn open_rollback_wizard(&mut self, cfg: &str, s: Snapshot) {
+        let cfg = cfg.to_string();
+        // A finished wizard for the same target can be reopened to reach the bootloader step
+        if let Some(w) = &self.rollback {
+            if w.target.id == s.id && w.config == cfg && w.step != Step::Review {
+                self.mode = Mode::Rollback;
+                return;
+            }
+        }
+        let (tx, rx) = mpsc::channel();
+        let runner = self.runner_for("rollback");
+        let target = s.clone();
+        let cfg_owned = cfg.clone();
         thread::spawn(move || {
-            let res = Snapper::rollback(&cfg_owned, id, use_sudo);
-            let _ = tx.send(res);
+            let _ = tx.send((target.id, rollback::probe(&cfg_owned, &target, &runner)));
         });
-        self.status_rx = Some(rx);
-        self.pending = Some(PendingOp::Rollback { id });
-        self.loading_message = format!("Rolling back to #{}", id);
-        self.status.clear();
-        self.mode = Mode::Loading;
+        self.rollback_probe_rx = Some(rx);
+        self.rollback = Some(Wizard::new(&cfg, s));
+        self.mode = Mode::Rollback;
+    }
+
+    fn on_rollback_key(&mut self, code: KeyCode) {
+        let Some(w) = self.rollback.as_mut() else {
+            self.mode = Mode::Normal;
+            return;
+        };
+        match (&w.step, code) {
+            (Step::Review, KeyCode::Esc) => {
+                self.rollback = None;
+                self.rollback_probe_rx = None;
+                self.mode = Mode::Normal;
+                self.status = "Rollback cancelled".into();
+            }
+            (Step::Review, KeyCode::Char(c)) if c.is_ascii_digit() => w.confirm.push(c),
+            (Step::Review, KeyCode::Backspace) => {
+                w.confirm.pop();
+            }
+            (Step::Review, KeyCode::Enter) => {
+                if !w.confirmed() {
+                    self.status = format!("Type {} to confirm the rollback", w.target.id);
+                    return;
+                }
+                let id = w.target.id;
+                let cfg = w.config.clone();
+                let job = self.spawn_job_for(
+                    cfg.clone(),
+                    PendingOp::Rollback { id },
+                    format!("Rollback to #{}", id),
+                    move |runner, _| Snapper::rollback(&cfg, id, runner),
+                );
+                match (job, self.rollback.as_mut()) {
+                    (Some(job), Some(w)) => w.step = Step::Running(job),
+                    _ => {
+                        self.rollback = None;
+                        self.mode = Mode::Normal;
+                    }
+                }
+            }
+            (Step::Running(job), KeyCode::Esc) => {
+                self.status = format!("Rollback continues in the background (job #{job})");
+                self.mode = Mode::Normal;
+            }
+            (Step::Done(outcome), KeyCode::Char('b')) => {
+                let Some(snap) = outcome.new_default else {
+                    self.status = "Rollback did not report the new default snapshot".into();
+                    return;
+                };
+                if !Limine::is_installed() {
+                    self.status = "Limine not found; regenerate your bootloader entries \
+                                   (e.g. grub-mkconfig -o /boot/grub/grub.cfg)"
+                        .into();
+                    return;
+                }
+                let cfg = w.config.clone();
+                self.rollback = None;
+                self.mode = Mode::Normal;
+                self.sync_limine(&cfg, snap);
+            }
+            (Step::Done(_), KeyCode::Esc | KeyCode::Enter) => {
+                self.rollback = None;
+                self.mode = Mode::Normal;
+            }
+            _ => {}
+        }
     }
 
     fn start_cleanup_input(&mut self) {
@@ -1395,115 +2436,328 @@ impl App {
             self.status = "Select a config first".into();
             return;
         }
-        self.input.clear();
-        self.input_cursor = 0;
-        self.mode = Mode::Input(InputKind::CleanupAlgorithm);
+        self.input.clear();
+        self.input_cursor = 0;
+        self.mode = Mode::Input(InputKind::CleanupAlgorithm);
+    }
+
+    fn finish_cleanup(&mut self, alg: &str) {
+        let alg = alg.trim();
+        if alg.is_empty() {
+            self.status = "Enter cleanup algorithm (e.g., number, timeline, empty-pre-post)".into();
+            self.mode = Mode::Normal;
+            return;
+        }
+        self.mode = Mode::ConfirmCleanup(alg.to_string());
+    }
+
+    // Details search helpers (simple substring search)
+    fn start_details_search(&mut self, backward: bool) {
+        self.vi.backward = backward;
+        self.input.clear();
+        self.input_cursor = 0;
+        self.mode = Mode::Input(InputKind::DetailsSearch);
+    }
+
+    fn finish_details_search(&mut self, q: &str) {
+        let q = q.trim();
+        if q.is_empty() {
+            self.mode = Mode::Details;
+            return;
+        }
+        self.details_query = q.to_string();
+        self.mode = Mode::Details;
+        if self.vi.backward {
+            self.jump_to_match_backward(true);
+        } else {
+            self.jump_to_match_forward(true);
+        }
+    }
+
+    fn jump_to_match_forward(&mut self, wrap: bool) {
+        if self.details_query.is_empty() {
+            return;
+        }
+        let q = self.details_query.to_lowercase();
+        let start = self.details_scroll.saturating_add(1);
+        let buf = self.details_buf();
+        let found = buf
+            .find_forward(start, &q)
+            .or_else(|| wrap.then(|| buf.find_forward(0, &q)).flatten());
+        if let Some(i) = found {
+            self.details_follow = false;
+            self.details_scroll = i;
+        }
+    }
+
+    fn jump_to_match_backward(&mut self, wrap: bool) {
+        if self.details_query.is_empty() {
+            return;
+        }
+        let q = self.details_query.to_lowercase();
+        let buf = self.details_buf();
+        let found = match self.details_scroll.checked_sub(1) {
+            Some(start) => buf.find_backward(start, &q),
+            None => None,
+        }
+        .or_else(|| wrap.then(|| buf.find_backward(usize::MAX, &q)).flatten());
+        if let Some(i) = found {
+            self.details_follow = false;
+            self.details_scroll = i;
+        }
+    }
+
+    fn find_next(&mut self) {
+        self.jump_to_match_forward(true);
+    }
+    fn find_prev(&mut self) {
+        self.jump_to_match_backward(true);
+    }
+
+    /// Run a complete vi key sequence against the table or the details view.
+    fn run_vi(&mut self, cmd: vi::Command, target: vi::Target) {
+        match (target, cmd) {
+            (_, vi::Command::Prompt) => {
+                self.input.clear();
+                self.input_cursor = 0;
+                self.mode = Mode::Input(InputKind::Command(target));
+            }
+            (vi::Target::Table, cmd) => self.run_vi_table(cmd),
+            (vi::Target::Details, cmd) => self.run_vi_details(cmd),
+        }
+    }
+
+    fn run_vi_table(&mut self, cmd: vi::Command) {
+        use vi::Command as C;
+        let len = self.filtered_snaps.len();
+        let idx = self.snaps_state.selected.unwrap_or(0);
+        let half = usize::from(self.snaps_page_lines.max(2) / 2);
+        match cmd {
+            C::Down(n) => self.select_row(idx.saturating_add(n)),
+            C::Up(n) => self.select_row(idx.saturating_sub(n)),
+            C::HalfPageDown => self.select_row(idx.saturating_add(half)),
+            C::HalfPageUp => self.select_row(idx.saturating_sub(half)),
+            C::Top => self.jump_to_row(0),
+            C::Bottom => self.jump_to_row(len.saturating_sub(1)),
+            C::Line(n) => self.jump_to_row(n.saturating_sub(1)),
+            C::Search { backward } => {
+                self.vi.backward = backward;
+                self.input = self.vi.table_query.clone();
+                self.input_cursor = self.input.chars().count();
+                self.mode = Mode::Input(InputKind::TableSearch);
+            }
+            C::NextMatch { reverse, count } => {
+                let backward = self.vi.backward != reverse;
+                for _ in 0..count {
+                    if !self.table_search(backward) {
+                        break;
+                    }
+                }
+            }
+            C::SetMark(c) => {
+                let (Some(cfg), Some(id)) =
+                    (self.selected_config_name(), self.selected_snapshot_id())
+                else {
+                    return;
+                };
+                self.vi.table_marks.insert((cfg.to_string(), c), id);
+                self.status = format!("Mark '{c}' set at #{id}");
+            }
+            C::JumpMark(c) => {
+                let cfg = self.selected_config_name().unwrap_or_default().to_string();
+                let Some(&id) = self.vi.table_marks.get(&(cfg, c)) else {
+                    self.status = format!("Mark '{c}' is not set in this config");
+                    return;
+                };
+                match self.filtered_snaps.iter().position(|s| s.id == id) {
+                    Some(row) => self.jump_to_row(row),
+                    None => self.status = format!("Mark '{c}': #{id} is not shown"),
+                }
+            }
+            C::Prompt => {}
+        }
+    }
+
+    fn run_vi_details(&mut self, cmd: vi::Command) {
+        use vi::Command as C;
+        let half = (self.details_page_lines.max(2) / 2) as usize;
+        match cmd {
+            C::Down(n) => self.details_scroll = self.details_scroll.saturating_add(n),
+            C::Up(n) => {
+                self.details_follow = false;
+                self.details_scroll = self.details_scroll.saturating_sub(n);
+            }
+            C::HalfPageDown => self.details_scroll = self.details_scroll.saturating_add(half),
+            C::HalfPageUp => {
+                self.details_follow = false;
+                self.details_scroll = self.details_scroll.saturating_sub(half);
+            }
+            C::Top => self.jump_to_line(0),
+            C::Bottom => {
+                self.vi
+                    .details_marks
+                    .insert(vi::PREVIOUS, self.details_scroll);
+                // Pin to the bottom like End; draw clamps to the content
+                self.details_follow = true;
+                self.details_scroll = usize::MAX;
+            }
+            C::Line(n) => self.jump_to_line(n.saturating_sub(1)),
+            C::Search { backward } => self.start_details_search(backward),
+            C::NextMatch { reverse, count } => {
+                let backward = self.vi.backward != reverse;
+                for _ in 0..count {
+                    if backward {
+                        self.jump_to_match_backward(true);
+                    } else {
+                        self.jump_to_match_forward(true);
+                    }
+                }
+            }
+            C::SetMark(c) => {
+                let line = self
+                    .details_scroll
+                    .min(self.details_buf().len().saturating_sub(1));
+                self.vi.details_marks.insert(c, line);
+                self.status = format!("Mark '{c}' set at line {}", line + 1);
+            }
+            C::JumpMark(c) => match self.vi.details_marks.get(&c) {
+                Some(&line) => self.jump_to_line(line),
+                None => self.status = format!("Mark '{c}' is not set"),
+            },
+            C::Prompt => {}
+        }
     }
 
-    fn finish_cleanup(&mut self, alg: &str) {
-        let alg = alg.trim();
-        if alg.is_empty() {
-            self.status = "Enter cleanup algorithm (e.g., number, timeline, empty-pre-post)".into();
-            self.mode = Mode::Normal;
+    fn select_row(&mut self, row: usize) {
+        let len = self.filtered_snaps.len();
+        if len == 0 {
             return;
         }
-        self.mode = Mode::ConfirmCleanup(alg.to_string());
+        self.snaps_state.selected = Some(row.min(len - 1));
+        self.update_selected_snapshot_meta();
     }
 
-    // Details search helpers (simple substring search)
-    fn start_details_search(&mut self) {
-        self.input.clear();
-        self.input_cursor = 0;
-        self.mode = Mode::Input(InputKind::DetailsSearch);
+    // A jump (gg, G, a search, a mark) remembers where it started, for ''
+    fn jump_to_row(&mut self, row: usize) {
+        if let (Some(cfg), Some(id)) = (self.selected_config_name(), self.selected_snapshot_id()) {
+            self.vi
+                .table_marks
+                .insert((cfg.to_string(), vi::PREVIOUS), id);
+        }
+        self.select_row(row);
     }
 
-    fn finish_details_search(&mut self, q: &str) {
-        let q = q.trim();
+    fn jump_to_line(&mut self, line: usize) {
+        let from = self
+            .details_scroll
+            .min(self.details_buf().len().saturating_sub(1));
+        self.vi.details_marks.insert(vi::PREVIOUS, from);
+        self.details_follow = false;
+        self.details_scroll = line;
+    }
+
+    fn finish_table_search(&mut self, q: &str) {
+        self.mode = Mode::Normal;
         if q.is_empty() {
-            self.mode = Mode::Details;
             return;
         }
-        self.details_query = q.to_string();
-        self.mode = Mode::Details;
-        self.jump_to_match_forward(true);
+        self.vi.table_query = q.to_string();
+        self.table_search(self.vi.backward);
     }
 
-    fn jump_to_match_forward(&mut self, wrap: bool) {
-        if self.details_query.is_empty() {
-            return;
-        }
-        let q = self.details_query.to_lowercase();
-        let lines: Vec<&str> = self.details_text.lines().collect();
-        let start = (self.details_scroll as usize)
-            .saturating_add(1)
-            .min(lines.len());
-        for (i, line) in lines.iter().enumerate().skip(start) {
-            if line.to_lowercase().contains(&q) {
-                self.details_scroll = i as u16;
-                return;
-            }
+    // Next row matching the last search, wrapping around; false when nothing matches
+    fn table_search(&mut self, backward: bool) -> bool {
+        let q = self.vi.table_query.to_lowercase();
+        let len = self.filtered_snaps.len();
+        if q.is_empty() || len == 0 {
+            return false;
         }
-        if wrap {
-            for (i, line) in lines.iter().enumerate() {
-                if line.to_lowercase().contains(&q) {
-                    self.details_scroll = i as u16;
-                    return;
+        let start = self.snaps_state.selected.unwrap_or(0);
+        let found = (1..=len)
+            .map(|step| {
+                if backward {
+                    (start + len - step % len) % len
+                } else {
+                    (start + step) % len
                 }
+            })
+            .find(|&i| self.filtered_snaps[i].matches(&q));
+        match found {
+            Some(row) => {
+                self.jump_to_row(row);
+                true
+            }
+            None => {
+                self.status = format!("Pattern not found: {}", self.vi.table_query);
+                false
             }
         }
     }
 
-    fn jump_to_match_backward(&mut self, wrap: bool) {
-        if self.details_query.is_empty() {
-            return;
-        }
-        let q = self.details_query.to_lowercase();
-        let lines: Vec<&str> = self.details_text.lines().collect();
-        let start = self.details_scroll as isize - 1;
-        for i in (0..=start.max(0) as usize).rev() {
-            if lines[i].to_lowercase().contains(&q) {
-                self.details_scroll = i as u16;
-                return;
+    /// `:` commands. In the table: a keymap action name, a snapshot number, `filter <query>`,
+    /// `set novi`, `q`. In the details view: a line number or `q`.
+    fn finish_command(&mut self, target: vi::Target, line: &str) {
+        self.mode = match target {
+            vi::Target::Table => Mode::Normal,
+            vi::Target::Details => Mode::Details,
+        };
+        let (cmd, arg) = line
+            .split_once(char::is_whitespace)
+            .map(|(c, a)| (c, a.trim()))
+            .unwrap_or((line, ""));
+        match (target, cmd) {
+            (_, "") => {}
+            (vi::Target::Details, "q" | "q!" | "quit") => self.close_details(),
+            (_, "q" | "q!" | "qa" | "quit") => self.should_quit = true,
+            (_, "set") => match arg {
+                "novi" => self.run_action(Action::ViKeys),
+                "vi" => {}
+                _ => self.status = format!("Unknown option: {arg} (vi, novi)"),
+            },
+            (vi::Target::Details, n) if n.parse::<usize>().is_ok() => {
+                self.jump_to_line(n.parse::<usize>().unwrap_or(1).saturating_sub(1));
             }
-        }
-        if wrap {
-            for i in (0..lines.len()).rev() {
-                if lines[i].to_lowercase().contains(&q) {
-                    self.details_scroll = i as u16;
+            (vi::Target::Table, n) if n.parse::<u64>().is_ok() => {
+                let id = n.parse::<u64>().unwrap_or(0);
+                match self.filtered_snaps.iter().position(|s| s.id == id) {
+                    Some(row) => self.jump_to_row(row),
+                    None => self.status = format!("#{id} is not shown"),
+                }
+            }
+            (vi::Target::Table, "filter" | "f") => {
+                if let Err(e) = Query::parse(arg) {
+                    self.status = format!("Filter: {e}");
                     return;
                 }
+                self.filter_text = arg.to_string();
+                self.filter_saved = arg.to_string();
+                self.apply_filter();
+                self.persist_state();
+            }
+            (vi::Target::Table, name) => match Action::from_name(name) {
+                Some(action) => self.run_action(action),
+                None => self.status = format!("Not an action: {name} (see ? for names)"),
+            },
+            (vi::Target::Details, _) => {
+                self.status = format!("Not a command here: {line} (a line number or q)")
             }
         }
     }
 
-    fn find_next(&mut self) {
-        self.jump_to_match_forward(true);
-    }
-    fn find_prev(&mut self) {
-        self.jump_to_match_backward(true);
-    }
-
     fn on_cleanup_confirmed(&mut self, alg: &str) {
         let Some(cfg) = self.selected_config_name() else {
             self.status = "Select a config first".into();
             return;
         };
-        let (tx, rx) = mpsc::channel::<Result<String>>();
         let cfg_owned = cfg.to_string();
-        let use_sudo = self.use_sudo;
         let alg_owned = alg.to_string();
-        thread::spawn(move || {
-            let res = Snapper::cleanup(&cfg_owned, &alg_owned, use_sudo);
-            let _ = tx.send(res);
-        });
-        self.status_rx = Some(rx);
-        self.pending = Some(PendingOp::Cleanup {
-            algorithm: alg.to_string(),
-        });
-        self.loading_message = format!("Cleaning up: {}", alg);
-        self.details_title = format!("Cleanup: {}", alg);
-        self.status.clear();
-        self.mode = Mode::Loading;
+        self.spawn_job(
+            PendingOp::Cleanup {
+                algorithm: alg.to_string(),
+            },
+            format!("Cleanup: {}", alg),
+            move |runner, _| Snapper::cleanup(&cfg_owned, &alg_owned, runner),
+        );
     }
 
     fn start_config_edit(&mut self) {
@@ -1514,17 +2768,10 @@ impl App {
         };
         self.cfg_fields.clear();
         self.cfg_field_idx = None;
-        let (tx, rx) = mpsc::channel::<Result<String>>();
-        let use_sudo = self.use_sudo;
-        std::thread::spawn(move || {
-            let res = Snapper::get_config(&cfg_name, use_sudo);
-            let _ = tx.send(res);
+        let label = format!("Load config {} for edit", cfg_name);
+        self.spawn_job(PendingOp::GetConfigForEdit, label, move |runner, _| {
+            Snapper::get_config(&cfg_name, runner)
         });
-        self.status_rx = Some(rx);
-        self.pending = Some(PendingOp::GetConfigForEdit);
-        self.loading_message = String::from("Loading config for edit…");
-        self.status.clear();
-        self.mode = Mode::Loading;
     }
 
     fn view_config(&mut self) {
@@ -1532,20 +2779,12 @@ impl App {
             self.status = "Select a config first".into();
             return;
         };
-        let (tx, rx) = mpsc::channel::<Result<String>>();
         let cfg_owned = cfg_name.clone();
-        let use_sudo = self.use_sudo;
-        thread::spawn(move || {
-            let res = Snapper::get_config(&cfg_owned, use_sudo);
-            let _ = tx.send(res);
-        });
-        self.status_rx = Some(rx);
-        self.pending = Some(PendingOp::Cleanup {
-            algorithm: String::from("get-config"),
-        });
-        self.loading_message = format!("Loading config: {}", cfg_name);
-        self.details_title = format!("Config: {}", cfg_name);
-        self.mode = Mode::Loading;
+        self.spawn_job(
+            PendingOp::ViewConfig,
+            format!("Config: {}", cfg_name),
+            move |runner, _| Snapper::get_config(&cfg_owned, runner),
+        );
     }
 
     fn setup_quota(&mut self) {
@@ -1553,18 +2792,12 @@ impl App {
             self.status = "Select a config first".into();
             return;
         };
-        let (tx, rx) = mpsc::channel::<Result<String>>();
         let cfg_owned = cfg_name.clone();
-        let use_sudo = self.use_sudo;
-        thread::spawn(move || {
-            let res = Snapper::setup_quota(&cfg_owned, use_sudo);
-            let _ = tx.send(res);
-        });
-        self.status_rx = Some(rx);
-        self.pending = Some(PendingOp::SetupQuota);
-        self.loading_message = format!("Setting up quota for {}", cfg_name);
-        self.status.clear();
-        self.mode = Mode::Loading;
+        self.spawn_job(
+            PendingOp::SetupQuota,
+            format!("Setup quota for {}", cfg_name),
+            move |runner, _| Snapper::setup_quota(&cfg_owned, runner),
+        );
     }
 
     // --- Input editing helpers ---
@@ -1636,6 +2869,7 @@ impl App {
         match me.kind {
             MouseEventKind::ScrollUp => match self.mode {
                 Mode::Details => {
+                    self.details_follow = false;
                     self.details_scroll = self.details_scroll.saturating_sub(3);
                 }
                 _ => {
@@ -1659,30 +2893,512 @@ impl App {
                 }
             },
             MouseEventKind::Down(MouseButton::Left) => {
-                // No selection-by-click mapping yet; safe no-op for now
+                let Some((area, hit)) = self.hits.at(me.column, me.row) else {
+                    return;
+                };
+                let double = self
+                    .last_click
+                    .take()
+                    .is_some_and(|(at, prev)| prev == hit && at.elapsed() < DOUBLE_CLICK);
+                self.last_click = Some((Instant::now(), hit));
+                match hit {
+                    Hit::ConfigTab(idx) => self.select_config_index(idx),
+                    Hit::SnapshotRow(idx) => {
+                        self.snaps_state.selected = Some(idx);
+                        self.update_selected_snapshot_meta();
+                        if double {
+                            self.last_click = None;
+                            self.on_enter();
+                        }
+                    }
+                    Hit::Scrollbar(target) => {
+                        self.dragging = Some(target);
+                        self.scroll_to(target, area, me.row);
+                    }
+                    Hit::Key(code) => self.on_key(KeyEvent::new(code, KeyModifiers::NONE)),
+                    Hit::Modal => {}
+                }
             }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                if let Some(target) = self.dragging {
+                    if let Some(area) = self.hits.find(Hit::Scrollbar(target)) {
+                        self.scroll_to(target, area, me.row);
+                    }
+                }
+            }
+            MouseEventKind::Up(MouseButton::Left) => self.dragging = None,
             _ => {}
         }
     }
-    fn apply_filter(&mut self) {
-        let q = self.filter_text.trim().to_lowercase();
-        if q.is_empty() {
+
+    // Map a row on a scrollbar track to the same fraction of the list
+    fn scroll_to(&mut self, target: ScrollTarget, track: Rect, row: u16) {
+        let span = track.height.saturating_sub(1).max(1) as usize;
+        let offset = row.clamp(track.y, track.bottom().saturating_sub(1)) - track.y;
+        let at = |len: usize| (offset as usize * len.saturating_sub(1) + span / 2) / span;
+        match target {
+            ScrollTarget::Snapshots => {
+                let len = self.filtered_snaps.len();
+                if len > 0 {
+                    self.snaps_state.selected = Some(at(len).min(len - 1));
+                    self.update_selected_snapshot_meta();
+                }
+            }
+            ScrollTarget::Details => {
+                let max_scroll = self
+                    .details_buf()
+                    .len()
+                    .saturating_sub(self.details_page_lines as usize);
+                self.details_follow = false;
+                self.details_scroll = at(max_scroll + 1);
+            }
+        }
+    }
+    pub fn apply_filter(&mut self) {
+        if self.filter_text.trim().is_empty() {
             self.filtered_snaps = self.snapshots.clone();
         } else {
+            // A saved or --filter text that no longer parses still works as a plain search
+            let query = Query::parse_lenient(&self.filter_text);
             self.filtered_snaps = self
                 .snapshots
                 .iter()
-                .filter(|s| {
-                    s.description.to_lowercase().contains(&q)
-                        || s.date.to_lowercase().contains(&q)
-                        || s.kind.to_lowercase().contains(&q)
-                        || s.cleanup.to_lowercase().contains(&q)
-                        || s.user.to_lowercase().contains(&q)
-                        || s.id.to_string().contains(&q)
-                })
+                .filter(|s| query.matches(s))
                 .cloned()
                 .collect();
         }
+        view::sort(&mut self.filtered_snaps, self.sort);
+    }
+
+    /// What the summary and the preview compare `s` with: its pre snapshot for a post,
+    /// else the nearest lower-numbered shown snapshot.
+    pub fn compare_base(&self, s: &Snapshot) -> u64 {
+        s.pre_num.unwrap_or_else(|| self.predecessor_id(s.id))
+    }
+
+    /// Nearest lower-numbered shown snapshot, whatever the sort order; 0 if there is none.
+    pub fn predecessor_id(&self, id: u64) -> u64 {
+        self.filtered_snaps
+            .iter()
+            .map(|s| s.id)
+            .filter(|&n| n < id)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The table's current settings as a view called `name`.
+    pub fn current_view(&self, name: &str) -> View {
+        View {
+            name: name.to_string(),
+            filter: self.filter_text.clone(),
+            sort: self.sort,
+            columns: self.columns.clone(),
+            show_userdata: self.show_userdata,
+        }
+    }
+
+    // Load settings only; callers re-run the filter when a list is already shown
+    fn set_view_settings(&mut self, v: &View) {
+        self.filter_text = v.filter.clone();
+        self.filter_saved = v.filter.clone();
+        self.sort = v.sort;
+        self.columns = if v.columns.is_empty() {
+            view::default_columns()
+        } else {
+            v.columns.clone()
+        };
+        self.show_userdata = v.show_userdata;
+    }
+
+    /// Named views of the config whose settings are loaded.
+    pub fn config_views(&self) -> &[View] {
+        self.views
+            .get(&self.view_config)
+            .map(|v| v.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Selected named view of the loaded config; None means the default view.
+    pub fn active_view_name(&self) -> Option<&str> {
+        self.active_view
+            .get(&self.view_config)
+            .map(|s| s.as_str())
+            .filter(|name| self.config_views().iter().any(|v| v.name == *name))
+    }
+
+    /// True when the table no longer matches the selected named view.
+    pub fn view_modified(&self) -> bool {
+        let Some(name) = self.active_view_name() else {
+            return false;
+        };
+        let current = self.current_view(name);
+        self.config_views()
+            .iter()
+            .any(|v| v.name == name && *v != current)
+    }
+
+    // The default view has no explicit save; remember its settings before leaving it
+    fn stash_default_view(&mut self) {
+        if self.active_view_name().is_none() {
+            self.default_view = self.current_view("");
+        }
+    }
+
+    fn enter_config_view(&mut self, cfg: &str) {
+        self.stash_default_view();
+        self.view_config = cfg.to_string();
+        let v = match self.active_view_name() {
+            Some(name) => self
+                .config_views()
+                .iter()
+                .find(|v| v.name == name)
+                .cloned()
+                .unwrap_or_default(),
+            None => self.default_view.clone(),
+        };
+        self.set_view_settings(&v);
+    }
+
+    // Switch the loaded config to a named view, or back to the default view with None
+    fn select_view(&mut self, name: Option<String>) {
+        self.stash_default_view();
+        let v = match &name {
+            Some(name) => match self.config_views().iter().find(|v| v.name == *name) {
+                Some(v) => v.clone(),
+                None => return,
+            },
+            None => self.default_view.clone(),
+        };
+        match &name {
+            Some(name) => self
+                .active_view
+                .insert(self.view_config.clone(), name.clone()),
+            None => self.active_view.remove(&self.view_config),
+        };
+        self.set_view_settings(&v);
+        self.table_layout_changed();
+        self.status = format!("View: {}", name.as_deref().unwrap_or("default"));
+    }
+
+    // W: default, then each named view in turn
+    fn cycle_view(&mut self) {
+        let names: Vec<String> = self.config_views().iter().map(|v| v.name.clone()).collect();
+        if names.is_empty() {
+            self.status = "No saved views for this config (w to save one)".into();
+            return;
+        }
+        let next = match self.active_view_name() {
+            None => Some(names[0].clone()),
+            Some(cur) => {
+                let idx = names.iter().position(|n| n == cur).unwrap_or(0);
+                names.get(idx + 1).cloned()
+            }
+        };
+        self.select_view(next);
+    }
+
+    fn open_views_picker(&mut self) {
+        if self.selected_config_name().is_none() {
+            self.status = "Select a config first".into();
+            return;
+        }
+        let idx = self
+            .active_view_name()
+            .and_then(|name| self.config_views().iter().position(|v| v.name == name))
+            .map_or(0, |i| i + 1);
+        self.views_sel = Some(idx);
+        self.mode = Mode::Views;
+    }
+
+    fn on_views_key(&mut self, key: KeyEvent) {
+        let last = self.config_views().len();
+        let sel = self.views_sel.unwrap_or(0).min(last);
+        // Entry 0 is the default view
+        let selected_name = sel
+            .checked_sub(1)
+            .and_then(|i| self.config_views().get(i))
+            .map(|v| v.name.clone());
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('w') => self.mode = Mode::Normal,
+            KeyCode::Up => self.views_sel = Some(sel.saturating_sub(1)),
+            KeyCode::Down => self.views_sel = Some((sel + 1).min(last)),
+            KeyCode::Home => self.views_sel = Some(0),
+            KeyCode::End => self.views_sel = Some(last),
+            KeyCode::Enter => {
+                self.mode = Mode::Normal;
+                self.select_view(selected_name);
+            }
+            KeyCode::Char(c @ '1'..='9') => {
+                let idx = c as usize - '1' as usize;
+                if let Some(name) = self.config_views().get(idx).map(|v| v.name.clone()) {
+                    self.mode = Mode::Normal;
+                    self.select_view(Some(name));
+                }
+            }
+            // Save the table as a new view (or over one with the same name)
+            KeyCode::Char('s') | KeyCode::Char('a') => {
+                self.input = selected_name.unwrap_or_default();
+                self.input_cursor = self.input.chars().count();
+                self.mode = Mode::Input(InputKind::ViewName);
+            }
+            KeyCode::Char('d') | KeyCode::Delete => {
+                let Some(name) = selected_name else {
+                    self.status = "The default view cannot be deleted".into();
+                    return;
+                };
+                let cfg = self.view_config.clone();
+                let was_active = self.active_view_name() == Some(name.as_str());
+                if let Some(list) = self.views.get_mut(&cfg) {
+                    list.retain(|v| v.name != name);
+                    if list.is_empty() {
+                        self.views.remove(&cfg);
+                    }
+                }
+                if was_active {
+                    // The table keeps showing the deleted view's settings as the default
+                    self.active_view.remove(&cfg);
+                }
+                self.views_sel = Some(sel.min(self.config_views().len()));
+                self.persist_state();
+                self.status = format!("Deleted view '{name}'");
+            }
+            _ => {}
+        }
+    }
+
+    fn on_palette_key(&mut self, key: KeyEvent) {
+        let entries = palette::search(&self.input);
+        let last = entries.len().saturating_sub(1);
+        let sel = self.palette_sel.unwrap_or(0).min(last);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => {
+                self.input.clear();
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Up => self.palette_sel = Some(sel.saturating_sub(1)),
+            KeyCode::Down => self.palette_sel = Some((sel + 1).min(last)),
+            KeyCode::Char('p') if ctrl => self.palette_sel = Some(sel.saturating_sub(1)),
+            KeyCode::Char('n') if ctrl => self.palette_sel = Some((sel + 1).min(last)),
+            KeyCode::PageUp => self.palette_sel = Some(sel.saturating_sub(10)),
+            KeyCode::PageDown => self.palette_sel = Some((sel + 10).min(last)),
+            KeyCode::Enter => {
+                let Some(action) = entries.get(sel).map(|e| e.action) else {
+                    return;
+                };
+                // Stay open so another action can be picked
+                if let Some(reason) = self.action_unavailable(action) {
+                    self.status = format!("{}: {reason}", action.label());
+                    return;
+                }
+                self.input.clear();
+                self.mode = Mode::Normal;
+                self.run_action(action);
+            }
+            KeyCode::Backspace => {
+                self.input_backspace();
+                self.palette_sel = Some(0);
+            }
+            KeyCode::Delete => {
+                self.input_delete();
+                self.palette_sel = Some(0);
+            }
+            KeyCode::Left => self.input_move_left(),
+            KeyCode::Right => self.input_move_right(),
+            KeyCode::Home => self.input_move_home(),
+            KeyCode::End => self.input_move_end(),
+            KeyCode::Char(c) if !ctrl => {
+                self.input_insert_char(c);
+                self.palette_sel = Some(0);
+            }
+            _ => {}
+        }
+    }
+
+    // Themes that fail to load are skipped, with the reason in the status line
+    fn next_theme(&mut self) {
+        let names = theme::available();
+        let start = names.iter().position(|n| *n == self.theme_name);
+        let mut problems = Vec::new();
+        for step in 1..=names.len() {
+            let idx = match start {
+                Some(i) => (i + step) % names.len(),
+                None => step - 1,
+            };
+            let name = &names[idx];
+            match theme::load(name) {
+                Ok(t) => {
+                    self.theme = t;
+                    self.theme_name = name.clone();
+                    self.theme_saved = Some(name.clone());
+                    self.status = match problems.first() {
+                        None => format!("Theme: {name}"),
+                        Some(p) => format!("Theme: {name} (skipped {p})"),
+                    };
+                    self.persist_state();
+                    return;
+                }
+                Err(e) => problems.push(e),
+            }
+        }
+    }
+
+    fn wants_size(&self) -> bool {
+        self.columns.contains(&Column::Size) || self.sort.column == Column::Size
+    }
+
+    // Re-sort and re-filter after a sort, column or view change, keeping the selected snapshot
+    fn table_layout_changed(&mut self) {
+        let keep = self.selected_snapshot_id();
+        self.apply_filter();
+        self.snaps_state.selected = keep
+            .and_then(|id| self.filtered_snaps.iter().position(|s| s.id == id))
+            .or(if self.filtered_snaps.is_empty() {
+                None
+            } else {
+                Some(0)
+            });
+        self.update_selected_snapshot_meta();
+        self.persist_state();
+        // Sizes are only fetched while something needs them
+        if self.wants_size() && self.snapshots.iter().all(|s| s.used_space.is_none()) {
+            if let Some(cfg) = self.selected_config_name().map(|s| s.to_string()) {
+                if self.snaps_loading_for.as_deref() != Some(cfg.as_str()) {
+                    self.snaps_cache.remove(&cfg);
+                    self.load_snapshots_for_selected();
+                }
+            }
+        }
+    }
+
+    // o: sort by the next visible column, ascending
+    fn cycle_sort_column(&mut self) {
+        let idx = self.columns.iter().position(|c| *c == self.sort.column);
+        let next = match idx {
+            Some(i) => self.columns[(i + 1) % self.columns.len()],
+            None => self.columns.first().copied().unwrap_or(Column::Id),
+        };
+        self.sort = Sort {
+            column: next,
+            descending: false,
+        };
+        self.table_layout_changed();
+        self.status = format!("Sorted by {} (O reverses)", next.header());
+    }
+
+    /// Every column for the editor: shown ones in table order, then the hidden ones.
+    pub fn column_choices(&self) -> Vec<Column> {
+        let mut all = self.columns.clone();
+        all.extend(
+            Column::ALL
+                .into_iter()
+                .filter(|c| !self.columns.contains(c)),
+        );
+        all
+    }
+
+    fn on_columns_key(&mut self, key: KeyEvent) {
+        let choices = self.column_choices();
+        let last = choices.len() - 1;
+        let sel = self.columns_sel.unwrap_or(0).min(last);
+        let col = choices[sel];
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        // Shift-Up/Down or < > move the column left or right in the table
+        let step = match key.code {
+            KeyCode::Up if shift => Some(-1),
+            KeyCode::Down if shift => Some(1),
+            KeyCode::Char('<') => Some(-1),
+            KeyCode::Char('>') => Some(1),
+            _ => None,
+        };
+        if let Some(step) = step {
+            let Some(i) = self.columns.iter().position(|c| *c == col) else {
+                return;
+            };
+            let Some(j) = i
+                .checked_add_signed(step)
+                .filter(|&j| j < self.columns.len())
+            else {
+                return;
+            };
+            self.columns.swap(i, j);
+            self.columns_sel = Some(j);
+            self.table_layout_changed();
+            return;
+        }
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('L') => self.mode = Mode::Normal,
+            KeyCode::Up => self.columns_sel = Some(sel.saturating_sub(1)),
+            KeyCode::Down => self.columns_sel = Some((sel + 1).min(last)),
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                if let Some(i) = self.columns.iter().position(|c| *c == col) {
+                    if self.columns.len() == 1 {
+                        self.status = "At least one column stays visible".into();
+                        return;
+                    }
+                    self.columns.remove(i);
+                    // The hidden column moves below the shown ones
+                    self.columns_sel = Some(
+                        self.column_choices()
+                            .iter()
+                            .position(|c| *c == col)
+                            .unwrap_or(0),
+                    );
+                } else {
+                    self.columns.push(col);
+                    self.columns_sel = Some(self.columns.len() - 1);
+                }
+                self.table_layout_changed();
+            }
+            // Sort by this column; again reverses the order
+            KeyCode::Char('s') => {
+                if self.sort.column == col {
+                    self.sort.descending = !self.sort.descending;
+                } else {
+                    self.sort = Sort {
+                        column: col,
+                        descending: false,
+                    };
+                }
+                self.table_layout_changed();
+            }
+            KeyCode::Char('r') => {
+                self.columns = view::default_columns();
+                self.sort = Sort::default();
+                self.columns_sel = Some(0);
+                self.table_layout_changed();
+                self.status = "Columns and sort reset".into();
+            }
+            _ => {}
+        }
+    }
+
+    fn save_view(&mut self, name: &str) {
+        if name.is_empty() {
+            self.status = "A view needs a name".into();
+            self.mode = Mode::Views;
+            return;
+        }
+        let v = self.current_view(name);
+        let list = self.views.entry(self.view_config.clone()).or_default();
+        match list.iter_mut().find(|x| x.name == name) {
+            Some(existing) => *existing = v,
+            None => list.push(v),
+        }
+        self.active_view
+            .insert(self.view_config.clone(), name.to_string());
+        self.persist_state();
+        self.status = format!("Saved view '{name}' for {}", self.view_config);
+        self.open_views_picker();
+    }
+
+    fn select_config_index(&mut self, idx: usize) {
+        if idx >= self.configs.len() || self.configs_state.selected == Some(idx) {
+            return;
+        }
+        self.configs_state.selected = Some(idx);
+        self.load_snapshots_for_selected();
+        self.persist_state();
     }
 
     fn select_prev_config(&mut self) {
@@ -1709,25 +3425,128 @@ impl App {
     }
 }
 
-impl App {
-    // Estimate the number of visible lines in the Details modal content area (page size).
-    // This mirrors ui.rs: the Details modal is centered at ~80% height with a modal block
-    // that consumes a title line and a bottom footer line. We also reserve a row for borders.
-    // We cannot know exact theme paddings here; use conservative estimates to stay close.
-    fn estimate_details_page_lines(&self) -> u16 {
-        // Terminal height in rows
-        let (cols, rows) = terminal::size().unwrap_or((80, 24));
-        let _ = cols; // not used here, but kept for future width-aware logic
-                      // The main layout has: tabs (3), main (flex), status (1), optional userdata (7)
-                      // The Details modal overlays the whole frame and is 70% height per ui.rs.
-                      // visible_h ≈ rows * 0.70 minus modal decorations (title/footer/borders ~ 4-5 rows)
-        let modal_h = (rows * 70) / 100; // 70%
-                                         // Subtract 4 lines for title (top), footer (bottom), and borders/padding
-        let mut content_h = modal_h.saturating_sub(4);
-        // Ensure at least 3 lines to avoid zero-page
-        if content_h < 3 {
-            content_h = 3;
-        }
-        content_h
+// Build form fields from get-config; accept multiple formats
+// Formats seen: 'Key | Value', 'key=value', 'Key: Value', 'Key<TAB>Value', or aligned with 2+ spaces
+fn parse_config_fields(text: &str) -> Vec<ConfigField> {
+    let mut fields = Vec::new();
+    for line in text.lines() {
+        let raw = line.trim();
+        if raw.is_empty() {
+            continue;
+        }
+        let lower = raw.to_ascii_lowercase();
+        if raw.starts_with('#') || lower.starts_with("key") || lower.starts_with("config") {
+            continue;
+        }
+        if raw
+            .chars()
+            .all(|c| c == '-' || c == '+' || c == '|' || c == '┼' || c == '─' || c.is_whitespace())
+        {
+            continue;
+        }
+        // Normalize various vertical bars
+        let t = raw.replace(['│', '┃', '┆', '¦'], "|");
+
+        let mut key: Option<String> = None;
+        let mut val: Option<String> = None;
+
+        // 1) Table with '|'
+        if key.is_none() && t.contains('|') {
+            let mut it = t.splitn(2, '|');
+            let k = it.next().unwrap_or("").trim();
+            let v = it.next().unwrap_or("").trim();
+            if !k.is_empty() {
+                key = Some(k.to_string());
+                val = Some(v.to_string());
+            }
+        }
+        // 2) key=value
+        if key.is_none() && t.contains('=') {
+            if let Some((k, v)) = t.split_once('=') {
+                key = Some(k.trim().trim_end_matches(':').to_string());
+                val = Some(v.trim().to_string());
+            }
+        }
+        // 3) key: value
+        if key.is_none() && t.contains(':') {
+            if let Some((k, v)) = t.split_once(':') {
+                // avoid capturing leading 'Config: ...' lines
+                if !k.trim().eq_ignore_ascii_case("config") {
+                    key = Some(k.trim().to_string());
+                    val = Some(v.trim().to_string());
+                }
+            }
+        }
+        // 4) key<TAB>value
+        if key.is_none() && t.contains('\t') {
+            let mut it = t.splitn(2, '\t');
+            let k = it.next().unwrap_or("").trim();
+            let v = it.next().unwrap_or("").trim();
+            if !k.is_empty() {
+                key = Some(k.to_string());
+                val = Some(v.to_string());
+            }
+        }
+        // 5) key  value  (2+ spaces as delimiter)
+        if key.is_none() {
+            let bytes = t.as_bytes();
+            let mut i = 0usize;
+            while i + 1 < bytes.len() {
+                if bytes[i] == b' ' && bytes[i + 1] == b' ' {
+                    // advance to end of this run of spaces
+                    let mut j = i + 2;
+                    while j < bytes.len() && bytes[j] == b' ' {
+                        j += 1;
+                    }
+                    let k = t[..i].trim();
+                    let v = t[j..].trim();
+                    if !k.is_empty() && !v.is_empty() {
+                        key = Some(k.to_string());
+                        val = Some(v.to_string());
+                    }
+                    break;
+                }
+                i += 1;
+            }
+        }
+
+        if let (Some(mut k), Some(v)) = (key, val) {
+            if k.ends_with(':') {
+                k.pop();
+            }
+            if !k.is_empty() {
+                fields.push(ConfigField {
+                    key: k,
+                    value: v.clone(),
+                    original: v,
+                    modified: false,
+                });
+            }
+        }
+    }
+    fields
+}
+
+// Step a history filter through "all" and every distinct value
+fn cycle_filter(current: &Option<String>, values: Vec<String>) -> Option<String> {
+    let mut values = values;
+    values.sort();
+    values.dedup();
+    match current {
+        None => values.into_iter().next(),
+        Some(cur) => values.into_iter().skip_while(|v| v != cur).nth(1),
+    }
+}
+
+fn preview_text(lines: &[String]) -> String {
+    let mut text = String::from("Dry run: nothing was executed.\n\n");
+    if lines.is_empty() {
+        text.push_str("No state-changing commands would run.\n");
+    } else {
+        text.push_str("Would run:\n");
+        for l in lines {
+            text.push_str(&format!("  $ {l}\n"));
+        }
     }
+    text
 }
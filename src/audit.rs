@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+
+use crate::state;
+use crate::system;
+
+// Keep log lines readable; snapper errors are short, pipelines can be chatty
+const STDERR_EXCERPT_CHARS: usize = 400;
+
+// The history viewer only looks at the most recent entries
+const HISTORY_LIMIT: usize = 5000;
+
+/// One state-changing command, as written to `audit.jsonl` (one JSON object per line).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AuditEntry {
+    pub timestamp: String,
+    pub user: String,
+    pub escalation: String,
+    pub config: String,
+    pub op: String,
+    pub argv: Vec<String>,
+    // None when the command never produced an exit status (spawn failure, cancel, timeout)
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+    pub stderr: String,
+}
+
+impl AuditEntry {
+    pub fn command_line(&self) -> String {
+        system::command_line(&self.argv)
+    }
+}
+
+pub fn log_path() -> PathBuf {
+    let mut p = state::state_dir();
+    p.push("audit.jsonl");
+    p
+}
+
+pub fn current_user() -> String {
+    std::env::var("USER").unwrap_or_else(|_| {
+        // SAFETY: getuid has no preconditions and cannot fail
+        format!("uid {}", unsafe { libc::getuid() })
+    })
+}
+
+pub fn stderr_excerpt(stderr: &[u8]) -> String {
+    let text = String::from_utf8_lossy(stderr);
+    let text = text.trim();
+    if text.chars().count() <= STDERR_EXCERPT_CHARS {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(STDERR_EXCERPT_CHARS).collect();
+    format!("{cut}…")
+}
+
+/// Open the log for appending, creating it private to the user if it doesn't exist yet.
+pub fn open_log() -> Result<File> {
+    let path = log_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    OpenOptions::new()
+        .append(true)
+        .create(true)
+        .mode(0o600)
+        .open(&path)
+        .with_context(|| format!("Failed to open audit log {}", path.display()))
+}
+
+/// Append one entry. The file is only ever opened for appending and is private to the user.
+pub fn append(entry: &AuditEntry) -> Result<()> {
+    let path = log_path();
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    let mut f = open_log()?;
+    // One write per entry so concurrent jobs don't interleave lines
+    f.write_all(&line)
+        .with_context(|| format!("Failed to write audit log {}", path.display()))
+}
+
+/// Most recent entries, oldest first; unreadable lines are skipped.
+pub fn load() -> Vec<AuditEntry> {
+    let Ok(data) = fs::read_to_string(log_path()) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<AuditEntry> = data
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect();
+    if entries.len() > HISTORY_LIMIT {
+        entries.drain(..entries.len() - HISTORY_LIMIT);
+    }
+    entries
+}
@@ -0,0 +1,283 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::os::unix::fs::{FileExt, OpenOptionsExt};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use unicode_width::UnicodeWidthStr;
+
+// Details text is pre-wrapped at a fixed width so scrolling works on display lines.
+pub const WRAP_COLS: usize = 100;
+
+// Keep up to ~8 MiB of display lines in memory; beyond that, spill to a temp file.
+const SPILL_THRESHOLD_BYTES: usize = 8 * 1024 * 1024;
+
+static SPILL_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Growable store of display lines for the details view (status, diff, command output).
+/// Lines are normalized and wrapped on insert; large buffers move to a temp file and
+/// are read back on demand, so only the visible slice is ever materialized.
+#[derive(Default)]
+pub struct LineBuffer {
+    mem: Vec<String>,
+    mem_bytes: usize,
+    spill: Option<Spill>,
+}
+
+struct Spill {
+    path: PathBuf,
+    file: File,
+    // start offset of every line, plus the end offset of the file as the last element
+    offsets: Vec<u64>,
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+impl LineBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_text(text: &str) -> Self {
+        let mut b = Self::new();
+        for line in text.lines() {
+            b.push_line(line);
+        }
+        b
+    }
+
+    /// Number of display lines (after wrapping).
+    pub fn len(&self) -> usize {
+        match &self.spill {
+            Some(s) => s.offsets.len().saturating_sub(1),
+            None => self.mem.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_spilled(&self) -> bool {
+        self.spill.is_some()
+    }
+
+    /// Append one raw output line; it is normalized and wrapped into one or more display lines.
+    pub fn push_line(&mut self, raw: &str) {
+        let normalized = normalize_line(raw);
+        for piece in wrap_line(&normalized, WRAP_COLS) {
+            self.push_display(piece);
+        }
+    }
+
+    fn push_display(&mut self, line: String) {
+        if let Some(spill) = &mut self.spill {
+            // A write error drops the line rather than splitting storage across two places
+            let _ = spill.append(&line);
+            return;
+        }
+        self.mem_bytes += line.len() + 1;
+        self.mem.push(line);
+        if self.spill.is_none() && self.mem_bytes > SPILL_THRESHOLD_BYTES {
+            // Best effort: if the temp file can't be created, keep everything in memory
+            if let Ok(spill) = Spill::create(&self.mem) {
+                self.spill = Some(spill);
+                self.mem = Vec::new();
+                self.mem_bytes = 0;
+            }
+        }
+    }
+
+    pub fn line(&self, idx: usize) -> Option<String> {
+        match &self.spill {
+            Some(s) => s.read_line(idx),
+            None => self.mem.get(idx).cloned(),
+        }
+    }
+
+    /// Lines in [start, end), clamped to the buffer length.
+    pub fn slice(&self, start: usize, end: usize) -> Vec<String> {
+        let end = end.min(self.len());
+        if start >= end {
+            return Vec::new();
+        }
+        match &self.spill {
+            Some(s) => s.read_range(start, end),
+            None => self.mem[start..end].to_vec(),
+        }
+    }
+
+    /// First line at or after `start` whose lowercase form contains `needle_lower`.
+    pub fn find_forward(&self, start: usize, needle_lower: &str) -> Option<usize> {
+        if start >= self.len() {
+            return None;
+        }
+        match &self.spill {
+            Some(s) => s.find_forward(start, needle_lower),
+            None => self.mem[start..]
+                .iter()
+                .position(|l| l.to_lowercase().contains(needle_lower))
+                .map(|p| p + start),
+        }
+    }
+
+    /// Last line at or before `start` whose lowercase form contains `needle_lower`.
+    pub fn find_backward(&self, start: usize, needle_lower: &str) -> Option<usize> {
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+        let start = start.min(len - 1);
+        (0..=start).rev().find(|&i| {
+            self.line(i)
+                .map(|l| l.to_lowercase().contains(needle_lower))
+                .unwrap_or(false)
+        })
+    }
+}
+
+impl Spill {
+    fn create(lines: &[String]) -> std::io::Result<Self> {
+        let seq = SPILL_SEQ.fetch_add(1, Ordering::Relaxed);
+        let mut path = std::env::temp_dir();
+        path.push(format!("snapper-tui-{}-{}.lines", std::process::id(), seq));
+        // Diff output may contain file contents; keep the spill file private
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)?;
+        let mut spill = Spill {
+            path,
+            file,
+            offsets: vec![0],
+        };
+        for l in lines {
+            spill.append(l)?;
+        }
+        Ok(spill)
+    }
+
+    fn end(&self) -> u64 {
+        self.offsets.last().copied().unwrap_or(0)
+    }
+
+    fn append(&mut self, line: &str) -> std::io::Result<()> {
+        let mut bytes = Vec::with_capacity(line.len() + 1);
+        bytes.extend_from_slice(line.as_bytes());
+        bytes.push(b'\n');
+        let at = self.end();
+        self.file.write_all_at(&bytes, at)?;
+        self.offsets.push(at + bytes.len() as u64);
+        Ok(())
+    }
+
+    fn read_line(&self, idx: usize) -> Option<String> {
+        let start = *self.offsets.get(idx)?;
+        let end = *self.offsets.get(idx + 1)?;
+        let mut buf = vec![0u8; (end - start) as usize];
+        self.file.read_exact_at(&mut buf, start).ok()?;
+        if buf.last() == Some(&b'\n') {
+            buf.pop();
+        }
+        Some(String::from_utf8_lossy(&buf).into_owned())
+    }
+
+    fn read_range(&self, start: usize, end: usize) -> Vec<String> {
+        let (Some(&from), Some(&to)) = (self.offsets.get(start), self.offsets.get(end)) else {
+            return Vec::new();
+        };
+        let mut buf = vec![0u8; (to - from) as usize];
+        if self.file.read_exact_at(&mut buf, from).is_err() {
+            return Vec::new();
+        }
+        String::from_utf8_lossy(&buf)
+            .lines()
+            .map(|l| l.to_string())
+            .collect()
+    }
+
+    fn find_forward(&self, start: usize, needle_lower: &str) -> Option<usize> {
+        // Sequential read is much cheaper than one positioned read per line
+        let mut f = self.file.try_clone().ok()?;
+        f.seek(SeekFrom::Start(*self.offsets.get(start)?)).ok()?;
+        let mut reader = BufReader::new(f.take(self.end()));
+        let mut buf = Vec::new();
+        let mut idx = start;
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => return None,
+                Ok(_) => {
+                    if String::from_utf8_lossy(&buf)
+                        .to_lowercase()
+                        .contains(needle_lower)
+                    {
+                        return Some(idx);
+                    }
+                    idx += 1;
+                }
+            }
+        }
+    }
+}
+
+// Normalize diff/status text for stable display in a terminal:
+// - Expand tabs to 4 spaces (diffs often contain tabs)
+// - Strip carriage returns (CR) that can cause overwriting artifacts
+// - Remove ANSI escape sequences if any slipped through
+pub fn normalize_line(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut esc = false;
+    let mut ansi = false;
+    for ch in text.chars() {
+        if ansi {
+            // End ANSI on letter (rough heuristic) or 'm'
+            if ch.is_ascii_alphabetic() || ch == 'm' {
+                ansi = false;
+            }
+            continue;
+        }
+        if esc {
+            // Expect '[' then parameters...
+            if ch == '[' {
+                ansi = true;
+                esc = false;
+                continue;
+            }
+            esc = false;
+            continue;
+        }
+        match ch {
+            '\t' => out.push_str("    "),
+            '\r' => {}
+            '\u{1b}' => esc = true, // ESC
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+// Split a line so that each piece is at most `width` cells wide (unicode-aware)
+pub fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let maxw = width.max(1);
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut current_width = 0usize;
+    for ch in line.chars() {
+        let cw = UnicodeWidthStr::width(ch.encode_utf8(&mut [0; 4]));
+        if cw > 0 && current_width + cw > maxw {
+            out.push(std::mem::take(&mut current));
+            current_width = 0;
+        }
+        current.push(ch);
+        current_width += cw;
+    }
+    out.push(current);
+    out
+}
@@ -0,0 +1,724 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::json;
+use std::fs;
+use std::time::Duration;
+
+use crate::audit;
+use crate::escalation::Escalation;
+use crate::export::{self, Format};
+use crate::filter::Query;
+use crate::keymap::{keymap_paths, Keymap};
+use crate::limine::Limine;
+use crate::snapper::{Snapper, Snapshot};
+use crate::state::{self, State as PersistedState};
+use crate::system::{self, Preview, ReadOnly, Runner};
+use crate::theme;
+use crate::view::{self, Column, Sort};
+
+// Exit codes
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILED: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_AUTH: i32 = 3;
+// Read-only mode, or a delete guard without --force
+pub const EXIT_REFUSED: i32 = 4;
+
+// A delete range wider than this is almost certainly a typo
+const MAX_DELETE_SPAN: u64 = 10_000;
+
+const USAGE: &str = "Usage: snapper-tui <command> [options]
+
+Commands:
+  list                      List snapshots
+  status <a..b>             Files changed between two snapshots
+  diff <a..b>               Unified diff between two snapshots
+  create [-d <text>]        Create a snapshot and print its number
+  delete <n|a-b>...         Delete snapshots (same guards as the TUI)
+  cleanup <algorithm>       Run number, timeline or empty-pre-post cleanup
+  limine-sync <n>           Add a snapshot to the Limine boot menu
+  export                    Write the snapshot list as CSV, JSON or Markdown
+  doctor                    Check snapper, escalation, permissions and Limine
+
+Options:
+  -c, --config <name>  Snapper config (default: last used in the TUI, else root)
+  --json               Machine-readable output on stdout
+  --dry-run            Print state-changing commands instead of running them
+  --preview            Same as --dry-run (e.g. cleanup --preview)
+  --read-only          Refuse state-changing commands
+  --format csv|json|md Export format (default: from --output, else csv)
+  -o, --output <path>  Export to a file instead of stdout
+  --filter <query>     Only export snapshots matching a TUI filter query
+  --columns <a,b,...>  Export these columns: id, date, user, type, cleanup, description, size
+                       (default: all but size)
+  --sort <column>      Export sorted by this column (default: id); --reverse for descending
+  --force              Delete important snapshots and orphan post snapshots
+  --no-sudo            Run without privilege escalation
+  --state-file <path>  Read settings from this state file
+
+Exit codes: 0 ok, 1 failed, 2 usage, 3 authentication required, 4 refused";
+
+const COMMANDS: [&str; 9] = [
+    "list",
+    "status",
+    "diff",
+    "create",
+    "delete",
+    "cleanup",
+    "limine-sync",
+    "export",
+    "doctor",
+];
+
+/// A `delete` argument: one snapshot that must exist, or whichever snapshots exist in a range.
+#[derive(Debug, Clone, Copy)]
+enum IdSpec {
+    One(u64),
+    Range(u64, u64),
+}
+
+#[derive(Debug)]
+enum Command {
+    List,
+    Status(u64, u64),
+    Diff(u64, u64),
+    Create(String),
+    Delete(Vec<IdSpec>),
+    Cleanup(String),
+    LimineSync(u64),
+    Export {
+        format: Option<Format>,
+        output: Option<String>,
+        filter: Query,
+        columns: Vec<Column>,
+        sort: Sort,
+    },
+    Doctor,
+}
+
+#[derive(Debug)]
+struct Invocation {
+    command: Command,
+    config: Option<String>,
+    json: bool,
+    dry_run: bool,
+    read_only: bool,
+    force: bool,
+    no_sudo: bool,
+}
+
+/// Whether `arg` names a subcommand, i.e. the run is headless.
+pub fn is_command(arg: &str) -> bool {
+    COMMANDS.contains(&arg)
+}
+
+/// Run a subcommand and return the process exit code.
+pub fn main(args: &[String]) -> i32 {
+    // Die quietly like other CLI tools when piped into `head` instead of panicking
+    // SAFETY: restoring the default disposition has no preconditions
+    unsafe {
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+    }
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{USAGE}");
+        return EXIT_OK;
+    }
+    let inv = match parse(args) {
+        Ok(inv) => inv,
+        Err(msg) => {
+            eprintln!("snapper-tui: {msg}\n\n{USAGE}");
+            return EXIT_USAGE;
+        }
+    };
+    match run(&inv) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("snapper-tui: {e:#}");
+            if system::auth_required(&e) {
+                eprintln!(
+                    "hint: authenticate first (e.g. 'sudo -v') or pick another escalation method"
+                );
+                EXIT_AUTH
+            } else if e.chain().any(|c| c.is::<ReadOnly>()) {
+                EXIT_REFUSED
+            } else {
+                EXIT_FAILED
+            }
+        }
+    }
+}
+
+fn parse(args: &[String]) -> Result<Invocation, String> {
+    let mut config = None;
+    let mut json = false;
+    let mut dry_run = false;
+    let mut read_only = false;
+    let mut force = false;
+    let mut no_sudo = false;
+    let mut description = None;
+    let mut format = None;
+    let mut output = None;
+    let mut filter = Query::default();
+    let mut columns = view::default_columns();
+    let mut sort = Sort::default();
+    let mut positional: Vec<&str> = Vec::new();
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "-c" | "--config" => {
+                config = Some(it.next().ok_or("--config needs a value")?.clone());
+            }
+            "-d" | "--description" => {
+                description = Some(it.next().ok_or("--description needs a value")?.clone());
+            }
+            "--format" => {
+                let name = it.next().ok_or("--format needs a value")?;
+                format = Some(
+                    Format::parse(name)
+                        .ok_or_else(|| format!("unknown format '{name}' (csv, json or md)"))?,
+                );
+            }
+            "-o" | "--output" => {
+                output = Some(it.next().ok_or("--output needs a value")?.clone());
+            }
+            "--filter" => {
+                let text = it.next().ok_or("--filter needs a value")?;
+                filter = Query::parse(text).map_err(|e| format!("--filter: {e}"))?;
+            }
+            "--columns" => {
+                let list = it.next().ok_or("--columns needs a value")?;
+                columns = list
+                    .split(',')
+                    .map(|key| parse_column(key.trim()))
+                    .collect::<Result<_, _>>()?;
+            }
+            "--sort" => sort.column = parse_column(it.next().ok_or("--sort needs a value")?)?,
+            "--reverse" => sort.descending = true,
+            "--json" => json = true,
+            "--dry-run" | "--preview" => dry_run = true,
+            "--read-only" => read_only = true,
+            "--force" => force = true,
+            "--no-sudo" => no_sudo = true,
+            "--state-file" => {
+                state::set_state_file(it.next().ok_or("--state-file needs a value")?.into());
+            }
+            other if other.starts_with("--config=") => {
+                config = Some(other["--config=".len()..].to_string());
+            }
+            other if other.starts_with('-') && other.len() > 1 => {
+                return Err(format!("unknown option '{other}'"));
+            }
+            other => positional.push(other),
+        }
+    }
+    let Some((name, rest)) = positional.split_first() else {
+        return Err("missing command".into());
+    };
+    let command = match (*name, rest) {
+        ("list", []) => Command::List,
+        ("status", [range]) => {
+            let (a, b) = parse_range(range)?;
+            Command::Status(a, b)
+        }
+        ("diff", [range]) => {
+            let (a, b) = parse_range(range)?;
+            Command::Diff(a, b)
+        }
+        ("create", []) => {
+            Command::Create(description.unwrap_or_else(|| "Created via snapper-tui".to_string()))
+        }
+        ("delete", ids) if !ids.is_empty() => {
+            let mut specs = Vec::new();
+            for spec in ids {
+                let Ok((a, b)) = parse_range(spec) else {
+                    specs.push(IdSpec::One(parse_number(spec)?));
+                    continue;
+                };
+                let (a, b) = (a.min(b), a.max(b));
+                if b - a >= MAX_DELETE_SPAN {
+                    return Err(format!(
+                        "range '{spec}' spans more than {MAX_DELETE_SPAN} snapshots"
+                    ));
+                }
+                specs.push(IdSpec::Range(a, b));
+            }
+            Command::Delete(specs)
+        }
+        ("cleanup", [algorithm]) => match *algorithm {
+            "number" | "timeline" | "empty-pre-post" => Command::Cleanup(algorithm.to_string()),
+            other => return Err(format!("unknown cleanup algorithm '{other}'")),
+        },
+        ("limine-sync", [id]) => Command::LimineSync(parse_number(id)?),
+        ("export", []) => Command::Export {
+            format,
+            output,
+            filter,
+            columns,
+            sort,
+        },
+        ("doctor", []) => Command::Doctor,
+        (name, _) if is_command(name) => {
+            return Err(format!("wrong arguments for '{name}'"));
+        }
+        (name, _) => return Err(format!("unknown command '{name}'")),
+    };
+    Ok(Invocation {
+        command,
+        config,
+        json,
+        dry_run,
+        read_only,
+        force,
+        no_sudo,
+    })
+}
+
+fn parse_number(s: &str) -> Result<u64, String> {
+    s.parse()
+        .map_err(|_| format!("'{s}' is not a snapshot number"))
+}
+
+fn parse_column(key: &str) -> Result<Column, String> {
+    Column::from_key(key).ok_or_else(|| {
+        let keys: Vec<&str> = Column::ALL.iter().map(|c| c.key()).collect();
+        format!("unknown column '{key}' ({})", keys.join(", "))
+    })
+}
+
+/// Snapshot ids named by `specs`, deduplicated in order. Ranges keep only the snapshots that
+/// exist, so gaps left by cleanup are fine; ranges with no snapshot at all are returned apart.
+fn resolve_ids(specs: &[IdSpec], snaps: &[Snapshot]) -> (Vec<u64>, Vec<(u64, u64)>) {
+    let mut ids: Vec<u64> = Vec::new();
+    let mut empty = Vec::new();
+    for spec in specs {
+        match *spec {
+            IdSpec::One(id) => {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+            IdSpec::Range(a, b) => {
+                let mut matched = false;
+                for s in snaps.iter().filter(|s| (a..=b).contains(&s.id)) {
+                    matched = true;
+                    if !ids.contains(&s.id) {
+                        ids.push(s.id);
+                    }
+                }
+                if !matched {
+                    empty.push((a, b));
+                }
+            }
+        }
+    }
+    (ids, empty)
+}
+
+/// "3..7" (snapper status/diff style) or "3-7" (snapper delete style).
+pub fn parse_range(s: &str) -> Result<(u64, u64), String> {
+    let (a, b) = s
+        .split_once("..")
+        .or_else(|| s.split_once('-'))
+        .ok_or_else(|| format!("'{s}' is not a range like 3..7"))?;
+    Ok((parse_number(a)?, parse_number(b)?))
+}
+
+// Same escalation, timeouts and read-only setting as the TUI
+fn runner(state: &PersistedState, inv: &Invocation, op: &str) -> Runner {
+    let escalation = if !inv.no_sudo && state.escalation.is_available() {
+        state.escalation
+    } else {
+        Escalation::Off
+    };
+    let timeout = state
+        .op_timeouts
+        .get(op)
+        .filter(|secs| **secs > 0)
+        .map(|secs| Duration::from_secs(*secs));
+    let mut runner = Runner::new(escalation, timeout);
+    runner.read_only = state.read_only || inv.read_only;
+    if inv.dry_run {
+        runner.preview = Some(Preview::default());
+    }
+    runner
+}
+
+fn resolve_config(state: &PersistedState, inv: &Invocation) -> Result<String> {
+    if let Some(cfg) = &inv.config {
+        if !Snapper::config_exists(cfg) {
+            anyhow::bail!("Unknown config '{cfg}' (not found in /etc/snapper/configs)");
+        }
+        return Ok(cfg.clone());
+    }
+    let configs = Snapper::available_configs_fs();
+    let last = state.last_config.as_ref().filter(|c| configs.contains(c));
+    last.cloned()
+        .or_else(|| configs.iter().find(|c| *c == "root").cloned())
+        .or_else(|| (configs.len() == 1).then(|| configs[0].clone()))
+        .context("No config given and no default found; use --config")
+}
+
+fn run(inv: &Invocation) -> Result<i32> {
+    let state = PersistedState::load();
+    if let Command::Doctor = inv.command {
+        return Ok(doctor(&state, inv));
+    }
+    let cfg = resolve_config(&state, inv)?;
+    match &inv.command {
+        Command::List => {
+            let snaps = Snapper::list_snapshots(&cfg, &runner(&state, inv, "list"))?;
+            if inv.json {
+                print_json(&snaps);
+            } else {
+                for s in &snaps {
+                    let marker = match (s.active, s.default) {
+                        (true, true) => "*",
+                        (true, false) => "-",
+                        (false, true) => "+",
+                        (false, false) => "",
+                    };
+                    println!(
+                        "{:<7} {:<6} {:<28} {:<8} {:<14} {}",
+                        format!("{}{marker}", s.id),
+                        s.kind,
+                        s.date,
+                        s.user,
+                        s.cleanup,
+                        s.description
+                    );
+                }
+            }
+        }
+        Command::Status(from, to) => {
+            let mut lines = Vec::new();
+            Snapper::snapshot_status(&cfg, *from, *to, &runner(&state, inv, "status"), |l| {
+                lines.push(l);
+                true
+            })?;
+            if inv.json {
+                let entries: Vec<_> = lines
+                    .iter()
+                    .filter_map(|l| {
+                        let (flags, path) = l.split_once(' ')?;
+                        Some(json!({ "status": flags, "path": path.trim_start() }))
+                    })
+                    .collect();
+                print_json(&entries);
+            } else {
+                lines.iter().for_each(|l| println!("{l}"));
+            }
+        }
+        Command::Diff(from, to) => {
+            let mut text = String::new();
+            Snapper::diff(&cfg, *from, *to, &runner(&state, inv, "diff"), |l| {
+                text.push_str(&l);
+                text.push('\n');
+                true
+            })?;
+            if inv.json {
+                print_json(&json!({ "config": cfg, "from": from, "to": to, "diff": text }));
+            } else {
+                print!("{text}");
+            }
+        }
+        Command::Create(description) => {
+            let runner = runner(&state, inv, "create");
+            let id = Snapper::create(&cfg, description, &runner)?;
+            if !print_preview(inv, &runner) {
+                if inv.json {
+                    print_json(&json!({ "config": cfg, "id": id }));
+                } else {
+                    println!("{id}");
+                }
+            }
+        }
+        Command::Delete(ids) => {
+            let runner = runner(&state, inv, "delete");
+            let snaps = Snapper::list_snapshots(&cfg, &self::runner(&state, inv, "list"))?;
+            let (ids, empty) = resolve_ids(ids, &snaps);
+            for (a, b) in &empty {
+                eprintln!("refused: range {a}-{b} matches no snapshots");
+            }
+            let plan = Snapper::plan_delete(&cfg, &snaps, &ids);
+            for reason in &plan.skipped {
+                eprintln!("refused: {reason}");
+            }
+            for warning in &plan.warnings {
+                eprintln!("warning: {warning}");
+            }
+            if !plan.important.is_empty() {
+                let list: Vec<String> = plan.important.iter().map(|i| format!("#{i}")).collect();
+                eprintln!("warning: {} marked important=yes", list.join(" "));
+            }
+            let needs_force = !plan.warnings.is_empty() || !plan.important.is_empty();
+            if !plan.skipped.is_empty() || !empty.is_empty() || (needs_force && !inv.force) {
+                if needs_force && !inv.force {
+                    eprintln!("nothing deleted; pass --force to delete anyway");
+                } else {
+                    eprintln!("nothing deleted");
+                }
+                return Ok(EXIT_REFUSED);
+            }
+            Snapper::delete(&cfg, &plan.ids, &runner)?;
+            if !print_preview(inv, &runner) {
+                if inv.json {
+                    print_json(&json!({ "config": cfg, "deleted": plan.ids }));
+                } else {
+                    let list: Vec<String> = plan.ids.iter().map(u64::to_string).collect();
+                    println!("deleted {}", list.join(" "));
+                }
+            }
+        }
+        Command::Cleanup(algorithm) => {
+            let runner = runner(&state, inv, "cleanup");
+            let out = Snapper::cleanup(&cfg, algorithm, &runner)?;
+            if !print_preview(inv, &runner) {
+                if inv.json {
+                    print_json(&json!({ "config": cfg, "algorithm": algorithm, "output": out }));
+                } else {
+                    print!("{out}");
+                }
+            }
+        }
+        Command::LimineSync(id) => {
+            let runner = runner(&state, inv, "limine-sync");
+            let name = format!("{cfg}-{id}");
+            let log = Limine::sync_snapshot_to_limine(&cfg, *id, &name, &runner)?;
+            if !print_preview(inv, &runner) {
+                if inv.json {
+                    print_json(&json!({ "config": cfg, "id": id, "log": log }));
+                } else {
+                    print!("{log}");
+                }
+            }
+        }
+        Command::Export {
+            format,
+            output,
+            filter,
+            columns,
+            sort,
+        } => {
+            let runner = runner(&state, inv, "list");
+            let mut snaps = Snapper::list_snapshots(&cfg, &runner)?;
+            // Sizes need quota; without it the column stays empty, as in the TUI
+            if columns.contains(&Column::Size) || sort.column == Column::Size {
+                if let Ok(sizes) = Snapper::used_space(&cfg, &runner) {
+                    for s in snaps.iter_mut() {
+                        s.used_space = sizes.get(&s.id).copied();
+                    }
+                }
+            }
+            let mut snaps: Vec<_> = snaps.into_iter().filter(|s| filter.matches(s)).collect();
+            view::sort(&mut snaps, *sort);
+            let path = output.as_deref().map(export::expand_home);
+            // --json is an alias for --format json
+            let format = format
+                .or(inv.json.then_some(Format::Json))
+                .or_else(|| path.as_deref().and_then(Format::from_path))
+                .unwrap_or(Format::Csv);
+            let text = export::render(&snaps, columns, format);
+            match path {
+                Some(path) => fs::write(&path, text)
+                    .with_context(|| format!("Failed to write {}", path.display()))?,
+                None => print!("{text}"),
+            }
+        }
+        Command::Doctor => unreachable!("handled above"),
+    }
+    Ok(EXIT_OK)
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(text) => println!("{text}"),
+        Err(e) => eprintln!("snapper-tui: failed to encode JSON: {e}"),
+    }
+}
+
+// In a dry run, print what would have run instead of the result; true if it did
+fn print_preview(inv: &Invocation, runner: &Runner) -> bool {
+    let Some(preview) = &runner.preview else {
+        return false;
+    };
+    let lines = preview.lines();
+    if inv.json {
+        print_json(&json!({ "dry_run": true, "commands": lines }));
+    } else {
+        lines.iter().for_each(|l| println!("{l}"));
+    }
+    true
+}
+
+#[derive(Serialize)]
+struct Check {
+    check: &'static str,
+    // "ok", "warn" or "fail"
+    status: &'static str,
+    detail: String,
+}
+
+fn doctor(state: &PersistedState, inv: &Invocation) -> i32 {
+    let mut checks = Vec::new();
+    let mut add = |check, status, detail: String| {
+        checks.push(Check {
+            check,
+            status,
+            detail,
+        })
+    };
+
+    if system::has_cmd("snapper") {
+        add("snapper", "ok", "installed".into());
+    } else {
+        add("snapper", "fail", "snapper is not in PATH".into());
+    }
+
+    let configs = Snapper::available_configs_fs();
+    if configs.is_empty() {
+        add(
+            "configs",
+            "fail",
+            "no configs in /etc/snapper/configs (create one with 'snapper create-config')".into(),
+        );
+    } else {
+        add("configs", "ok", configs.join(", "));
+    }
+
+    let runner = runner(state, inv, "list");
+    let method = state.escalation;
+    if !method.is_available() {
+        add(
+            "escalation",
+            "fail",
+            format!("{} is configured but not installed", method.name()),
+        );
+    } else if method.is_on() {
+        match system::run("true", &[], &runner) {
+            Ok(out) if out.status.success() => add(
+                "escalation",
+                "ok",
+                format!("{} works without a prompt", method.name()),
+            ),
+            Err(e) if system::auth_required(&e) => add(
+                "escalation",
+                "warn",
+                format!(
+                    "{} needs a password; authenticate before scripted use",
+                    method.name()
+                ),
+            ),
+            Ok(_) | Err(_) => add(
+                "escalation",
+                "fail",
+                format!("{} could not run a command", method.name()),
+            ),
+        }
+    } else {
+        add(
+            "escalation",
+            "ok",
+            "off (commands run as the current user)".into(),
+        );
+    }
+
+    for cfg in &configs {
+        match Snapper::list_snapshots(cfg, &runner) {
+            Ok(snaps) => {
+                let default = snaps
+                    .iter()
+                    .find(|s| s.default)
+                    .map(|s| format!(", default #{}", s.id));
+                add(
+                    "list",
+                    "ok",
+                    format!(
+                        "{cfg}: {} snapshots{}",
+                        snaps.len(),
+                        default.unwrap_or_default()
+                    ),
+                )
+            }
+            Err(e) if system::auth_required(&e) => {
+                add("list", "warn", format!("{cfg}: authentication required"))
+            }
+            Err(e) => add("list", "fail", format!("{cfg}: {e:#}")),
+        }
+    }
+
+    if system::has_cmd("btrfs") {
+        add("btrfs", "ok", "installed".into());
+    } else {
+        add(
+            "btrfs",
+            "warn",
+            "btrfs-progs missing; default/active detection and the rollback wizard are limited"
+                .into(),
+        );
+    }
+
+    match audit::open_log() {
+        Ok(_) => add("audit-log", "ok", audit::log_path().display().to_string()),
+        Err(e) => add("audit-log", "fail", format!("{e:#}")),
+    }
+
+    if Limine::is_installed() {
+        let sync = if Limine::has_sync() {
+            "limine-snapper-sync found"
+        } else {
+            "entries are written directly (limine-snapper-sync not found)"
+        };
+        add("limine", "ok", sync.into());
+    } else {
+        add("limine", "ok", "not installed (sync not needed)".into());
+    }
+
+    let (_, problems) = Keymap::load(state.vi_keys);
+    match keymap_paths().into_iter().find(|p| p.exists()) {
+        None => add("keymap", "ok", "default keys".into()),
+        Some(path) if problems.is_empty() => add("keymap", "ok", path.display().to_string()),
+        Some(_) => add(
+            "keymap",
+            "warn",
+            format!("ignored, default keys in use: {}", problems.join("; ")),
+        ),
+    }
+
+    // Every theme file, so a broken one is reported before it is picked
+    let name = state.theme.clone().unwrap_or_else(|| "dark".into());
+    let broken: Vec<String> = theme::available()
+        .iter()
+        .filter_map(|n| theme::load(n).err())
+        .collect();
+    if !broken.is_empty() {
+        add("theme", "warn", broken.join("; "));
+    } else if theme::no_color_requested() {
+        add(
+            "theme",
+            "ok",
+            format!("{name} (NO_COLOR is set: no-color in use)"),
+        );
+    } else if theme::load(&name).is_err() {
+        add(
+            "theme",
+            "warn",
+            format!("unknown theme '{name}', dark in use"),
+        );
+    } else {
+        add("theme", "ok", name);
+    }
+
+    let failed = checks.iter().any(|c| c.status == "fail");
+    if inv.json {
+        print_json(&checks);
+    } else {
+        for c in &checks {
+            println!("{:<5} {:<11} {}", c.status, c.check, c.detail);
+        }
+    }
+    if failed {
+        EXIT_FAILED
+    } else {
+        EXIT_OK
+    }
+}
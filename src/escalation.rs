@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use crate::system;
+
+/// How privileged commands are run. Every backend is invoked non-interactively so a
+/// missing credential fails fast instead of prompting on the TUI's terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Escalation {
+    #[default]
+    Off,
+    Sudo,
+    Doas,
+    Run0,
+    Pkexec,
+}
+
+impl Escalation {
+    pub const ALL: [Escalation; 5] = [
+        Escalation::Off,
+        Escalation::Sudo,
+        Escalation::Doas,
+        Escalation::Run0,
+        Escalation::Pkexec,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Escalation::Off => "off",
+            Escalation::Sudo => "sudo",
+            Escalation::Doas => "doas",
+            Escalation::Run0 => "run0",
+            Escalation::Pkexec => "pkexec",
+        }
+    }
+
+    pub fn is_on(self) -> bool {
+        self != Escalation::Off
+    }
+
+    /// Program plus flags to put in front of a privileged command.
+    pub fn prefix(self) -> &'static [&'static str] {
+        match self {
+            Escalation::Off => &[],
+            Escalation::Sudo => &["sudo", "-n"],
+            Escalation::Doas => &["doas", "-n"],
+            Escalation::Run0 => &["run0", "--no-ask-password"],
+            // Without this pkexec starts a text agent on our terminal
+            Escalation::Pkexec => &["pkexec", "--disable-internal-agent"],
+        }
+    }
+
+    /// `prefix` for embedding in `sh -c` strings: "sudo -n " or "" when off.
+    pub fn shell_prefix(self) -> String {
+        let p = self.prefix();
+        if p.is_empty() {
+            String::new()
+        } else {
+            format!("{} ", p.join(" "))
+        }
+    }
+
+    pub fn is_available(self) -> bool {
+        match self.prefix().first() {
+            Some(prog) => system::has_cmd(prog),
+            None => true,
+        }
+    }
+
+    /// Methods installed on this machine, in cycling order; `Off` is always present.
+    pub fn available() -> Vec<Escalation> {
+        Self::ALL.into_iter().filter(|e| e.is_available()).collect()
+    }
+
+    /// The next installed method after `self`, wrapping around to `Off`.
+    pub fn next_available(self) -> Escalation {
+        let avail = Self::available();
+        let pos = avail.iter().position(|e| *e == self);
+        match pos {
+            Some(i) => avail[(i + 1) % avail.len()],
+            None => avail.first().copied().unwrap_or_default(),
+        }
+    }
+
+    /// Only sudo can be fed a password from the app (`sudo -S -v`).
+    pub fn can_prompt(self) -> bool {
+        self == Escalation::Sudo
+    }
+
+    /// Hint for when this method refused to run without authentication.
+    pub fn auth_hint(self) -> &'static str {
+        match self {
+            Escalation::Off | Escalation::Sudo => "",
+            Escalation::Doas => "doas needs a password; add a 'persist' or 'nopass' rule",
+            Escalation::Run0 | Escalation::Pkexec => {
+                "polkit needs authentication; run a polkit agent or add a rule"
+            }
+        }
+    }
+}
+
+// Messages each backend prints when it would have to ask for credentials
+const AUTH_MARKERS: [&str; 4] = [
+    "a password is required",        // sudo -n
+    "authorization required",        // doas -n
+    "authentication required",       // run0 / systemd-run, opendoas
+    "no authentication agent found", // pkexec --disable-internal-agent
+];
+
+/// Whether stderr says escalation failed only for lack of credentials.
+pub fn needs_auth(stderr: &str) -> bool {
+    let lower = stderr.to_ascii_lowercase();
+    AUTH_MARKERS.iter().any(|m| lower.contains(m))
+}
@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::snapper::Snapshot;
+use crate::view::Column;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            "md" | "markdown" => Some(Format::Markdown),
+            _ => None,
+        }
+    }
+
+    /// Format implied by a file extension (.csv, .json, .md).
+    pub fn from_path(path: &Path) -> Option<Format> {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .and_then(Format::parse)
+    }
+}
+
+fn field(s: &Snapshot, column: Column) -> String {
+    match column {
+        Column::Id => s.id.to_string(),
+        Column::Date => s.date.clone(),
+        Column::User => s.user.clone(),
+        Column::Type => s.kind.clone(),
+        Column::Cleanup => s.cleanup.clone(),
+        Column::Description => s.description.clone(),
+        Column::Size => s.used_space.map(|n| n.to_string()).unwrap_or_default(),
+    }
+}
+
+// RFC 4180: quote fields with separators, quotes or line breaks
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+/// Render `snaps` with the given columns; JSON uses the column keys, the others the headers.
+pub fn render(snaps: &[Snapshot], columns: &[Column], format: Format) -> String {
+    let mut out = String::new();
+    match format {
+        Format::Csv => {
+            let header: Vec<String> = columns.iter().map(|c| csv_field(c.header())).collect();
+            out.push_str(&header.join(","));
+            out.push('\n');
+            for s in snaps {
+                let row: Vec<String> = columns.iter().map(|c| csv_field(&field(s, *c))).collect();
+                out.push_str(&row.join(","));
+                out.push('\n');
+            }
+        }
+        Format::Json => {
+            let rows: Vec<Value> = snaps
+                .iter()
+                .map(|s| {
+                    let mut obj = Map::new();
+                    for column in columns {
+                        let value = match column {
+                            Column::Id => Value::from(s.id),
+                            Column::Size => s.used_space.map(Value::from).unwrap_or(Value::Null),
+                            _ => Value::from(field(s, *column)),
+                        };
+                        obj.insert(column.key().to_string(), value);
+                    }
+                    Value::Object(obj)
+                })
+                .collect();
+            out = serde_json::to_string_pretty(&rows).unwrap_or_default();
+            out.push('\n');
+        }
+        Format::Markdown => {
+            let header: Vec<String> = columns.iter().map(|c| markdown_cell(c.header())).collect();
+            out.push_str(&format!("| {} |\n", header.join(" | ")));
+            out.push_str(&format!("|{}\n", "---|".repeat(columns.len())));
+            for s in snaps {
+                let row: Vec<String> = columns
+                    .iter()
+                    .map(|c| markdown_cell(&field(s, *c)))
+                    .collect();
+                out.push_str(&format!("| {} |\n", row.join(" | ")));
+            }
+        }
+    }
+    out
+}
+
+/// Expand a leading `~/` the way a shell would.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Write `snaps` to `path`, picking the format from its extension.
+pub fn write(snaps: &[Snapshot], columns: &[Column], path: &Path) -> Result<Format> {
+    let format = Format::from_path(path).with_context(|| {
+        format!(
+            "Unknown export format for {} (use .csv, .json or .md)",
+            path.display()
+        )
+    })?;
+    fs::write(path, render(snaps, columns, format))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(format)
+}
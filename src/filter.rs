@@ -0,0 +1,469 @@
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+use regex::Regex;
+
+use crate::snapper::{self, Snapshot};
+
+/// Why a filter query could not be parsed; shown inline in the filter prompt.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{0}")]
+pub struct ParseError(pub String);
+
+const FIELDS: &str = "type, cleanup, user, id, date, age, desc, userdata";
+
+/// A parsed filter such as `type:pre -user:root (age<7d OR userdata:important=yes)`.
+///
+/// Terms are ANDed unless joined with `OR` (or `|`). `-`, `!` or `NOT` negates a term or a
+/// parenthesized group. A term without a field matches any column, like the old plain filter.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    // None matches everything (empty query)
+    expr: Option<Expr>,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Term(Term),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Cmp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+impl Cmp {
+    fn test<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Cmp::Lt => left < right,
+            Cmp::Le => left <= right,
+            Cmp::Gt => left > right,
+            Cmp::Ge => left >= right,
+            Cmp::Eq => left == right,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    // lowercase substring of any column
+    Text(String),
+    Kind(String),
+    Cleanup(String),
+    User(String),
+    // inclusive range
+    Id(u64, u64),
+    // day_only compares calendar days, so date:2025-01-01 means that whole day
+    Date {
+        cmp: Cmp,
+        at: NaiveDateTime,
+        day_only: bool,
+    },
+    Age(Cmp, TimeDelta),
+    Desc(Regex),
+    Userdata(String, Option<String>),
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, ParseError> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Ok(Query::default());
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if let Some(tok) = parser.peek() {
+            return Err(ParseError(match tok {
+                Token::Close => "unmatched ')'".into(),
+                _ => format!("unexpected '{}'", tok.text()),
+            }));
+        }
+        Ok(Query { expr: Some(expr) })
+    }
+
+    /// Parse, falling back to a plain substring search when `input` isn't a valid query.
+    pub fn parse_lenient(input: &str) -> Query {
+        Query::parse(input).unwrap_or_else(|_| Query {
+            expr: Some(Expr::Term(Term::Text(input.trim().to_lowercase()))),
+        })
+    }
+
+    pub fn matches(&self, s: &Snapshot) -> bool {
+        let now = chrono::Local::now().naive_local();
+        self.expr.as_ref().is_none_or(|e| e.matches(s, now))
+    }
+}
+
+impl Expr {
+    fn matches(&self, s: &Snapshot, now: NaiveDateTime) -> bool {
+        match self {
+            Expr::And(list) => list.iter().all(|e| e.matches(s, now)),
+            Expr::Or(list) => list.iter().any(|e| e.matches(s, now)),
+            Expr::Not(e) => !e.matches(s, now),
+            Expr::Term(t) => t.matches(s, now),
+        }
+    }
+}
+
+impl Term {
+    fn matches(&self, s: &Snapshot, now: NaiveDateTime) -> bool {
+        match self {
+            Term::Text(q) => s.matches(q),
+            Term::Kind(v) => s.kind.eq_ignore_ascii_case(v),
+            Term::Cleanup(v) => s.cleanup.eq_ignore_ascii_case(v),
+            Term::User(v) => s.user.eq_ignore_ascii_case(v),
+            Term::Id(lo, hi) => (*lo..=*hi).contains(&s.id),
+            Term::Date { cmp, at, day_only } => match snapper::parse_date(&s.date) {
+                Some(d) if *day_only => cmp.test(d.date(), at.date()),
+                Some(d) => cmp.test(d, *at),
+                None => false,
+            },
+            Term::Age(cmp, limit) => {
+                snapper::parse_date(&s.date).is_some_and(|d| cmp.test(now - d, *limit))
+            }
+            Term::Desc(re) => re.is_match(&s.description),
+            Term::Userdata(key, value) => match (s.userdata_value(key), value) {
+                (Some(actual), Some(want)) => actual.eq_ignore_ascii_case(want),
+                (Some(_), None) => true,
+                (None, _) => false,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    // quoted words are always plain text, never field:value
+    Word { text: String, quoted: bool },
+}
+
+impl Token {
+    fn text(&self) -> &str {
+        match self {
+            Token::Open => "(",
+            Token::Close => ")",
+            Token::Word { text, .. } => text,
+        }
+    }
+
+    fn keyword(&self) -> Option<String> {
+        match self {
+            Token::Word {
+                text,
+                quoted: false,
+            } => Some(text.to_ascii_uppercase()),
+            _ => None,
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            _ => {
+                let mut text = String::new();
+                let mut quoted = false;
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    chars.next();
+                    if c == '"' {
+                        // "two words" as one term; a leading quote makes it plain text
+                        quoted |= text.is_empty();
+                        loop {
+                            match chars.next() {
+                                Some('"') => break,
+                                Some(c) => text.push(c),
+                                None => return Err(ParseError("unterminated quote".into())),
+                            }
+                        }
+                    } else if c == '/' && text.ends_with(':') {
+                        // desc:/regex/ may contain spaces and parentheses
+                        text.push('/');
+                        let mut closed = false;
+                        while let Some(c) = chars.next() {
+                            text.push(c);
+                            if c == '\\' {
+                                if let Some(n) = chars.next() {
+                                    text.push(n);
+                                }
+                            } else if c == '/' {
+                                closed = true;
+                                break;
+                            }
+                        }
+                        if !closed {
+                            return Err(ParseError("unterminated /regex/".into()));
+                        }
+                    } else {
+                        text.push(c);
+                    }
+                }
+                tokens.push(Token::Word { text, quoted });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let tok = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        tok
+    }
+
+    fn peek_keyword(&self) -> Option<String> {
+        self.peek().and_then(Token::keyword)
+    }
+
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        let mut list = vec![self.and()?];
+        while matches!(self.peek_keyword().as_deref(), Some("OR" | "|")) {
+            self.next();
+            list.push(self.and()?);
+        }
+        Ok(if list.len() == 1 {
+            list.remove(0)
+        } else {
+            Expr::Or(list)
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut list = vec![self.unary()?];
+        loop {
+            match self.peek_keyword().as_deref() {
+                Some("AND" | "&") => {
+                    self.next();
+                }
+                Some("OR" | "|") => break,
+                _ if matches!(self.peek(), None | Some(Token::Close)) => break,
+                _ => {}
+            }
+            list.push(self.unary()?);
+        }
+        Ok(if list.len() == 1 {
+            list.remove(0)
+        } else {
+            Expr::And(list)
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        match self.next() {
+            None => Err(ParseError("expected a term at the end".into())),
+            Some(Token::Close) => Err(ParseError("unexpected ')'".into())),
+            Some(Token::Open) => {
+                let inner = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(inner),
+                    _ => Err(ParseError("missing ')'".into())),
+                }
+            }
+            Some(tok @ Token::Word { .. }) => {
+                let Token::Word { text, quoted } = tok else {
+                    unreachable!()
+                };
+                if quoted {
+                    return Ok(Expr::Term(Term::Text(text.to_lowercase())));
+                }
+                let upper = text.to_ascii_uppercase();
+                if matches!(upper.as_str(), "AND" | "OR" | "&" | "|") {
+                    return Err(ParseError(format!("'{text}' needs a term before it")));
+                }
+                if matches!(upper.as_str(), "NOT" | "!" | "-") {
+                    return Ok(Expr::Not(Box::new(self.unary()?)));
+                }
+                if let Some(rest) = text.strip_prefix(['-', '!']) {
+                    // "-(" is tokenized as "-" then "(", handled above
+                    return Ok(Expr::Not(Box::new(Expr::Term(term(rest)?))));
+                }
+                Ok(Expr::Term(term(&text)?))
+            }
+        }
+    }
+}
+
+// Split "date>=2025-01-01" into ("date", Some(">="), "2025-01-01")
+fn split_field(word: &str) -> (&str, Option<&str>, &str) {
+    let name_len = word
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(word.len());
+    let (name, rest) = word.split_at(name_len);
+    for op in [":", ">=", "<=", ">", "<", "="] {
+        if let Some(value) = rest.strip_prefix(op) {
+            if !name.is_empty() {
+                return (name, Some(op), value);
+            }
+        }
+    }
+    (word, None, "")
+}
+
+fn cmp_of(op: &str) -> Cmp {
+    match op {
+        "<" => Cmp::Lt,
+        "<=" => Cmp::Le,
+        ">" => Cmp::Gt,
+        ">=" => Cmp::Ge,
+        _ => Cmp::Eq,
+    }
+}
+
+fn term(word: &str) -> Result<Term, ParseError> {
+    let (name, op, value) = split_field(word);
+    let Some(op) = op else {
+        return Ok(Term::Text(word.to_lowercase()));
+    };
+    let field = name.to_ascii_lowercase();
+    let colon_only = |t: Term| {
+        if op == ":" {
+            Ok(t)
+        } else {
+            Err(ParseError(format!("{field} only supports '{field}:value'")))
+        }
+    };
+    if value.is_empty() {
+        return Err(ParseError(format!("{field}{op} needs a value")));
+    }
+    match field.as_str() {
+        "type" => colon_only(Term::Kind(value.to_string())),
+        "cleanup" => colon_only(Term::Cleanup(value.to_string())),
+        "user" => colon_only(Term::User(value.to_string())),
+        "desc" | "description" => {
+            let pattern = match value.strip_prefix('/').and_then(|v| v.strip_suffix('/')) {
+                Some(re) => format!("(?i){re}"),
+                None => format!("(?i){}", regex::escape(value)),
+            };
+            // regex errors span several lines; the last one says what is wrong
+            let re = Regex::new(&pattern).map_err(|e| {
+                let e = e.to_string();
+                let reason = e
+                    .lines()
+                    .last()
+                    .unwrap_or_default()
+                    .trim_start_matches("error: ");
+                ParseError(format!("bad regex in {word}: {reason}"))
+            })?;
+            colon_only(Term::Desc(re))
+        }
+        "userdata" => colon_only(match value.split_once('=') {
+            Some((k, v)) => Term::Userdata(k.to_string(), Some(v.to_string())),
+            None => Term::Userdata(value.to_string(), None),
+        }),
+        "id" => id_term(op, value),
+        "date" => {
+            let (at, day_only) = parse_date_value(value)?;
+            Ok(Term::Date {
+                cmp: cmp_of(op),
+                at,
+                day_only,
+            })
+        }
+        "age" => {
+            if op == ":" || op == "=" {
+                return Err(ParseError("use age<7d or age>30d".into()));
+            }
+            Ok(Term::Age(cmp_of(op), parse_age(value)?))
+        }
+        _ if op == ":" => Err(ParseError(format!(
+            "unknown field '{name}' (fields: {FIELDS})"
+        ))),
+        // e.g. "a=b" is just text
+        _ => Ok(Term::Text(word.to_lowercase())),
+    }
+}
+
+fn parse_id(value: &str) -> Result<u64, ParseError> {
+    value
+        .parse()
+        .map_err(|_| ParseError(format!("'{value}' is not a snapshot number")))
+}
+
+fn id_term(op: &str, value: &str) -> Result<Term, ParseError> {
+    if op == ":" {
+        if let Some((lo, hi)) = value.split_once("..") {
+            let lo = if lo.is_empty() { 0 } else { parse_id(lo)? };
+            let hi = if hi.is_empty() {
+                u64::MAX
+            } else {
+                parse_id(hi)?
+            };
+            return Ok(Term::Id(lo, hi));
+        }
+        let n = parse_id(value)?;
+        return Ok(Term::Id(n, n));
+    }
+    let n = parse_id(value)?;
+    Ok(match cmp_of(op) {
+        Cmp::Lt => Term::Id(0, n.saturating_sub(1)),
+        Cmp::Le => Term::Id(0, n),
+        Cmp::Gt => Term::Id(n.saturating_add(1), u64::MAX),
+        Cmp::Ge => Term::Id(n, u64::MAX),
+        Cmp::Eq => Term::Id(n, n),
+    })
+}
+
+fn parse_date_value(value: &str) -> Result<(NaiveDateTime, bool), ParseError> {
+    if let Ok(day) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok((day.and_time(chrono::NaiveTime::MIN), true));
+    }
+    [
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M:%S",
+    ]
+    .iter()
+    .find_map(|f| NaiveDateTime::parse_from_str(value, f).ok())
+    .map(|at| (at, false))
+    .ok_or_else(|| ParseError(format!("'{value}' is not a date like 2025-01-31")))
+}
+
+// "90m", "12h", "7d", "2w"
+fn parse_age(value: &str) -> Result<TimeDelta, ParseError> {
+    let bad = || ParseError(format!("'{value}' is not an age like 30m, 12h, 7d or 2w"));
+    let unit = value.chars().last().ok_or_else(bad)?;
+    let n: i64 = value[..value.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| bad())?;
+    match unit {
+        'm' => TimeDelta::try_minutes(n),
+        'h' => TimeDelta::try_hours(n),
+        'd' => TimeDelta::try_days(n),
+        'w' => TimeDelta::try_weeks(n),
+        _ => None,
+    }
+    .ok_or_else(bad)
+}
@@ -0,0 +1,58 @@
+use crossterm::event::KeyCode;
+use ratatui::layout::{Position, Rect};
+
+/// What a clickable part of the last frame does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hit {
+    ConfigTab(usize),
+    // Index into the shown (filtered, sorted) snapshot list
+    SnapshotRow(usize),
+    // Scrollbar track; clicking or dragging jumps to that point of the list
+    Scrollbar(ScrollTarget),
+    // A modal button that stands in for a key press
+    Key(KeyCode),
+    // Modal body: swallows clicks so they don't reach what is drawn underneath
+    Modal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScrollTarget {
+    Snapshots,
+    Details,
+}
+
+/// Clickable regions recorded while drawing, in drawing order.
+#[derive(Debug, Clone, Default)]
+pub struct HitMap {
+    regions: Vec<(Rect, Hit)>,
+}
+
+impl HitMap {
+    pub fn add(&mut self, area: Rect, hit: Hit) {
+        if area.width > 0 && area.height > 0 {
+            self.regions.push((area, hit));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.regions.clear();
+    }
+
+    /// Topmost region under the cursor; later regions are drawn over earlier ones.
+    pub fn at(&self, column: u16, row: u16) -> Option<(Rect, Hit)> {
+        self.regions
+            .iter()
+            .rev()
+            .find(|(area, _)| area.contains(Position::new(column, row)))
+            .copied()
+    }
+
+    /// Where `hit` was drawn, e.g. to keep following a scrollbar drag.
+    pub fn find(&self, hit: Hit) -> Option<Rect> {
+        self.regions
+            .iter()
+            .rev()
+            .find(|(_, h)| *h == hit)
+            .map(|(area, _)| *area)
+    }
+}
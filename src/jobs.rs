@@ -0,0 +1,403 @@
+use anyhow::Result;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::buffer::LineBuffer;
+use crate::system::{self, Preview, ProcHandle, Runner};
+
+// Bounded so a fast producer (huge diffs) blocks instead of buffering everything in memory
+const STREAM_CHANNEL_CAP: usize = 4096;
+
+// Finished jobs kept around for the jobs panel; older ones are dropped first
+const MAX_FINISHED_JOBS: usize = 30;
+
+#[derive(Debug, Clone)]
+pub enum PendingOp {
+    Status {
+        from: u64,
+        to: u64,
+    },
+    Diff {
+        from: u64,
+        to: u64,
+    },
+    Mount {
+        id: u64,
+    },
+    Umount {
+        id: u64,
+    },
+    Rollback {
+        id: u64,
+    },
+    Cleanup {
+        algorithm: String,
+    },
+    SetupQuota,
+    SetConfig,
+    GetConfigForEdit,
+    ViewConfig,
+    LimineSync {
+        id: u64,
+        name: String,
+    },
+    Create,
+    Modify {
+        id: u64,
+    },
+    Delete {
+        ids: Vec<u64>,
+    },
+    // Pre snapshot of a command run around a pair; the command runs once it exists
+    AroundPre {
+        command: String,
+        description: String,
+    },
+    // `outcome` describes how the command ended
+    AroundPost {
+        pre: u64,
+        command: String,
+        outcome: String,
+    },
+}
+
+impl PendingOp {
+    /// Short operation name; also the key for per-operation timeouts in state.json.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PendingOp::Status { .. } => "status",
+            PendingOp::Diff { .. } => "diff",
+            PendingOp::Mount { .. } => "mount",
+            PendingOp::Umount { .. } => "umount",
+            PendingOp::Rollback { .. } => "rollback",
+            PendingOp::Cleanup { .. } => "cleanup",
+            PendingOp::SetupQuota => "setup-quota",
+            PendingOp::SetConfig => "set-config",
+            PendingOp::GetConfigForEdit | PendingOp::ViewConfig => "get-config",
+            PendingOp::LimineSync { .. } => "limine-sync",
+            PendingOp::Create | PendingOp::AroundPre { .. } | PendingOp::AroundPost { .. } => {
+                "create"
+            }
+            PendingOp::Modify { .. } => "modify",
+            PendingOp::Delete { .. } => "delete",
+        }
+    }
+
+    /// Operations that change system state (previewed in dry-run mode).
+    pub fn is_mutating(&self) -> bool {
+        !matches!(
+            self,
+            PendingOp::Status { .. }
+                | PendingOp::Diff { .. }
+                | PendingOp::GetConfigForEdit
+                | PendingOp::ViewConfig
+        )
+    }
+
+    /// Operations still available in read-only mode (browse, diff, mount).
+    pub fn allowed_read_only(&self) -> bool {
+        !matches!(
+            self,
+            PendingOp::Rollback { .. }
+                | PendingOp::Cleanup { .. }
+                | PendingOp::SetupQuota
+                | PendingOp::SetConfig
+                | PendingOp::GetConfigForEdit
+                | PendingOp::LimineSync { .. }
+                | PendingOp::Create
+                | PendingOp::Modify { .. }
+                | PendingOp::Delete { .. }
+                | PendingOp::AroundPre { .. }
+                | PendingOp::AroundPost { .. }
+        )
+    }
+
+    /// Operations that may leave the system half-changed if killed; cancel asks first.
+    pub fn is_dangerous(&self) -> bool {
+        matches!(
+            self,
+            PendingOp::Rollback { .. }
+                | PendingOp::Cleanup { .. }
+                | PendingOp::SetupQuota
+                | PendingOp::SetConfig
+                | PendingOp::LimineSync { .. }
+                | PendingOp::Delete { .. }
+        )
+    }
+
+    /// What state the system may be in after this operation was terminated midway.
+    pub fn cancel_outcome(&self) -> &'static str {
+        match self {
+            PendingOp::Status { .. } | PendingOp::Diff { .. } => "nothing was changed",
+            PendingOp::Mount { .. } => {
+                "the snapshot may still have been mounted; check and U to unmount"
+            }
+            PendingOp::Umount { .. } => "the snapshot may still be mounted",
+            PendingOp::Rollback { .. } => {
+                "the default subvolume may already have changed; verify the list before rebooting"
+            }
+            PendingOp::Cleanup { .. } => "some snapshots may already have been deleted",
+            PendingOp::SetupQuota => "quota groups may be partially configured",
+            PendingOp::SetConfig => {
+                "some config values may already be written; reopen with g to check"
+            }
+            PendingOp::GetConfigForEdit | PendingOp::ViewConfig => "nothing was changed",
+            PendingOp::LimineSync { .. } => {
+                "boot entries and /etc/default/limine may be partially written"
+            }
+            PendingOp::Create => "the snapshot may still have been created",
+            PendingOp::Modify { .. } => "the description may already have changed",
+            PendingOp::Delete { .. } => "some snapshots may already have been deleted",
+            PendingOp::AroundPre { .. } => {
+                "the pre snapshot may still have been created; the command was not run"
+            }
+            PendingOp::AroundPost { .. } => "the post snapshot may still have been created",
+        }
+    }
+}
+
+/// Body of a job: gets the runner and a line sink for live output (returns false once
+/// nobody listens); its returned text is the job's result. Kept so the job can be retried.
+pub type Work = dyn Fn(&Runner, &mut dyn FnMut(String) -> bool) -> Result<String> + Send + Sync;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobState {
+    Running,
+    Succeeded,
+    Failed(String),
+    // cancelled by the user or timed out
+    Interrupted(String),
+}
+
+enum JobMsg {
+    Line(String),
+    Done(Result<String>),
+}
+
+/// One background operation: its live output, timing and final result.
+pub struct Job {
+    pub id: u64,
+    pub op: PendingOp,
+    pub config: String,
+    pub label: String,
+    pub started: Instant,
+    pub finished: Option<Instant>,
+    pub output: LineBuffer,
+    pub state: JobState,
+    pub cancel_requested: bool,
+    // Set for dry runs: the commands the job would have executed
+    pub preview: Option<Preview>,
+    // Failed only because sudo wanted a password; retried after authenticating
+    pub needs_auth: bool,
+    // Text returned by the worker, taken by the app's completion handler
+    pub result_text: Option<String>,
+    handle: ProcHandle,
+    rx: Option<Receiver<JobMsg>>,
+    work: Arc<Work>,
+}
+
+impl Job {
+    pub fn is_running(&self) -> bool {
+        self.state == JobState::Running
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.finished
+            .unwrap_or_else(Instant::now)
+            .duration_since(self.started)
+    }
+
+    /// Terminate the job's processes; the worker reports back once they are gone.
+    pub fn cancel(&mut self) {
+        if self.is_running() {
+            self.handle.cancel();
+            self.cancel_requested = true;
+        }
+    }
+
+    pub fn state_label(&self) -> String {
+        match &self.state {
+            JobState::Running if self.cancel_requested => "cancelling".into(),
+            JobState::Running => "running".into(),
+            JobState::Succeeded => "done".into(),
+            JobState::Failed(_) => "failed".into(),
+            JobState::Interrupted(why) => why.clone(),
+        }
+    }
+
+    pub fn last_line(&self) -> String {
+        self.output
+            .len()
+            .checked_sub(1)
+            .and_then(|i| self.output.line(i))
+            .unwrap_or_default()
+    }
+
+    fn finish(&mut self, res: Result<String>) {
+        self.rx = None;
+        self.finished = Some(Instant::now());
+        match res {
+            Ok(text) => {
+                for line in text.lines() {
+                    self.output.push_line(line);
+                }
+                self.result_text = Some(text);
+                self.state = JobState::Succeeded;
+            }
+            Err(e) => match system::interrupted(&e) {
+                Some(why) => self.state = JobState::Interrupted(why.to_string()),
+                None => {
+                    self.needs_auth = system::auth_required(&e);
+                    self.output.push_line(&format!("[error] {e}"));
+                    self.state = JobState::Failed(e.to_string());
+                }
+            },
+        }
+    }
+}
+
+/// Background job queue; several operations may run at once.
+#[derive(Default)]
+pub struct Jobs {
+    next_id: u64,
+    pub list: Vec<Job>,
+}
+
+impl Jobs {
+    /// Start `work` on a worker thread.
+    pub fn spawn<F>(
+        &mut self,
+        op: PendingOp,
+        config: String,
+        label: String,
+        runner: Runner,
+        work: F,
+    ) -> u64
+    where
+        F: Fn(&Runner, &mut dyn FnMut(String) -> bool) -> Result<String> + Send + Sync + 'static,
+    {
+        self.start(op, config, label, runner, Arc::new(work))
+    }
+
+    /// Run a finished job's work again as a new job (e.g. after sudo authentication).
+    pub fn retry(&mut self, id: u64, runner: Runner) -> Option<u64> {
+        let job = self.get(id)?;
+        let (op, config, label, work) = (
+            job.op.clone(),
+            job.config.clone(),
+            job.label.clone(),
+            job.work.clone(),
+        );
+        Some(self.start(op, config, label, runner, work))
+    }
+
+    fn start(
+        &mut self,
+        op: PendingOp,
+        config: String,
+        label: String,
+        runner: Runner,
+        work: Arc<Work>,
+    ) -> u64 {
+        self.next_id += 1;
+        let id = self.next_id;
+        let (tx, rx) = mpsc::sync_channel::<JobMsg>(STREAM_CHANNEL_CAP);
+        let handle = runner.handle.clone();
+        let preview = runner.preview.clone();
+        let body = work.clone();
+        thread::spawn(move || {
+            let line_tx: SyncSender<JobMsg> = tx.clone();
+            let res = body(&runner, &mut |line| {
+                line_tx.send(JobMsg::Line(line)).is_ok()
+            });
+            let _ = tx.send(JobMsg::Done(res));
+        });
+        self.list.push(Job {
+            id,
+            op,
+            config,
+            label,
+            started: Instant::now(),
+            finished: None,
+            output: LineBuffer::new(),
+            state: JobState::Running,
+            cancel_requested: false,
+            preview,
+            needs_auth: false,
+            result_text: None,
+            handle,
+            rx: Some(rx),
+            work,
+        });
+        self.prune();
+        id
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Job> {
+        self.list.iter().find(|j| j.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut Job> {
+        self.list.iter_mut().find(|j| j.id == id)
+    }
+
+    pub fn running(&self) -> impl Iterator<Item = &Job> {
+        self.list.iter().filter(|j| j.is_running())
+    }
+
+    /// Move pending output into job buffers (bounded per call to keep input responsive)
+    /// and return the ids of jobs that finished.
+    pub fn poll(&mut self) -> Vec<u64> {
+        let deadline = Instant::now() + Duration::from_millis(30);
+        let mut finished = Vec::new();
+        for job in self.list.iter_mut() {
+            let Some(rx) = &job.rx else {
+                continue;
+            };
+            let mut done: Option<Result<String>> = None;
+            loop {
+                match rx.try_recv() {
+                    Ok(JobMsg::Line(line)) => {
+                        job.output.push_line(&line);
+                        if Instant::now() >= deadline {
+                            break;
+                        }
+                    }
+                    Ok(JobMsg::Done(res)) => {
+                        done = Some(res);
+                        break;
+                    }
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        done = Some(Err(anyhow::anyhow!("worker disconnected")));
+                        break;
+                    }
+                }
+            }
+            if let Some(res) = done {
+                job.finish(res);
+                finished.push(job.id);
+            }
+        }
+        finished
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.list.retain(|j| j.is_running());
+    }
+
+    fn prune(&mut self) {
+        let finished = self.list.iter().filter(|j| !j.is_running()).count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED_JOBS);
+        self.list.retain(|j| {
+            if excess > 0 && !j.is_running() {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
@@ -0,0 +1,707 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::state;
+use crate::vi;
+
+/// Everything the main snapshot view can do from a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    Quit,
+    Refresh,
+    Create,
+    Edit,
+    Delete,
+    Status,
+    Diff,
+    Mount,
+    Umount,
+    Rollback,
+    MakePermanent,
+    Cleanup,
+    ViewConfig,
+    EditConfig,
+    SetupQuota,
+    LimineSync,
+    Around,
+    Export,
+    ToggleMark,
+    MarkRange,
+    ClearMarks,
+    Jobs,
+    History,
+    DryRun,
+    Filter,
+    SortNext,
+    SortReverse,
+    Columns,
+    Views,
+    NextView,
+    NextConfig,
+    PrevConfig,
+    ToggleUserdata,
+    Preview,
+    Escalation,
+    ViKeys,
+    Theme,
+    Help,
+    Palette,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Section {
+    Basics,
+    Navigation,
+    Snapshots,
+    Config,
+}
+
+impl Section {
+    pub const ALL: [Section; 4] = [
+        Section::Basics,
+        Section::Navigation,
+        Section::Snapshots,
+        Section::Config,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Section::Basics => "Basics",
+            Section::Navigation => "Navigation",
+            Section::Snapshots => "Snapshots",
+            Section::Config => "Config management",
+        }
+    }
+}
+
+impl Action {
+    // Also the order of the status bar hints
+    pub const ALL: [Action; 45] = [
+        Action::Quit,
+        Action::Refresh,
+        Action::Create,
+        Action::Edit,
+        Action::Delete,
+        Action::Status,
+        Action::Diff,
+        Action::Mount,
+        Action::Umount,
+        Action::Rollback,
+        Action::MakePermanent,
+        Action::Cleanup,
+        Action::ViewConfig,
+        Action::EditConfig,
+        Action::SetupQuota,
+        Action::LimineSync,
+        Action::Around,
+        Action::Export,
+        Action::ToggleMark,
+        Action::MarkRange,
+        Action::ClearMarks,
+        Action::Jobs,
+        Action::History,
+        Action::DryRun,
+        Action::Filter,
+        Action::SortNext,
+        Action::SortReverse,
+        Action::Columns,
+        Action::Views,
+        Action::NextView,
+        Action::NextConfig,
+        Action::PrevConfig,
+        Action::ToggleUserdata,
+        Action::Preview,
+        Action::Escalation,
+        Action::ViKeys,
+        Action::Theme,
+        Action::Help,
+        Action::Palette,
+        Action::Up,
+        Action::Down,
+        Action::PageUp,
+        Action::PageDown,
+        Action::First,
+        Action::Last,
+    ];
+
+    /// Name used in keymap.toml.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Refresh => "refresh",
+            Action::Create => "create",
+            Action::Edit => "edit",
+            Action::Delete => "delete",
+            Action::Status => "status",
+            Action::Diff => "diff",
+            Action::Mount => "mount",
+            Action::Umount => "umount",
+            Action::Rollback => "rollback",
+            Action::MakePermanent => "make-permanent",
+            Action::Cleanup => "cleanup",
+            Action::ViewConfig => "view-config",
+            Action::EditConfig => "edit-config",
+            Action::SetupQuota => "setup-quota",
+            Action::LimineSync => "limine-sync",
+            Action::Around => "snapshot-around",
+            Action::Export => "export",
+            Action::ToggleMark => "mark",
+            Action::MarkRange => "mark-range",
+            Action::ClearMarks => "clear-marks",
+            Action::Jobs => "jobs",
+            Action::History => "history",
+            Action::DryRun => "dry-run",
+            Action::Filter => "filter",
+            Action::SortNext => "sort-next",
+            Action::SortReverse => "sort-reverse",
+            Action::Columns => "columns",
+            Action::Views => "views",
+            Action::NextView => "next-view",
+            Action::NextConfig => "next-config",
+            Action::PrevConfig => "prev-config",
+            Action::ToggleUserdata => "userdata",
+            Action::Preview => "preview",
+            Action::Escalation => "escalation",
+            Action::ViKeys => "vi-keys",
+            Action::Theme => "theme",
+            Action::Help => "help",
+            Action::Palette => "palette",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::PageUp => "page-up",
+            Action::PageDown => "page-down",
+            Action::First => "first",
+            Action::Last => "last",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|a| a.name() == name)
+    }
+
+    /// What the action does, for the help screen.
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::Refresh => "Refresh configs and snapshots",
+            Action::Create => "Create snapshot",
+            Action::Edit => "Edit description",
+            Action::Delete => "Delete selected or marked snapshots",
+            Action::Status => "Show status (prev..selected)",
+            Action::Diff => "Show diff (prev..selected)",
+            Action::Mount => "Mount snapshot",
+            Action::Umount => "Unmount snapshot",
+            Action::Rollback => "Rollback to selected (wizard, type the id to confirm)",
+            Action::MakePermanent => "Booted from a snapshot: make it permanent",
+            Action::Cleanup => "Cleanup (number | timeline | empty-pre-post)",
+            Action::ViewConfig => "View config (get-config)",
+            Action::EditConfig => "Edit config (form)",
+            Action::SetupQuota => "Setup quota",
+            Action::LimineSync => "Sync to Limine",
+            Action::Around => "Snapshot around a command (pre, run, post)",
+            Action::Export => "Export shown snapshots (CSV, JSON, Markdown)",
+            Action::ToggleMark => "Mark/unmark for bulk delete",
+            Action::MarkRange => "Mark range to cursor",
+            Action::ClearMarks => "Clear marks",
+            Action::Jobs => "Jobs panel",
+            Action::History => "History (audit log)",
+            Action::DryRun => "Toggle dry run (preview changing commands)",
+            Action::Filter => "Filter snapshots",
+            Action::SortNext => "Sort by the next column",
+            Action::SortReverse => "Reverse the sort order",
+            Action::Columns => "Columns: show, hide, reorder",
+            Action::Views => "Views picker",
+            Action::NextView => "Next saved view",
+            Action::NextConfig => "Next config tab",
+            Action::PrevConfig => "Previous config tab",
+            Action::ToggleUserdata => "Toggle Userdata panel",
+            Action::Preview => "Toggle change preview pane (selected vs pre or previous)",
+            Action::Escalation => "Cycle escalation: off → sudo → doas → run0 → pkexec",
+            Action::ViKeys => "Toggle vi keys (j/k, gg/G, Ctrl-d/u, / ? n N, counts, :, marks)",
+            Action::Theme => "Next color theme (dark, light, high-contrast, no-color, themes/)",
+            Action::Help => "Toggle this help",
+            Action::Palette => "Command palette: find and run any action",
+            Action::Up => "Move selection up",
+            Action::Down => "Move selection down",
+            Action::PageUp => "Page up",
+            Action::PageDown => "Page down",
+            Action::First => "First snapshot",
+            Action::Last => "Last snapshot",
+        }
+    }
+
+    /// Short status bar hint; None keeps the action out of the hint line.
+    pub fn hint(self) -> Option<&'static str> {
+        Some(match self {
+            Action::Quit => "quit",
+            Action::Refresh => "refresh",
+            Action::Create => "create",
+            Action::Edit => "edit",
+            Action::Delete => "delete",
+            Action::Status => "details",
+            Action::Diff => "diff",
+            Action::Mount => "mount",
+            Action::Umount => "umount",
+            Action::Rollback => "rollback",
+            Action::Cleanup => "cleanup",
+            Action::ViewConfig => "view-config",
+            Action::EditConfig => "edit-config",
+            Action::SetupQuota => "setup-quota",
+            Action::LimineSync => "limine-sync",
+            Action::Jobs => "jobs",
+            Action::History => "history",
+            Action::DryRun => "dry-run",
+            Action::Filter => "filter",
+            Action::SortNext => "sort",
+            Action::Columns => "columns",
+            Action::Views => "views",
+            Action::NextConfig => "switch-config",
+            Action::ToggleUserdata => "userdata",
+            Action::Preview => "preview",
+            Action::Escalation => "escalation",
+            Action::Help => "help",
+            Action::Palette => "palette",
+            _ => return None,
+        })
+    }
+
+    pub fn section(self) -> Section {
+        match self {
+            Action::Quit
+            | Action::Refresh
+            | Action::Help
+            | Action::Palette
+            | Action::Escalation
+            | Action::ViKeys
+            | Action::Theme
+            | Action::DryRun
+            | Action::Jobs
+            | Action::History => Section::Basics,
+            Action::Up
+            | Action::Down
+            | Action::PageUp
+            | Action::PageDown
+            | Action::First
+            | Action::Last
+            | Action::NextConfig
+            | Action::PrevConfig => Section::Navigation,
+            Action::ViewConfig | Action::EditConfig | Action::SetupQuota => Section::Config,
+            _ => Section::Snapshots,
+        }
+    }
+
+    /// Refused in read-only mode.
+    pub fn changes_state(self) -> bool {
+        matches!(
+            self,
+            Action::Create
+                | Action::Edit
+                | Action::Delete
+                | Action::Around
+                | Action::Rollback
+                | Action::MakePermanent
+                | Action::Cleanup
+                | Action::EditConfig
+                | Action::SetupQuota
+                | Action::LimineSync
+        )
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q"],
+            Action::Refresh => &["r"],
+            Action::Create => &["c"],
+            Action::Edit => &["e"],
+            Action::Delete => &["d"],
+            Action::Status => &["Enter"],
+            Action::Diff => &["x"],
+            Action::Mount => &["m"],
+            Action::Umount => &["U"],
+            Action::Rollback => &["R"],
+            Action::MakePermanent => &["P"],
+            Action::Cleanup => &["K"],
+            Action::ViewConfig => &["C"],
+            Action::EditConfig => &["g"],
+            Action::SetupQuota => &["Q"],
+            Action::LimineSync => &["Y"],
+            Action::Around => &["A"],
+            Action::Export => &["E"],
+            Action::ToggleMark => &["Space"],
+            Action::MarkRange => &["v"],
+            Action::ClearMarks => &["V"],
+            Action::Jobs => &["J"],
+            Action::History => &["H"],
+            Action::DryRun => &["D"],
+            Action::Filter => &["F", "Ctrl-f"],
+            Action::SortNext => &["o"],
+            Action::SortReverse => &["O"],
+            Action::Columns => &["L"],
+            Action::Views => &["w"],
+            Action::NextView => &["W"],
+            Action::NextConfig => &["Tab", "Right", "]"],
+            Action::PrevConfig => &["Shift-Tab", "Left", "["],
+            Action::ToggleUserdata => &["u"],
+            Action::Preview => &["p"],
+            Action::Escalation => &["S"],
+            Action::ViKeys => &["Ctrl-v"],
+            Action::Theme => &["T"],
+            Action::Help => &["?"],
+            Action::Palette => &["Ctrl-p"],
+            Action::Up => &["Up"],
+            Action::Down => &["Down"],
+            Action::PageUp => &["PageUp"],
+            Action::PageDown => &["PageDown"],
+            Action::First => &["Home"],
+            Action::Last => &["End"],
+        }
+    }
+}
+
+/// A key plus the modifiers that matter for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    // Shift is already in the character ('A') and Shift-Tab arrives as BackTab
+    fn normalized(code: KeyCode, modifiers: KeyModifiers) -> Key {
+        let modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        match code {
+            KeyCode::Char(_) | KeyCode::BackTab => Key {
+                code,
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => Key {
+                code: KeyCode::BackTab,
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            _ => Key { code, modifiers },
+        }
+    }
+
+    pub fn from_event(key: &KeyEvent) -> Key {
+        Key::normalized(key.code, key.modifiers)
+    }
+
+    /// "x", "Enter", "Ctrl-f", "Alt-Left", "Shift-Tab", "F5", ...
+    pub fn parse(spec: &str) -> Result<Key, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = spec;
+        // A lone "-" is a key, not a modifier separator
+        while let Some((prefix, tail)) = rest.split_once('-').filter(|(_, t)| !t.is_empty()) {
+            modifiers |= match prefix.to_ascii_lowercase().as_str() {
+                "ctrl" | "c" => KeyModifiers::CONTROL,
+                "alt" | "m" => KeyModifiers::ALT,
+                "shift" | "s" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier '{prefix}' in '{spec}'")),
+            };
+            rest = tail;
+        }
+        let code = match rest.to_ascii_lowercase().as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "pageup" | "pgup" => KeyCode::PageUp,
+            "pagedown" | "pgdn" => KeyCode::PageDown,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            f if f.len() > 1 && f.starts_with('f') && f[1..].parse::<u8>().is_ok() => {
+                KeyCode::F(f[1..].parse().unwrap_or(1))
+            }
+            _ => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(format!("unknown key '{spec}'")),
+                }
+            }
+        };
+        // Escape is how every panel closes, so it can't be taken by the main view
+        if code == KeyCode::Esc {
+            return Err("Esc is reserved for closing panels".into());
+        }
+        Ok(Key::normalized(code, modifiers))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            f.write_str("Shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::BackTab => f.write_str("Shift-Tab"),
+            KeyCode::Up => f.write_str("↑"),
+            KeyCode::Down => f.write_str("↓"),
+            KeyCode::Left => f.write_str("←"),
+            KeyCode::Right => f.write_str("→"),
+            KeyCode::PageUp => f.write_str("PgUp"),
+            KeyCode::PageDown => f.write_str("PgDn"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+/// Key bindings of the main view: the defaults, overridden by keymap.toml in the config dir.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: BTreeMap<Action, Vec<Key>>,
+    // Actions keymap.toml rebinds
+    custom: BTreeSet<Action>,
+    // Vi keys are on: the keys they take are left out of hints and help
+    vi: bool,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .into_iter()
+            .map(|a| {
+                let keys = a
+                    .default_keys()
+                    .iter()
+                    .filter_map(|k| Key::parse(k).ok())
+                    .collect();
+                (a, keys)
+            })
+            .collect();
+        Keymap {
+            bindings,
+            custom: BTreeSet::new(),
+            vi: false,
+        }
+    }
+}
+
+// `delete = "d"` or `filter = ["F", "Ctrl-f"]`; an empty list unbinds the action
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeySpec {
+    One(String),
+    Many(Vec<String>),
+}
+
+pub fn keymap_paths() -> [PathBuf; 2] {
+    let dir = state::config_dir();
+    [dir.join("keymap.toml"), dir.join("keymap.json")]
+}
+
+impl Keymap {
+    /// The keymap from keymap.toml (or keymap.json), and what was wrong with it. Any problem
+    /// (unknown action or key, two actions on one key, a rebound key that vi keys take while
+    /// `vi` is on) falls back to the default keys.
+    pub fn load(vi: bool) -> (Keymap, Vec<String>) {
+        let (mut map, problems) = Keymap::load_file(vi);
+        map.vi = vi;
+        (map, problems)
+    }
+
+    fn load_file(vi: bool) -> (Keymap, Vec<String>) {
+        let Some((path, text)) = keymap_paths()
+            .into_iter()
+            .find_map(|p| fs::read_to_string(&p).ok().map(|t| (p, t)))
+        else {
+            return (Keymap::default(), Vec::new());
+        };
+        let json = path.extension().is_some_and(|e| e == "json");
+        let parsed: Result<BTreeMap<String, KeySpec>, String> = if json {
+            serde_json::from_str(&text).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&text).map_err(|e| e.message().replace('\n', ", "))
+        };
+        let name = path.display();
+        let overrides = match parsed {
+            Ok(map) => map,
+            Err(e) => return (Keymap::default(), vec![format!("{name}: {e}")]),
+        };
+        match Keymap::with_overrides(overrides, vi) {
+            Ok(map) => (map, Vec::new()),
+            Err(problems) => (
+                Keymap::default(),
+                problems
+                    .into_iter()
+                    .map(|p| format!("{name}: {p}"))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn with_overrides(
+        overrides: BTreeMap<String, KeySpec>,
+        vi: bool,
+    ) -> Result<Keymap, Vec<String>> {
+        let mut map = Keymap::default();
+        let mut problems = Vec::new();
+        for (name, spec) in overrides {
+            let Some(action) = Action::from_name(&name) else {
+                problems.push(format!("unknown action '{name}'"));
+                continue;
+            };
+            let specs = match spec {
+                KeySpec::One(s) => vec![s],
+                KeySpec::Many(list) => list,
+            };
+            let mut keys = Vec::new();
+            for spec in specs {
+                match Key::parse(&spec) {
+                    Ok(key) => keys.push(key),
+                    Err(e) => problems.push(format!("{name}: {e}")),
+                }
+            }
+            map.bindings.insert(action, keys);
+            map.custom.insert(action);
+        }
+        problems.extend(map.conflicts());
+        if vi {
+            problems.extend(map.vi_conflicts());
+        }
+        if problems.is_empty() {
+            Ok(map)
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Keys bound to more than one action.
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut owner: BTreeMap<String, Action> = BTreeMap::new();
+        let mut problems = Vec::new();
+        for (&action, keys) in &self.bindings {
+            for key in keys {
+                match owner.insert(key.to_string(), action) {
+                    Some(other) if other != action => problems.push(format!(
+                        "{key} is bound to both {} and {}",
+                        other.name(),
+                        action.name()
+                    )),
+                    _ => {}
+                }
+            }
+        }
+        problems
+    }
+
+    /// Keys keymap.toml binds that vi keys take first, so they never reach their action.
+    pub fn vi_conflicts(&self) -> Vec<String> {
+        self.custom
+            .iter()
+            .flat_map(|a| self.keys(*a).iter().map(move |k| (a, k)))
+            .filter(|(_, k)| vi_takes(k))
+            .map(|(a, k)| format!("{k} is taken by vi keys (run :{} instead)", a.name()))
+            .collect()
+    }
+
+    pub fn set_vi(&mut self, on: bool) {
+        self.vi = on;
+    }
+
+    // Keys that reach the action right now
+    fn live_keys(&self, action: Action) -> Vec<Key> {
+        self.keys(action)
+            .iter()
+            .copied()
+            .filter(|k| !(self.vi && vi_takes(k)))
+            .collect()
+    }
+
+    pub fn action_for(&self, key: &KeyEvent) -> Option<Action> {
+        let key = Key::from_event(key);
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.bindings
+            .get(&action)
+            .map(|k| k.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// "F / Ctrl-f" style list of an action's keys; empty when unbound. An action whose
+    /// keys vi keys took is reached with its `:name` instead.
+    pub fn label(&self, action: Action) -> String {
+        let keys = self.live_keys(action);
+        if keys.is_empty() && !self.keys(action).is_empty() {
+            return format!(":{}", action.name());
+        }
+        keys.iter()
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+
+    /// The first key that reaches `action`, or `:name` when vi keys took them all.
+    pub fn first_key(&self, action: Action) -> Option<String> {
+        match self.live_keys(action).first() {
+            Some(key) => Some(key.to_string()),
+            None if !self.keys(action).is_empty() => Some(format!(":{}", action.name())),
+            None => None,
+        }
+    }
+
+    /// Status bar hint line, e.g. "q quit · r refresh · ...".
+    pub fn hints(&self) -> String {
+        self.hint_line(&Action::ALL)
+    }
+
+    /// Hint line for narrow screens: just where to find the rest.
+    pub fn short_hints(&self) -> String {
+        self.hint_line(&[Action::Help, Action::Palette])
+    }
+
+    fn hint_line(&self, actions: &[Action]) -> String {
+        actions
+            .iter()
+            .filter_map(|&a| Some((a.hint()?, self.first_key(a)?)))
+            .map(|(hint, key)| {
+                // ":help" already says what it does
+                if key.starts_with(':') {
+                    key
+                } else {
+                    format!("{key} {hint}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" · ")
+    }
+}
+
+fn vi_takes(key: &Key) -> bool {
+    vi::KEYS
+        .iter()
+        .any(|k| Key::parse(k).is_ok_and(|v| v == *key))
+}
@@ -2,7 +2,10 @@ use anyhow::{Context, Result};
 use std::fs;
 use std::path::PathBuf;
 
-use crate::system;
+use crate::system::{self, Runner};
+
+// Everything Limine sync changes is recorded in the audit log under this op name
+const AUDIT_OP: &str = "limine-sync";
 
 pub struct Limine;
 
@@ -32,7 +35,7 @@ impl Limine {
         None
     }
 
-    pub fn ensure_snapshots_marker(use_sudo: bool) -> Result<String> {
+    pub fn ensure_snapshots_marker(config: &str, runner: &Runner) -> Result<String> {
         let mut log = String::new();
         if let Some(path) = Self::detect_limine_conf() {
             let content = fs::read_to_string(&path).unwrap_or_default();
@@ -50,18 +53,21 @@ impl Limine {
                 "[+] Adding //Snapshots marker to {}\n",
                 path.display()
             ));
-            let _ = system::run(
+            system::run_string_mutating(
+                AUDIT_OP,
+                config,
                 "sh",
                 &[
                     "-c",
                     &format!(
-                        "printf '\n//Snapshots\n' | {} tee -a '{}' >/dev/null",
-                        if use_sudo { "sudo" } else { "" },
+                        "printf '\n//Snapshots\n' | {}tee -a '{}' >/dev/null",
+                        runner.escalation.shell_prefix(),
                         path.display()
                     ),
                 ],
-                false,
-            );
+                &runner.unprivileged(),
+            )
+            .with_context(|| format!("Adding the //Snapshots marker to {}", path.display()))?;
             Ok(log)
         } else {
             log.push_str(
@@ -73,7 +79,9 @@ impl Limine {
 
     pub fn detect_root_subvol_path() -> String {
         // Try findmnt options
-        if let Ok(opts) = system::run_string("findmnt", &["-no", "OPTIONS", "/"], false) {
+        if let Ok(opts) =
+            system::run_string("findmnt", &["-no", "OPTIONS", "/"], &Runner::default())
+        {
             for part in opts.split(',') {
                 let mut kv = part.trim().splitn(2, '=');
                 if let (Some(k), Some(v)) = (kv.next(), kv.next()) {
@@ -94,15 +102,17 @@ impl Limine {
     }
 
     pub fn detect_esp_path() -> Option<String> {
-        if let Ok(out) = system::run_string("bootctl", &["--print-esp-path"], false) {
+        if let Ok(out) = system::run_string("bootctl", &["--print-esp-path"], &Runner::default()) {
             let p = out.trim();
             if !p.is_empty() {
                 return Some(p.to_string());
             }
         }
-        if let Ok(out) =
-            system::run_string("findmnt", &["-ln", "-t", "vfat", "-o", "TARGET"], false)
-        {
+        if let Ok(out) = system::run_string(
+            "findmnt",
+            &["-ln", "-t", "vfat", "-o", "TARGET"],
+            &Runner::default(),
+        ) {
             for line in out.lines() {
                 let t = line.trim();
                 if t.ends_with("/efi") || t.ends_with("/boot/efi") || t == "/boot" {
@@ -114,9 +124,10 @@ impl Limine {
     }
 
     pub fn configure_defaults(
+        config: &str,
         subvol_path: &str,
         esp_path: Option<&str>,
-        use_sudo: bool,
+        runner: &Runner,
     ) -> Result<String> {
         let mut log = String::new();
         let default_conf = "/etc/default/limine";
@@ -126,51 +137,68 @@ impl Limine {
                 "# Auto-generated by snapper-tui on {}\n",
                 chrono::Local::now().to_rfc3339()
             );
-            let _ = system::run(
+            system::run_string_mutating(
+                AUDIT_OP,
+                config,
                 "sh",
                 &[
                     "-c",
                     &format!(
-                        "echo {} | {} tee '{}' >/dev/null",
+                        "echo {} | {}tee '{}' >/dev/null",
                         shell_escape::escape(hdr.into()),
-                        if use_sudo { "sudo" } else { "" },
+                        runner.escalation.shell_prefix(),
                         default_conf
                     ),
                 ],
-                false,
-            );
+                &runner.unprivileged(),
+            )
+            .with_context(|| format!("Creating {default_conf}"))?;
         }
-        let set = |key: &str, val: &str| {
+        let set = |key: &str, val: &str| -> Result<()> {
             // Replace if exists
-            let sed_cmd = if use_sudo {
-                "sudo sed -i -E"
-            } else {
-                "sed -i -E"
-            };
             let replace = format!(
-                "{} 's|^{}=.*|{}=\"{}\"|' '{}'",
-                sed_cmd, key, key, val, default_conf
+                "{}sed -i -E 's|^{}=.*|{}=\"{}\"|' '{}'",
+                runner.escalation.shell_prefix(),
+                key,
+                key,
+                val,
+                default_conf
             );
-            let _ = system::run("sh", &["-c", &replace], false);
+            system::run_string_mutating(
+                AUDIT_OP,
+                config,
+                "sh",
+                &["-c", &replace],
+                &runner.unprivileged(),
+            )
+            .with_context(|| format!("Setting {key} in {default_conf}"))?;
             // Append if missing
             let append = format!(
-                "grep -q '^{}=' '{}' || echo '{}=\"{}\"' | {} tee -a '{}' >/dev/null",
+                "grep -q '^{}=' '{}' || echo '{}=\"{}\"' | {}tee -a '{}' >/dev/null",
                 key,
                 default_conf,
                 key,
                 val,
-                if use_sudo { "sudo" } else { "" },
+                runner.escalation.shell_prefix(),
                 default_conf
             );
-            let _ = system::run("sh", &["-c", &append], false);
+            system::run_string_mutating(
+                AUDIT_OP,
+                config,
+                "sh",
+                &["-c", &append],
+                &runner.unprivileged(),
+            )
+            .with_context(|| format!("Adding {key} to {default_conf}"))?;
+            Ok(())
         };
-        set("ROOT_SUBVOLUME_PATH", subvol_path);
+        set("ROOT_SUBVOLUME_PATH", subvol_path)?;
         set(
             "ROOT_SNAPSHOTS_PATH",
             &format!("{}/.snapshots", subvol_path),
-        );
+        )?;
         if let Some(esp) = esp_path {
-            set("ESP_PATH", esp);
+            set("ESP_PATH", esp)?;
         }
         log.push_str(&format!("[i] Set ROOT_SUBVOLUME_PATH={}\n", subvol_path));
         if let Some(esp) = esp_path {
@@ -180,26 +208,31 @@ impl Limine {
     }
 
     pub fn root_uuid() -> Result<String> {
-        let src = system::run_string("findmnt", &["-no", "SOURCE", "/"], false)?
+        let src = system::run_string("findmnt", &["-no", "SOURCE", "/"], &Runner::default())?
             .trim()
             .to_string();
         let dev = src.split('[').next().unwrap_or(&src).trim().to_string();
-        let uuid = system::run_string("blkid", &["-s", "UUID", "-o", "value", &dev], false)?
-            .trim()
-            .to_string();
+        let uuid = system::run_string(
+            "blkid",
+            &["-s", "UUID", "-o", "value", &dev],
+            &Runner::default(),
+        )?
+        .trim()
+        .to_string();
         Ok(uuid)
     }
 
-    pub fn limine_install(use_sudo: bool) -> Result<String> {
-        let _ = system::run_string("limine-install", &[], use_sudo)?;
-        let _ = system::run_string("limine", &["--version"], use_sudo)?;
+    pub fn limine_install(config: &str, runner: &Runner) -> Result<String> {
+        let _ = system::run_string_mutating(AUDIT_OP, config, "limine-install", &[], runner)?;
+        let _ = system::run_string("limine", &["--version"], runner)?;
         Ok("[+] Limine installed".to_string())
     }
 
     pub fn manual_add_entry(
+        config: &str,
         snapshot_id: u64,
         snapshot_name: &str,
-        use_sudo: bool,
+        runner: &Runner,
     ) -> Result<String> {
         let mut log = String::new();
         let snap_path = format!("/.snapshots/{}/snapshot", snapshot_id);
@@ -208,11 +241,12 @@ impl Limine {
         }
         let find = |pattern: &str| -> Result<String> {
             let script = format!(
-                "sudo sh -c 'find {} -maxdepth 1 -type f {} | head -n1'",
+                "{}sh -c 'find {} -maxdepth 1 -type f {} | head -n1'",
+                runner.escalation.shell_prefix(),
                 shell_escape::escape(snap_path.clone().into()),
                 pattern
             );
-            let out = system::run_string("sh", &["-c", &script], false)?;
+            let out = system::run_string("sh", &["-c", &script], &runner.unprivileged())?;
             Ok(out.trim().to_string())
         };
         let kernel = find("-name 'vmlinuz*'")?;
@@ -224,7 +258,7 @@ impl Limine {
         let limine_conf = Self::detect_limine_conf()
             .unwrap_or_else(|| PathBuf::from(format!("{}/EFI/limine/limine.conf", esp)));
         let dst_dir = format!("{}/snapshots/{}", esp, snapshot_id);
-        let _ = system::run_string("sudo", &["mkdir", "-p", &dst_dir], false)?;
+        let _ = system::run_string_mutating(AUDIT_OP, config, "mkdir", &["-p", &dst_dir], runner)?;
         let kernel_base = std::path::Path::new(&kernel)
             .file_name()
             .unwrap()
@@ -235,48 +269,47 @@ impl Limine {
             .unwrap()
             .to_string_lossy()
             .to_string();
-        let _ = system::run_string(
+        let _ = system::run_string_mutating(
+            AUDIT_OP,
+            config,
             "cp",
             &["-f", &kernel, &format!("{}/{}", dst_dir, kernel_base)],
-            use_sudo,
+            runner,
         )?;
-        let _ = system::run_string(
+        let _ = system::run_string_mutating(
+            AUDIT_OP,
+            config,
             "cp",
             &["-f", &initrd, &format!("{}/{}", dst_dir, initrd_base)],
-            use_sudo,
+            runner,
         )?;
         let uuid = Self::root_uuid()?;
         let entry = format!(
             "\n/{}\n    protocol: linux\n    path: boot():/snapshots/{}/{}\n    module_path: boot():/snapshots/{}/{}\n    cmdline: root=UUID={} rootflags=subvol=/.snapshots/{}/snapshot rw\n",
             snapshot_name, snapshot_id, kernel_base, snapshot_id, initrd_base, uuid, snapshot_id
         );
-        let _ = system::run_string(
-            "sudo",
-            &[
-                "mkdir",
-                "-p",
-                limine_conf.parent().unwrap().to_str().unwrap(),
-            ],
-            false,
-        )?;
-        let _ = system::run_string(
+        let _ = system::run_string_mutating(
+            AUDIT_OP,
+            config,
             "mkdir",
             &["-p", limine_conf.parent().unwrap().to_str().unwrap()],
-            use_sudo,
+            runner,
         )?;
-        let tee_sudo = if use_sudo { "sudo" } else { "" };
-        let _ = system::run_string(
+        let tee_sudo = runner.escalation.shell_prefix();
+        let _ = system::run_string_mutating(
+            AUDIT_OP,
+            config,
             "sh",
             &[
                 "-c",
                 &format!(
-                    "printf %s '{}' | {} tee -a '{}' >/dev/null",
+                    "printf %s '{}' | {}tee -a '{}' >/dev/null",
                     shell_escape::escape(entry.into()),
                     tee_sudo,
                     limine_conf.display()
                 ),
             ],
-            false,
+            &runner.unprivileged(),
         )?;
         log.push_str(&format!(
             "[+] Appended entry to {}\n",
@@ -285,46 +318,55 @@ impl Limine {
         Ok(log)
     }
 
+    /// Limine writes are audited under the snapper `config` the sync was started for.
     pub fn sync_snapshot_to_limine(
+        config: &str,
         snapshot_id: u64,
         snapshot_name: &str,
-        use_sudo: bool,
+        runner: &Runner,
     ) -> Result<String> {
         if !Self::is_installed() {
             anyhow::bail!("Limine is not installed");
         }
         let mut log = String::new();
         if Self::has_sync() {
-            log.push_str(&Self::ensure_snapshots_marker(use_sudo)?);
+            log.push_str(&Self::ensure_snapshots_marker(config, runner)?);
             let subvol = Self::detect_root_subvol_path();
             log.push_str(&Self::configure_defaults(
+                config,
                 &subvol,
                 Self::detect_esp_path().as_deref(),
-                use_sudo,
+                runner,
             )?);
             log.push_str("[+] Running limine-snapper-sync\n");
-            let _ = system::run_string("sudo", &["limine-snapper-sync"], false)?;
+            let _ =
+                system::run_string_mutating(AUDIT_OP, config, "limine-snapper-sync", &[], runner)?;
             if system::has_cmd("systemctl") {
-                let _ = system::run(
-                    "sudo",
-                    &[
-                        "systemctl",
-                        "enable",
-                        "--now",
-                        "limine-snapper-sync.service",
-                    ],
-                    false,
-                );
-                log.push_str("[i] Enabled limine-snapper-sync.service\n");
+                let out = system::run_mutating(
+                    AUDIT_OP,
+                    config,
+                    "systemctl",
+                    &["enable", "--now", "limine-snapper-sync.service"],
+                    runner,
+                )?;
+                if out.status.success() {
+                    log.push_str("[i] Enabled limine-snapper-sync.service\n");
+                } else {
+                    log.push_str(&format!(
+                        "[error] Could not enable limine-snapper-sync.service: {}\n",
+                        String::from_utf8_lossy(&out.stderr).trim()
+                    ));
+                }
             }
         } else {
             log.push_str(&Self::manual_add_entry(
+                config,
                 snapshot_id,
                 snapshot_name,
-                use_sudo,
+                runner,
             )?);
         }
-        log.push_str(&Self::limine_install(use_sudo)?);
+        log.push_str(&Self::limine_install(config, runner)?);
         Ok(log)
     }
 }
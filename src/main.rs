@@ -1,21 +1,115 @@
 mod app;
+mod audit;
+mod buffer;
+mod cli;
+mod escalation;
+mod export;
+mod filter;
+mod hit;
+mod jobs;
+mod keymap;
 mod limine;
+mod palette;
+mod rollback;
 mod snapper;
 mod state;
 mod system;
 mod theme; // Declare the theme module
 mod ui;
+mod vi;
+mod view;
 
 use anyhow::Result;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "Usage: snapper-tui [options]
+       snapper-tui <command> [options]   (headless; 'snapper-tui doctor --help' lists commands)
+
+Options:
+  --dry-run            Preview state-changing commands instead of running them
+  --read-only          Browse, diff and mount only; no create/delete/rollback/config changes
+  --config <name>      Open this snapper config
+  --snapshot <n>       Select this snapshot
+  --filter <text>      Start with this filter
+  --view status|diff   Open the status or diff view (of --range, else the selection)
+  --range <a..b>       Snapshot range for --view
+  --no-sudo            Run without privilege escalation this session (not saved)
+  --state-file <path>  Read and write UI state here instead of the config dir
+  -h, --help           Show this help";
+
+fn parse_options(args: Vec<String>) -> Result<Option<app::Options>, String> {
+    let mut opts = app::Options::default();
+    let mut it = args.into_iter();
+    while let Some(arg) = it.next() {
+        let mut value = |name: &str| it.next().ok_or(format!("{name} needs a value"));
+        match arg.as_str() {
+            "--dry-run" => opts.dry_run = true,
+            "--read-only" => opts.read_only = true,
+            "--no-sudo" => opts.no_sudo = true,
+            "--config" => opts.config = Some(value("--config")?),
+            "--filter" => {
+                let text = value("--filter")?;
+                filter::Query::parse(&text).map_err(|e| format!("--filter: {e}"))?;
+                opts.filter = Some(text);
+            }
+            "--snapshot" => {
+                let v = value("--snapshot")?;
+                let id = v
+                    .parse()
+                    .map_err(|_| format!("'{v}' is not a snapshot number"))?;
+                opts.startup.snapshot = Some(id);
+            }
+            "--view" => {
+                opts.startup.view = match value("--view")?.as_str() {
+                    "status" => Some(app::StartupView::Status),
+                    "diff" => Some(app::StartupView::Diff),
+                    other => return Err(format!("unknown view '{other}' (status or diff)")),
+                }
+            }
+            "--range" => opts.startup.range = Some(cli::parse_range(&value("--range")?)?),
+            "--state-file" => state::set_state_file(value("--state-file")?.into()),
+            "-h" | "--help" => return Ok(None),
+            other => return Err(format!("unknown argument '{other}'")),
+        }
+    }
+    if opts.startup.range.is_some() && opts.startup.view.is_none() {
+        return Err("--range needs --view status or --view diff".into());
+    }
+    if let Some(cfg) = &opts.config {
+        if !snapper::Snapper::config_exists(cfg) {
+            return Err(format!(
+                "unknown config '{cfg}' (not found in /etc/snapper/configs)"
+            ));
+        }
+    }
+    Ok(Some(opts))
+}
 
 fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // A subcommand runs headless and never touches the terminal
+    if args.first().is_some_and(|a| cli::is_command(a)) {
+        std::process::exit(cli::main(&args));
+    }
+    let opts = match parse_options(args) {
+        Ok(Some(opts)) => opts,
+        Ok(None) => {
+            println!("{USAGE}");
+            return Ok(());
+        }
+        Err(msg) => {
+            eprintln!("snapper-tui: {msg}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
     // Ensure terminal is restored even on panic
     let prev_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
@@ -32,7 +126,7 @@ fn main() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let res = run_app(&mut terminal);
+    let res = run_app(&mut terminal, &opts);
 
     // restore terminal
     disable_raw_mode()?;
@@ -47,26 +141,40 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
-    let mut app = app::App::new();
+fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    opts: &app::Options,
+) -> Result<()> {
+    let mut app = app::App::new(opts);
+    let tick_rate = Duration::from_millis(100);
+    let mut last_tick = Instant::now();
 
     loop {
-        // poll input with a short timeout to generate UI ticks
-        if event::poll(std::time::Duration::from_millis(100))? {
+        // poll input until the next tick is due; ticks also fire while events keep
+        // arriving, so job output and completions don't stall under mouse motion or key repeat
+        let timeout = tick_rate.saturating_sub(last_tick.elapsed());
+        if event::poll(timeout)? {
             match event::read()? {
-                Event::Key(key) => match key.code {
-                    KeyCode::Char('q') => break,
-                    _ => app.on_key(key),
-                },
+                Event::Key(key) => app.on_key(key),
                 Event::Mouse(me) => {
                     app.on_mouse(me);
                 }
                 Event::Resize(_, _) => { /* will redraw immediately below */ }
                 _ => {}
             }
-        } else {
-            // no input -> tick
+        }
+        if last_tick.elapsed() >= tick_rate {
             app.on_tick();
+            last_tick = Instant::now();
+        }
+
+        if app.should_quit {
+            break;
+        }
+
+        if let Some(req) = app.run_around.take() {
+            let res = run_suspended(terminal, &req);
+            app.finish_around(req, res);
         }
 
         // draw after handling input/resize for immediate visual update
@@ -75,3 +183,29 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()>
 
     Ok(())
 }
+
+// Hand the terminal to a command wrapped in a pre/post snapshot pair, then take it back
+fn run_suspended(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    req: &app::AroundCommand,
+) -> Result<std::process::ExitStatus> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+    terminal.show_cursor()?;
+    println!(
+        "snapper-tui: pre snapshot #{} ({}) taken; running: {}\n",
+        req.pre, req.config, req.command
+    );
+    let res = system::run_interactive(&req.command);
+    match &res {
+        Ok(status) => println!("\nsnapper-tui: command finished ({status})."),
+        Err(e) => println!("\nsnapper-tui: {e:#}"),
+    }
+    println!("Press Enter to take the post snapshot and return.");
+    let mut line = String::new();
+    let _ = io::stdin().read_line(&mut line);
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()?;
+    res
+}
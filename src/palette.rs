@@ -0,0 +1,51 @@
+use crate::keymap::Action;
+
+/// An action offered by the command palette for the typed query.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub action: Action,
+    // Char indices of the label that matched the query, for highlighting
+    pub matched: Vec<usize>,
+}
+
+/// Fuzzy match: every query char must appear in `text` in order (case-insensitive). Runs of
+/// consecutive chars and matches at the start of a word score higher; skipped chars lower.
+fn score(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let text: Vec<char> = text.chars().collect();
+    let mut matched = Vec::new();
+    let mut score = 0i64;
+    let mut pos = 0;
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let q = q.to_ascii_lowercase();
+        let found = (pos..text.len()).find(|&i| text[i].to_ascii_lowercase() == q)?;
+        let word_start = found == 0 || !text[found - 1].is_alphanumeric();
+        score += 1;
+        if word_start {
+            score += 8;
+        }
+        if matched.last().is_some_and(|&last| last + 1 == found) {
+            score += 5;
+        }
+        score -= (found - pos).min(10) as i64;
+        matched.push(found);
+        pos = found + 1;
+    }
+    Some((score, matched))
+}
+
+/// Actions matching `query`, best first; every action in help order for an empty query.
+pub fn search(query: &str) -> Vec<Entry> {
+    let mut found: Vec<(i64, Entry)> = Action::ALL
+        .into_iter()
+        .filter(|a| *a != Action::Palette)
+        .filter_map(|action| {
+            // The label is what is shown; the keymap name is a fallback
+            let (score, matched) = score(query, action.label())
+                .or_else(|| score(query, action.name()).map(|(s, _)| (s - 1, Vec::new())))?;
+            Some((score, Entry { action, matched }))
+        })
+        .collect();
+    // Stable: equal scores keep help order
+    found.sort_by_key(|(score, _)| -score);
+    found.into_iter().map(|(_, entry)| entry).collect()
+}
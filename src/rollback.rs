@@ -0,0 +1,163 @@
+use regex::Regex;
+use std::fs;
+use std::time::SystemTime;
+
+use crate::snapper::{self, Snapper, Snapshot};
+use crate::system::{self, Runner};
+
+/// What the rollback wizard knows before anything is changed.
+#[derive(Debug, Clone)]
+pub struct Probe {
+    // `btrfs subvolume get-default` line, or why it could not be read
+    pub default_subvol: Result<String, String>,
+    pub default_snapshot: Option<u64>,
+    pub kernel_warning: Option<String>,
+}
+
+/// Snapshot numbers reported by `snapper rollback`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Outcome {
+    // read-only copy of the system as it was before the rollback
+    pub backup: Option<u64>,
+    // read-write snapshot that is now the default subvolume
+    pub new_default: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    // Showing what will happen; waiting for the snapshot id to be typed
+    Review,
+    // Rollback job in flight
+    Running(u64),
+    Done(Outcome),
+}
+
+/// State of the rollback wizard for one target snapshot.
+#[derive(Debug, Clone)]
+pub struct Wizard {
+    pub config: String,
+    pub target: Snapshot,
+    // None until the background probe reports back
+    pub probe: Option<Probe>,
+    pub confirm: String,
+    pub step: Step,
+}
+
+impl Wizard {
+    pub fn new(config: &str, target: Snapshot) -> Self {
+        Self {
+            config: config.to_string(),
+            target,
+            probe: None,
+            confirm: String::new(),
+            step: Step::Review,
+        }
+    }
+
+    pub fn confirmed(&self) -> bool {
+        self.confirm == self.target.id.to_string()
+    }
+}
+
+pub fn probe(config: &str, target: &Snapshot, runner: &Runner) -> Probe {
+    let subvol = Snapper::config_subvolume(config).unwrap_or_else(|| "/".to_string());
+    let default_subvol =
+        system::run_string("btrfs", &["subvolume", "get-default", &subvol], runner)
+            .map(|s| s.trim().to_string())
+            .map_err(|e| format!("{e:#}"));
+    let default_snapshot = default_subvol
+        .as_ref()
+        .ok()
+        .and_then(|line| snapper::snapshot_number_in_path(line));
+    // Kernels only matter when rolling back the root filesystem
+    let kernel_warning = if subvol == "/" {
+        kernel_warning(target, runner)
+    } else {
+        None
+    };
+    Probe {
+        default_subvol,
+        default_snapshot,
+        kernel_warning,
+    }
+}
+
+fn running_kernel() -> Option<String> {
+    fs::read_to_string("/proc/sys/kernel/osrelease")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+// When the running kernel's modules were installed
+fn kernel_installed_at(release: &str) -> Option<SystemTime> {
+    ["/usr/lib/modules", "/lib/modules"]
+        .iter()
+        .find_map(|base| fs::metadata(format!("{base}/{release}")).ok())
+        .and_then(|m| m.modified().ok())
+}
+
+// Prints present/missing for the modules dir $2, or unreadable when the snapshot
+// root $1 can't be seen
+const MODULES_PROBE: &str = "if [ -d \"$1\" ]; then \
+     if [ -d \"$2\" ]; then echo present; else echo missing; fi; \
+     else echo unreadable; fi";
+
+fn kernel_warning(target: &Snapshot, runner: &Runner) -> Option<String> {
+    let release = running_kernel()?;
+    let root = format!("/.snapshots/{}/snapshot", target.id);
+    let modules = format!("{root}/usr/lib/modules/{release}");
+    // .snapshots is usually root-only, so ask through the runner; the snapshot root must be
+    // visible first, or a missing modules dir would only mean we weren't allowed to look
+    let probe = system::run("sh", &["-c", MODULES_PROBE, "sh", &root, &modules], runner)
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string());
+    match probe.as_deref() {
+        Some("present") => {}
+        Some("missing") => {
+            return Some(format!(
+                "Snapshot #{} has no modules for the running kernel {release}. After rollback \
+                 the system may not boot or load drivers until that kernel is reinstalled.",
+                target.id
+            ))
+        }
+        _ => {
+            return Some(format!(
+                "Could not check kernel modules in snapshot #{} ({root} is not readable); make \
+                 sure it still has modules for the running kernel {release}.",
+                target.id
+            ))
+        }
+    }
+    let installed = kernel_installed_at(&release)?;
+    let taken = snapper::parse_date(&target.date)?;
+    let installed_local: chrono::DateTime<chrono::Local> = installed.into();
+    if taken < installed_local.naive_local() {
+        return Some(format!(
+            "Snapshot #{} ({}) is older than the installed kernel {release} (installed {}). \
+             Check that its boot entries still match the kernel on disk.",
+            target.id,
+            target.date,
+            installed_local.format("%Y-%m-%d %H:%M")
+        ));
+    }
+    None
+}
+
+/// Pull the backup and new default numbers out of `snapper rollback` output, e.g.
+/// "Creating read-only snapshot of default subvolume. (Snapshot 42.)" and
+/// "Setting default subvolume to snapshot 43."
+pub fn parse_output(text: &str) -> Outcome {
+    let number = |pattern: &str| {
+        Regex::new(pattern)
+            .ok()
+            .and_then(|re| re.captures(text).and_then(|c| c.get(1)))
+            .and_then(|m| m.as_str().parse().ok())
+    };
+    Outcome {
+        backup: number(r"(?i)read-only snapshot of [^(]*\(snapshot (\d+)\.?\)"),
+        new_default: number(r"(?i)default subvolume to snapshot (\d+)")
+            .or_else(|| number(r"(?i)read-write snapshot of [^(]*\(snapshot (\d+)\.?\)")),
+    }
+}
@@ -1,16 +1,224 @@
 use anyhow::{Context, Result};
-use std::{fs, process::Command};
+use serde::Serialize;
+use std::{collections::BTreeMap, fs, process::Command};
 
-#[derive(Debug, Clone)]
+use crate::system::{self, Runner};
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Snapshot {
     pub id: u64,
     pub config: String,
     // 'type' is a reserved word in Rust; use 'kind' to represent snapper's Type column
+    #[serde(rename = "type")]
     pub kind: String,
     pub cleanup: String,
     pub user: String,
     pub date: String,
     pub description: String,
+    // For a post snapshot, the number of its pre snapshot
+    pub pre_num: Option<u64>,
+    // Raw "key=value, key=value" userdata
+    pub userdata: String,
+    // Snapper's `-` (mounted now), `+` (default for next boot) and `*` (both) markers
+    pub active: bool,
+    pub default: bool,
+    // Exclusive bytes from `snapper list --columns used-space`; only fetched when shown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub used_space: Option<u64>,
+}
+
+impl Snapshot {
+    pub fn userdata_value(&self, key: &str) -> Option<&str> {
+        self.userdata.split(',').find_map(|kv| {
+            let (k, v) = kv.split_once('=')?;
+            (k.trim() == key).then(|| v.trim())
+        })
+    }
+
+    /// Plain-text filter: `q` (lowercase) appears in any visible column.
+    pub fn matches(&self, q: &str) -> bool {
+        self.description.to_lowercase().contains(q)
+            || self.date.to_lowercase().contains(q)
+            || self.kind.to_lowercase().contains(q)
+            || self.cleanup.to_lowercase().contains(q)
+            || self.user.to_lowercase().contains(q)
+            || self.id.to_string().contains(q)
+    }
+
+    /// Marked `important=yes`, e.g. by zypper for kernel updates.
+    pub fn is_important(&self) -> bool {
+        self.userdata_value("important") == Some("yes")
+    }
+}
+
+/// A checked delete: what will be removed and what the user has to acknowledge first.
+#[derive(Debug, Clone, Default)]
+pub struct DeletePlan {
+    pub ids: Vec<u64>,
+    // Snapshots refused outright, with the reason
+    pub skipped: Vec<String>,
+    pub warnings: Vec<String>,
+    // Post snapshots that would lose their pre; `p` adds them to the plan
+    pub orphaned_posts: Vec<u64>,
+    // important=yes snapshots in `ids`; they need a second confirmation
+    pub important: Vec<u64>,
+    pub important_acked: bool,
+}
+
+/// Which snapshots the kernel reports as mounted and as the default subvolume.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BootState {
+    // None when the subvolume itself (not a snapshot) is mounted / default
+    pub active: Option<u64>,
+    pub default: Option<u64>,
+    // Whether each value could actually be read (get-default usually needs root)
+    pub active_known: bool,
+    pub default_known: bool,
+}
+
+/// `snapper status` output counted by category, with the first lines kept for display.
+#[derive(Debug, Clone, Default)]
+pub struct StatusSummary {
+    pub created: usize,
+    pub deleted: usize,
+    // Content or file type changed
+    pub modified: usize,
+    // Only permissions, owner, group, xattrs or ACLs changed
+    pub metadata: usize,
+    pub lines: Vec<String>,
+    // All lines seen, including the ones not kept
+    pub total: usize,
+}
+
+impl StatusSummary {
+    /// Count one status line ("c..... /path", "+..... /path", "....x. /path").
+    pub fn add(&mut self, line: String, keep: usize) {
+        match line.chars().next() {
+            Some('+') => self.created += 1,
+            Some('-') => self.deleted += 1,
+            Some('c' | 't') => self.modified += 1,
+            _ => self.metadata += 1,
+        }
+        self.total += 1;
+        if self.lines.len() < keep {
+            self.lines.push(line);
+        }
+    }
+}
+
+/// "ID 267 gen 1042 top level 5 path @/.snapshots/3/snapshot" -> 3
+pub fn snapshot_number_in_path(path: &str) -> Option<u64> {
+    let re = regex::Regex::new(r"\.snapshots/(\d+)/snapshot").ok()?;
+    re.captures(path)?.get(1)?.as_str().parse().ok()
+}
+
+// "42", "42-", "42+" or "42*" -> (id, active, default)
+fn parse_number(col: &str) -> Option<(u64, bool, bool)> {
+    let col = col.trim();
+    let (num, marker) = match col.chars().last()? {
+        c @ ('-' | '+' | '*') => (&col[..col.len() - 1], Some(c)),
+        _ => (col, None),
+    };
+    let id = num.trim().parse().ok()?;
+    Some((
+        id,
+        matches!(marker, Some('-' | '*')),
+        matches!(marker, Some('+' | '*')),
+    ))
+}
+
+// RFC 4180 records as snapper's --csvout writes them: quoted fields may hold commas,
+// doubled quotes and newlines
+fn csv_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            '\r' if !quoted => {}
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+// `snapper --csvout list`; columns are looked up by their header name
+fn parse_csv_list(config: &str, text: &str) -> Vec<Snapshot> {
+    let mut records = csv_records(text).into_iter();
+    let Some(header) = records.next() else {
+        return Vec::new();
+    };
+    let column = |name: &str| header.iter().position(|h| h.trim() == name);
+    records
+        .filter_map(|record| {
+            let get = |name: &str| {
+                column(name)
+                    .and_then(|i| record.get(i))
+                    .map(|v| v.trim())
+                    .unwrap_or("")
+            };
+            let id = get("number").parse().ok()?;
+            let (kind, cleanup) = (get("type"), get("cleanup"));
+            let description = match get("description") {
+                "" if !cleanup.is_empty() && cleanup != "-" => format!("[{cleanup}]"),
+                "" if !kind.is_empty() && kind != "-" => format!("[{kind}]"),
+                "" => String::from("(no description)"),
+                desc => desc.to_string(),
+            };
+            Some(Snapshot {
+                id,
+                config: config.to_string(),
+                kind: kind.to_string(),
+                cleanup: cleanup.to_string(),
+                user: get("user").to_string(),
+                date: get("date").to_string(),
+                description,
+                pre_num: get("pre-number").parse().ok(),
+                userdata: get("userdata").to_string(),
+                active: get("active") == "yes",
+                default: get("default") == "yes",
+                used_space: None,
+            })
+        })
+        .collect()
+}
+
+/// Parse snapper's Date column. The format follows the locale, so try the common ones;
+/// a trailing timezone name such as "CEST" is ignored.
+pub fn parse_date(date: &str) -> Option<chrono::NaiveDateTime> {
+    const FORMATS: [&str; 5] = [
+        "%Y-%m-%d %H:%M:%S",
+        "%a %d %b %Y %H:%M:%S",
+        "%a %b %d %H:%M:%S %Y",
+        "%a %d %b %Y %I:%M:%S %p",
+        "%d.%m.%Y %H:%M:%S",
+    ];
+    let date = date.trim();
+    let candidates = [
+        Some(date),
+        date.rsplit_once(' ').map(|(head, _)| head.trim_end()),
+    ];
+    candidates.into_iter().flatten().find_map(|d| {
+        FORMATS
+            .iter()
+            .find_map(|f| chrono::NaiveDateTime::parse_from_str(d, f).ok())
+    })
 }
 
 #[derive(Debug, Clone)]
@@ -21,19 +229,20 @@ pub struct Config {
 pub struct Snapper;
 
 impl Snapper {
-    fn run_snapper(args: &[&str], use_sudo: bool) -> Result<std::process::Output> {
-        let out = if use_sudo {
-            // Non-interactive: if sudo needs a password, fail fast so UI can show a hint.
-            Command::new("sudo")
-                .args(["-n", "snapper"])
-                .args(args)
-                .output()
-        } else {
-            Command::new("snapper").args(args).output()
-        }
-        .context("Failed to spawn snapper")?;
-        Ok(out)
+    fn run_snapper(args: &[&str], runner: &Runner) -> Result<std::process::Output> {
+        // Non-interactive escalation: if sudo needs a password, fail fast so the UI can prompt.
+        system::run("snapper", args, runner).context("Failed to run snapper")
     }
+    // State-changing snapper calls go through here so they land in the audit log
+    fn run_snapper_mutating(
+        op: &str,
+        config: &str,
+        args: &[&str],
+        runner: &Runner,
+    ) -> Result<std::process::Output> {
+        system::run_mutating(op, config, "snapper", args, runner).context("Failed to run snapper")
+    }
+
     pub fn available_configs_fs() -> Vec<String> {
         let mut v = Vec::new();
         if let Ok(entries) = fs::read_dir("/etc/snapper/configs") {
@@ -51,6 +260,15 @@ impl Snapper {
         let path = format!("/etc/snapper/configs/{name}");
         fs::metadata(&path).is_ok()
     }
+
+    /// SUBVOLUME from /etc/snapper/configs/<name>, e.g. "/" for the root config.
+    pub fn config_subvolume(name: &str) -> Option<String> {
+        let text = fs::read_to_string(format!("/etc/snapper/configs/{name}")).ok()?;
+        text.lines().find_map(|line| {
+            let value = line.trim().strip_prefix("SUBVOLUME=")?;
+            Some(value.trim_matches('"').to_string())
+        })
+    }
     pub fn list_configs() -> Result<Vec<Config>> {
         // Preferred: read names from /etc/snapper/configs (avoids headers/formatting)
         let mut fs_configs: Vec<String> = Vec::new();
@@ -77,7 +295,7 @@ impl Snapper {
             let hint = if err.to_ascii_lowercase().contains("permission")
                 || err.to_ascii_lowercase().contains("dbus")
             {
-                " (hint: try running with sudo)"
+                " (hint: press S to pick an escalation method)"
             } else {
                 ""
             };
@@ -121,26 +339,222 @@ impl Snapper {
         Ok(names.into_iter().map(|name| Config { name }).collect())
     }
 
-    pub fn list_snapshots(config: &str, use_sudo: bool) -> Result<Vec<Snapshot>> {
+    /// Snapshots of `config`, with active/default flags cross-checked against the kernel.
+    pub fn list_snapshots(config: &str, runner: &Runner) -> Result<Vec<Snapshot>> {
+        let mut snaps = Self::list_snapshots_table(config, runner)?;
+        if let Some(subvol) = Self::config_subvolume(config) {
+            let boot = Self::boot_state(&subvol, runner);
+            // The kernel's view wins; snapper's markers stay when it can't be read
+            for s in &mut snaps {
+                if boot.active_known {
+                    s.active = boot.active == Some(s.id);
+                }
+                if boot.default_known {
+                    s.default = boot.default == Some(s.id);
+                }
+            }
+        }
+        Ok(snaps)
+    }
+
+    /// Mounted and default snapshot of `subvol` via `findmnt` and `btrfs subvolume get-default`.
+    pub fn boot_state(subvol: &str, runner: &Runner) -> BootState {
+        let mut state = BootState::default();
+        if let Ok(line) = system::run_string("btrfs", &["subvolume", "get-default", subvol], runner)
+        {
+            state.default = snapshot_number_in_path(&line);
+            state.default_known = true;
+        }
+        let unprivileged = runner.unprivileged();
+        let fsroot = system::run_string(
+            "findmnt",
+            &["-n", "-o", "FSROOT", "-M", subvol],
+            &unprivileged,
+        )
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default();
+        if !fsroot.is_empty() && fsroot != "/" {
+            state.active = snapshot_number_in_path(&fsroot);
+            state.active_known = true;
+        } else if subvol == "/" {
+            // An overlayfs root (read-only snapshot boots) hides the subvolume; ask the kernel cmdline
+            if let Ok(cmdline) = fs::read_to_string("/proc/cmdline") {
+                state.active = snapshot_number_in_path(&cmdline);
+                state.active_known = cmdline.contains("subvol=");
+            }
+        }
+        state
+    }
+
+    /// Exclusive space used by each snapshot (id -> bytes). Needs a snapper with the
+    /// `used-space` column and btrfs quota (Q sets it up); snapshots without a value are left out.
+    pub fn used_space(config: &str, runner: &Runner) -> Result<BTreeMap<u64, u64>> {
+        let out = Self::run_snapper(
+            &[
+                "--csvout",
+                "-c",
+                config,
+                "list",
+                "--columns",
+                "number,used-space",
+            ],
+            runner,
+        )?;
+        if !out.status.success() {
+            anyhow::bail!(
+                "snapper list --columns used-space failed: {}",
+                String::from_utf8_lossy(&out.stderr).trim()
+            );
+        }
+        let stdout = String::from_utf8_lossy(&out.stdout);
+        // First line is the "number,used-space" header
+        Ok(stdout
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let (id, bytes) = line.split_once(',')?;
+                Some((id.trim().parse().ok()?, bytes.trim().parse().ok()?))
+            })
+            .collect())
+    }
+
+    /// Snapshots of `config` mounted somewhere right now (id -> mount point), from
+    /// /proc/self/mountinfo. Works for nested and flat `.snapshots` layouts alike.
+    pub fn mounted_snapshots(config: &str) -> BTreeMap<u64, String> {
+        let mut mounted = BTreeMap::new();
+        let Some(subvol) = Self::config_subvolume(config) else {
+            return mounted;
+        };
+        let Ok(info) = fs::read_to_string("/proc/self/mountinfo") else {
+            return mounted;
+        };
+        // (device, root within the filesystem, mount point)
+        let entries: Vec<(&str, String, String)> = info
+            .lines()
+            .filter_map(|line| {
+                let f: Vec<&str> = line.split(' ').collect();
+                let unescape = |s: &str| s.replace("\\040", " ");
+                Some((*f.get(2)?, unescape(f.get(3)?), unescape(f.get(4)?)))
+            })
+            .collect();
+        let snapdir = format!("{}/.snapshots", subvol.trim_end_matches('/'));
+        // The mount that .snapshots lives on; later mounts shadow earlier ones
+        let covers =
+            |mnt: &str| mnt == "/" || snapdir == mnt || snapdir.starts_with(&format!("{mnt}/"));
+        let Some((dev, root, mnt)) = entries
+            .iter()
+            .filter(|(_, _, mnt)| covers(mnt))
+            .max_by_key(|(_, _, mnt)| mnt.len())
+        else {
+            return mounted;
+        };
+        let rel = snapdir[mnt.trim_end_matches('/').len()..].trim_start_matches('/');
+        let mut base = root.trim_end_matches('/').to_string();
+        if !rel.is_empty() {
+            base.push('/');
+            base.push_str(rel);
+        }
+        base.push('/');
+        for (d, r, m) in &entries {
+            if d != dev {
+                continue;
+            }
+            let Some(rest) = r.strip_prefix(&base) else {
+                continue;
+            };
+            if let Some((num, tail)) = rest.split_once('/') {
+                if tail == "snapshot" || tail.starts_with("snapshot/") {
+                    if let Ok(id) = num.parse() {
+                        mounted.insert(id, m.clone());
+                    }
+                }
+            }
+        }
+        mounted
+    }
+
+    /// Check a delete: refuse snapshots the system depends on, flag broken pairs and
+    /// important ones. Shared by the TUI and the CLI.
+    pub fn plan_delete(config: &str, snapshots: &[Snapshot], ids: &[u64]) -> DeletePlan {
+        let mounted = Self::mounted_snapshots(config);
+        let mut plan = DeletePlan::default();
+        for &id in ids {
+            let Some(s) = snapshots.iter().find(|s| s.id == id) else {
+                plan.skipped.push(format!("#{id} does not exist"));
+                continue;
+            };
+            if id == 0 {
+                plan.skipped.push("#0 is the live filesystem".into());
+            } else if s.default {
+                plan.skipped
+                    .push(format!("#{id} is the default subvolume for the next boot"));
+            } else if s.active {
+                plan.skipped.push(format!("#{id} is the running system"));
+            } else if let Some(mp) = mounted.get(&id) {
+                plan.skipped
+                    .push(format!("#{id} is mounted at {mp}; unmount it first (U)"));
+            } else {
+                plan.ids.push(id);
+                if s.is_important() {
+                    plan.important.push(id);
+                }
+            }
+        }
+        for s in snapshots {
+            let Some(pre) = s.pre_num else {
+                continue;
+            };
+            if plan.ids.contains(&pre) && !plan.ids.contains(&s.id) {
+                plan.warnings.push(format!(
+                    "#{pre} is the pre snapshot of #{}; deleting it alone leaves #{} without a pair",
+                    s.id, s.id
+                ));
+                plan.orphaned_posts.push(s.id);
+            }
+        }
+        plan
+    }
+
+    fn list_snapshots_table(config: &str, runner: &Runner) -> Result<Vec<Snapshot>> {
         if !Self::config_exists(config) {
             anyhow::bail!("Unknown config '{config}' (not found in /etc/snapper/configs)");
         }
-        // Prefer a narrow, stable set of columns for robust parsing
-        // Columns (to match SnapperGUI layout): number | date | user | description | cleanup | type
+        // CSV quotes its fields, so a '|' or ',' in a description can't shift the pre-number
+        // and userdata columns the delete guards rely on
+        let csv = Self::run_snapper(
+            &[
+                "--csvout",
+                "-c",
+                config,
+                "list",
+                "--columns",
+                "number,default,active,date,user,cleanup,type,pre-number,userdata,description",
+            ],
+            runner,
+        )
+        .with_context(|| format!("Failed to run snapper list for config {config}"))?;
+        if csv.status.success() {
+            return Ok(parse_csv_list(
+                config,
+                &String::from_utf8_lossy(&csv.stdout),
+            ));
+        }
+        // Older snapper without --csvout: the table, with the free-text description last so
+        // a '|' in it stays inside the last field
         let out = Self::run_snapper(
             &[
                 "-c",
                 config,
                 "list",
                 "--columns",
-                "number,date,user,description,cleanup,type",
+                "number,date,user,cleanup,type,pre-number,userdata,description",
             ],
-            use_sudo,
+            runner,
         )
         .with_context(|| format!("Failed to run snapper list for config {config}"))?;
         if !out.status.success() {
             // Fallback for older snapper without --columns support: try plain 'list'
-            let fallback = Self::run_snapper(&["-c", config, "list"], use_sudo)?;
+            let fallback = Self::run_snapper(&["-c", config, "list"], runner)?;
             if !fallback.status.success() {
                 let err = String::from_utf8_lossy(&fallback.stderr);
                 let err_lower = err.to_ascii_lowercase();
@@ -148,13 +562,11 @@ impl Snapper {
                     || err_lower.contains("config not found")
                 {
                     " (hint: check your config name; see /etc/snapper/configs)"
-                } else if err_lower.contains("a password is required") && use_sudo {
-                    " (hint: run 'sudo -v' to cache credentials)"
                 } else if err_lower.contains("permission") || err_lower.contains("dbus") {
-                    if use_sudo {
+                    if runner.escalation.is_on() {
                         " (hint: run 'make sudo-run')"
                     } else {
-                        " (hint: try running with sudo)"
+                        " (hint: press S to pick an escalation method)"
                     }
                 } else {
                     ""
@@ -178,10 +590,12 @@ impl Snapper {
                 }
                 let parts: Vec<&str> = lt.split(['|', '│']).map(|s| s.trim()).collect();
                 if parts.len() >= 7 {
-                    if let Ok(id) = parts[0].parse::<u64>() {
+                    if let Some((id, active, default)) = parse_number(parts[0]) {
                         let kind = parts.get(1).copied().unwrap_or("").to_string();
+                        let pre_num = parts.get(2).and_then(|p| p.parse().ok());
                         let date = parts.get(3).unwrap_or(&"").to_string();
                         let cleanup = parts.get(5).copied().unwrap_or("").to_string();
+                        let userdata = parts.get(7).copied().unwrap_or("").to_string();
                         let mut description = parts.get(6).copied().unwrap_or("").to_string();
                         let user = String::new();
                         if description.is_empty() {
@@ -202,10 +616,15 @@ impl Snapper {
                             user,
                             date,
                             description,
+                            pre_num,
+                            userdata,
+                            active,
+                            default,
+                            used_space: None,
                         });
                     }
                 } else if parts.len() >= 4 {
-                    if let Ok(id) = parts[0].parse::<u64>() {
+                    if let Some((id, active, default)) = parse_number(parts[0]) {
                         let kind = parts.get(1).copied().unwrap_or("").to_string();
                         let date = parts.get(3).unwrap_or(&"").to_string();
                         let description = parts.last().copied().unwrap_or("").to_string();
@@ -224,6 +643,11 @@ impl Snapper {
                             user,
                             date,
                             description,
+                            pre_num: None,
+                            userdata: String::new(),
+                            active,
+                            default,
+                            used_space: None,
                         });
                     }
                 }
@@ -233,7 +657,7 @@ impl Snapper {
         let stdout = String::from_utf8_lossy(&out.stdout);
         let mut snaps = Vec::new();
         for line in stdout.lines() {
-            // Expected columns now: number | type | cleanup | date | description
+            // Expected columns: number | date | user | cleanup | type | pre-number | userdata | description
             let lt = line.trim();
             if lt.is_empty()
                 || lt.starts_with('#')
@@ -245,17 +669,20 @@ impl Snapper {
             {
                 continue;
             }
-            // Normalize to ASCII '|' and split into at most five fields: number | type | cleanup | date | description
+            // Normalize to ASCII '|' and split into at most eight fields; any '|' left over
+            // belongs to the description
             let normalized = lt.replace('│', "|");
-            let mut it = normalized.splitn(6, '|').map(|s| s.trim());
+            let mut it = normalized.splitn(8, '|').map(|s| s.trim());
             let c1 = it.next();
             let c2 = it.next(); // date
             let c3 = it.next(); // user
-            let c4 = it.next(); // description
             let c5 = it.next(); // cleanup
             let c6 = it.next(); // type
+            let pre_num = it.next().and_then(|p| p.parse().ok()); // pre-number
+            let userdata = it.next().unwrap_or("").to_string();
+            let c4 = it.next(); // description
             if let Some(id_str) = c1 {
-                if let Ok(id) = id_str.parse::<u64>() {
+                if let Some((id, active, default)) = parse_number(id_str) {
                     if let (Some(date), Some(user), Some(desc), Some(cleanup_col), Some(type_col)) =
                         (c2, c3, c4, c5, c6)
                     {
@@ -279,6 +706,11 @@ impl Snapper {
                             user: user.to_string(),
                             date: date.to_string(),
                             description,
+                            pre_num,
+                            userdata,
+                            active,
+                            default,
+                            used_space: None,
                         });
                     } else if let (Some(date), Some(desc)) = (c2, c3) {
                         // Fallback for three columns: number | date | description (older formats)
@@ -295,6 +727,11 @@ impl Snapper {
                             user: String::new(),
                             date: date.to_string(),
                             description,
+                            pre_num: None,
+                            userdata: String::new(),
+                            active,
+                            default,
+                            used_space: None,
                         });
                     }
                 }
@@ -303,41 +740,102 @@ impl Snapper {
         Ok(snaps)
     }
 
-    pub fn snapshot_status(config: &str, from: u64, to: u64, use_sudo: bool) -> Result<String> {
+    /// Stream `snapper status from..to` line by line; `on_line` returns false to stop early.
+    pub fn snapshot_status(
+        config: &str,
+        from: u64,
+        to: u64,
+        runner: &Runner,
+        on_line: impl FnMut(String) -> bool,
+    ) -> Result<()> {
         if !Self::config_exists(config) {
             anyhow::bail!("Unknown config '{config}' (not found in /etc/snapper/configs)");
         }
         let range = format!("{}..{}", from, to);
-        let out = Self::run_snapper(&["-c", config, "status", &range], use_sudo)
-            .with_context(|| format!("Failed to run snapper status for {config} {range}"))?;
-        if !out.status.success() {
-            let err = String::from_utf8_lossy(&out.stderr);
+        let out = system::stream(
+            "snapper",
+            &["-c", config, "status", &range],
+            runner,
+            on_line,
+        )
+        .with_context(|| format!("Failed to run snapper status for {config} {range}"))?;
+        if !out.stopped && !out.status.success() {
+            let err = out.stderr;
             let hint = if err.to_ascii_lowercase().contains("permission")
                 || err.to_ascii_lowercase().contains("dbus")
             {
-                " (hint: try running with sudo)"
+                " (hint: press S to pick an escalation method)"
             } else {
                 ""
             };
             anyhow::bail!("snapper status failed: {err}{hint}");
         }
-        Ok(String::from_utf8_lossy(&out.stdout).to_string())
+        Ok(())
+    }
+
+    /// Single snapshot; returns its number (0 in a dry run).
+    pub fn create(config: &str, description: &str, runner: &Runner) -> Result<u64> {
+        Self::create_numbered(config, &[], description, runner)
+    }
+
+    /// Pre half of a pair; returns its number (0 in a dry run, where nothing is created).
+    pub fn create_pre(config: &str, description: &str, runner: &Runner) -> Result<u64> {
+        Self::create_numbered(
+            config,
+            &["--type", "pre", "--cleanup-algorithm", "number"],
+            description,
+            runner,
+        )
+    }
+
+    pub fn create_post(config: &str, pre: u64, description: &str, runner: &Runner) -> Result<u64> {
+        let pre = pre.to_string();
+        Self::create_numbered(
+            config,
+            &[
+                "--type",
+                "post",
+                "--pre-number",
+                &pre,
+                "--cleanup-algorithm",
+                "number",
+            ],
+            description,
+            runner,
+        )
     }
 
-    pub fn create(config: &str, description: &str, use_sudo: bool) -> Result<()> {
-        let out = Self::run_snapper(&["-c", config, "create", "-d", description], use_sudo)
+    fn create_numbered(
+        config: &str,
+        extra: &[&str],
+        description: &str,
+        runner: &Runner,
+    ) -> Result<u64> {
+        let mut args = vec!["-c", config, "create", "--print-number"];
+        args.extend_from_slice(extra);
+        args.extend_from_slice(&["-d", description]);
+        let out = Self::run_snapper_mutating("create", config, &args, runner)
             .with_context(|| format!("Failed to run snapper create for {config}"))?;
         if !out.status.success() {
             let stderr = String::from_utf8_lossy(&out.stderr);
             anyhow::bail!("snapper create failed: {}", stderr);
         }
-        Ok(())
+        if runner.preview.is_some() {
+            return Ok(0);
+        }
+        let stdout = String::from_utf8_lossy(&out.stdout);
+        stdout
+            .trim()
+            .parse()
+            .with_context(|| format!("snapper create printed no snapshot number: {stdout}"))
     }
 
-    pub fn modify(config: &str, id: u64, description: &str, use_sudo: bool) -> Result<()> {
-        let out = Self::run_snapper(
+    pub fn modify(config: &str, id: u64, description: &str, runner: &Runner) -> Result<()> {
+        let out = Self::run_snapper_mutating(
+            "modify",
+            config,
             &["-c", config, "modify", &id.to_string(), "-d", description],
-            use_sudo,
+            runner,
         )
         .with_context(|| format!("Failed to run snapper modify for {config}#{id}"))?;
         if !out.status.success() {
@@ -347,9 +845,18 @@ impl Snapper {
         Ok(())
     }
 
-    pub fn delete(config: &str, id: u64, use_sudo: bool) -> Result<()> {
-        let out = Self::run_snapper(&["-c", config, "delete", &id.to_string()], use_sudo)
-            .with_context(|| format!("Failed to run snapper delete for {config}#{id}"))?;
+    /// Delete one or more snapshots in a single `snapper delete` call.
+    pub fn delete(config: &str, ids: &[u64], runner: &Runner) -> Result<()> {
+        let numbers: Vec<String> = ids.iter().map(u64::to_string).collect();
+        let mut args = vec!["-c", config, "delete"];
+        args.extend(numbers.iter().map(String::as_str));
+        let out =
+            Self::run_snapper_mutating("delete", config, &args, runner).with_context(|| {
+                format!(
+                    "Failed to run snapper delete for {config} {}",
+                    numbers.join(" ")
+                )
+            })?;
         if !out.status.success() {
             let stderr = String::from_utf8_lossy(&out.stderr);
             anyhow::bail!("snapper delete failed: {}", stderr);
@@ -357,27 +864,38 @@ impl Snapper {
         Ok(())
     }
 
-    pub fn diff(config: &str, from: u64, to: u64, use_sudo: bool) -> Result<String> {
+    /// Stream `snapper diff from..to` line by line; `on_line` returns false to stop early.
+    pub fn diff(
+        config: &str,
+        from: u64,
+        to: u64,
+        runner: &Runner,
+        on_line: impl FnMut(String) -> bool,
+    ) -> Result<()> {
         if !Self::config_exists(config) {
             anyhow::bail!("Unknown config '{config}' (not found in /etc/snapper/configs)");
         }
         let range = format!("{}..{}", from, to);
         // Use plain snapper diff; some setups do not allow passing extra flags.
-        let out = Self::run_snapper(&["-c", config, "diff", &range], use_sudo)
+        let out = system::stream("snapper", &["-c", config, "diff", &range], runner, on_line)
             .with_context(|| format!("Failed to run snapper diff for {config} {range}"))?;
-        if !out.status.success() {
-            let err = String::from_utf8_lossy(&out.stderr);
-            anyhow::bail!("snapper diff failed: {err}");
+        if !out.stopped && !out.status.success() {
+            anyhow::bail!("snapper diff failed: {}", out.stderr);
         }
-        Ok(String::from_utf8_lossy(&out.stdout).to_string())
+        Ok(())
     }
 
-    pub fn mount(config: &str, id: u64, use_sudo: bool) -> Result<String> {
+    pub fn mount(config: &str, id: u64, runner: &Runner) -> Result<String> {
         if !Self::config_exists(config) {
             anyhow::bail!("Unknown config '{config}' (not found in /etc/snapper/configs)");
         }
-        let out = Self::run_snapper(&["-c", config, "mount", &id.to_string()], use_sudo)
-            .with_context(|| format!("Failed to run snapper mount for {config}#{id}"))?;
+        let out = Self::run_snapper_mutating(
+            "mount",
+            config,
+            &["-c", config, "mount", &id.to_string()],
+            runner,
+        )
+        .with_context(|| format!("Failed to run snapper mount for {config}#{id}"))?;
         if !out.status.success() {
             let err = String::from_utf8_lossy(&out.stderr);
             anyhow::bail!("snapper mount failed: {err}");
@@ -385,12 +903,17 @@ impl Snapper {
         Ok(String::from_utf8_lossy(&out.stdout).to_string())
     }
 
-    pub fn umount(config: &str, id: u64, use_sudo: bool) -> Result<()> {
+    pub fn umount(config: &str, id: u64, runner: &Runner) -> Result<()> {
         if !Self::config_exists(config) {
             anyhow::bail!("Unknown config '{config}' (not found in /etc/snapper/configs)");
         }
-        let out = Self::run_snapper(&["-c", config, "umount", &id.to_string()], use_sudo)
-            .with_context(|| format!("Failed to run snapper umount for {config}#{id}"))?;
+        let out = Self::run_snapper_mutating(
+            "umount",
+            config,
+            &["-c", config, "umount", &id.to_string()],
+            runner,
+        )
+        .with_context(|| format!("Failed to run snapper umount for {config}#{id}"))?;
         if !out.status.success() {
             let err = String::from_utf8_lossy(&out.stderr);
             anyhow::bail!("snapper umount failed: {err}");
@@ -398,12 +921,17 @@ impl Snapper {
         Ok(())
     }
 
-    pub fn rollback(config: &str, id: u64, use_sudo: bool) -> Result<String> {
+    pub fn rollback(config: &str, id: u64, runner: &Runner) -> Result<String> {
         if !Self::config_exists(config) {
             anyhow::bail!("Unknown config '{config}' (not found in /etc/snapper/configs)");
         }
-        let out = Self::run_snapper(&["-c", config, "rollback", &id.to_string()], use_sudo)
-            .with_context(|| format!("Failed to run snapper rollback for {config}#{id}"))?;
+        let out = Self::run_snapper_mutating(
+            "rollback",
+            config,
+            &["-c", config, "rollback", &id.to_string()],
+            runner,
+        )
+        .with_context(|| format!("Failed to run snapper rollback for {config}#{id}"))?;
         if !out.status.success() {
             let err = String::from_utf8_lossy(&out.stderr);
             anyhow::bail!("snapper rollback failed: {err}");
@@ -411,12 +939,17 @@ impl Snapper {
         Ok(String::from_utf8_lossy(&out.stdout).to_string())
     }
 
-    pub fn cleanup(config: &str, algorithm: &str, use_sudo: bool) -> Result<String> {
+    pub fn cleanup(config: &str, algorithm: &str, runner: &Runner) -> Result<String> {
         if !Self::config_exists(config) {
             anyhow::bail!("Unknown config '{config}' (not found in /etc/snapper/configs)");
         }
-        let out = Self::run_snapper(&["-c", config, "cleanup", algorithm], use_sudo)
-            .with_context(|| format!("Failed to run snapper cleanup {algorithm} for {config}"))?;
+        let out = Self::run_snapper_mutating(
+            "cleanup",
+            config,
+            &["-c", config, "cleanup", algorithm],
+            runner,
+        )
+        .with_context(|| format!("Failed to run snapper cleanup {algorithm} for {config}"))?;
         if !out.status.success() {
             let err = String::from_utf8_lossy(&out.stderr);
             anyhow::bail!("snapper cleanup failed: {err}");
@@ -424,11 +957,11 @@ impl Snapper {
         Ok(String::from_utf8_lossy(&out.stdout).to_string())
     }
 
-    pub fn get_config(config: &str, use_sudo: bool) -> Result<String> {
+    pub fn get_config(config: &str, runner: &Runner) -> Result<String> {
         if !Self::config_exists(config) {
             anyhow::bail!("Unknown config '{config}' (not found in /etc/snapper/configs)");
         }
-        let out = Self::run_snapper(&["-c", config, "get-config"], use_sudo)
+        let out = Self::run_snapper(&["-c", config, "get-config"], runner)
             .with_context(|| format!("Failed to run snapper get-config for {config}"))?;
         if !out.status.success() {
             let err = String::from_utf8_lossy(&out.stderr);
@@ -437,7 +970,7 @@ impl Snapper {
         Ok(String::from_utf8_lossy(&out.stdout).to_string())
     }
 
-    pub fn set_config(config: &str, kv_pairs: &[String], use_sudo: bool) -> Result<String> {
+    pub fn set_config(config: &str, kv_pairs: &[String], runner: &Runner) -> Result<String> {
         if !Self::config_exists(config) {
             anyhow::bail!("Unknown config '{config}' (not found in /etc/snapper/configs)");
         }
@@ -445,7 +978,7 @@ impl Snapper {
         for kv in kv_pairs.iter() {
             args.push(kv.as_str());
         }
-        let out = Self::run_snapper(&args, use_sudo)
+        let out = Self::run_snapper_mutating("set-config", config, &args, runner)
             .with_context(|| format!("Failed to run snapper set-config for {config}"))?;
         if !out.status.success() {
             let err = String::from_utf8_lossy(&out.stderr);
@@ -454,12 +987,17 @@ impl Snapper {
         Ok(String::from_utf8_lossy(&out.stdout).to_string())
     }
 
-    pub fn setup_quota(config: &str, use_sudo: bool) -> Result<String> {
+    pub fn setup_quota(config: &str, runner: &Runner) -> Result<String> {
         if !Self::config_exists(config) {
             anyhow::bail!("Unknown config '{config}' (not found in /etc/snapper/configs)");
         }
-        let out = Self::run_snapper(&["-c", config, "setup-quota"], use_sudo)
-            .with_context(|| format!("Failed to run snapper setup-quota for {config}"))?;
+        let out = Self::run_snapper_mutating(
+            "setup-quota",
+            config,
+            &["-c", config, "setup-quota"],
+            runner,
+        )
+        .with_context(|| format!("Failed to run snapper setup-quota for {config}"))?;
         if !out.status.success() {
             let err = String::from_utf8_lossy(&out.stderr);
             anyhow::bail!("snapper setup-quota failed: {err}");
@@ -1,18 +1,43 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::Write;
 use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::escalation::Escalation;
+use crate::view::{Column, Sort, View};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct State {
-    pub use_sudo: bool,
+    pub escalation: Escalation,
+    // Older state files stored a sudo on/off flag; read once and mapped onto `escalation`
+    #[serde(skip_serializing)]
+    pub use_sudo: Option<bool>,
     pub last_config: Option<String>,
     pub filter: Option<String>,
     pub show_userdata: bool,
+    // Table layout used when a config has no named view selected; empty columns means all
+    pub sort: Sort,
+    pub columns: Vec<Column>,
+    // Named views per config name, and the one each config last had selected
+    pub views: BTreeMap<String, Vec<View>>,
+    pub active_view: BTreeMap<String, String>,
+    // Viewer mode: no create/delete/rollback/config changes (mount/umount stay available)
+    pub read_only: bool,
+    // Vi keys (j/k, gg/G, / search, :, marks) in the snapshot table and details view
+    pub vi_keys: bool,
+    // Change list of the selected snapshot next to the table
+    pub show_preview: bool,
+    // Color theme: a built-in name or themes/<name>.toml in the config dir
+    pub theme: Option<String>,
+    // Seconds before an operation is terminated, keyed by operation name
+    // (e.g. "diff", "rollback", "limine-sync"); missing or 0 means no timeout.
+    pub op_timeouts: BTreeMap<String, u64>,
 }
 
-fn config_dir() -> PathBuf {
+pub fn config_dir() -> PathBuf {
     if let Ok(xdg) = std::env::var("XDG_CONFIG_HOME") {
         let mut p = PathBuf::from(xdg);
         p.push("snapper-tui");
@@ -28,7 +53,36 @@ fn config_dir() -> PathBuf {
     PathBuf::from(".snapper-tui")
 }
 
+/// `$XDG_STATE_HOME/snapper-tui` (default `~/.local/state/snapper-tui`) for logs and history.
+pub fn state_dir() -> PathBuf {
+    if let Ok(xdg) = std::env::var("XDG_STATE_HOME") {
+        let mut p = PathBuf::from(xdg);
+        p.push("snapper-tui");
+        return p;
+    }
+    if let Ok(home) = std::env::var("HOME") {
+        let mut p = PathBuf::from(home);
+        p.push(".local");
+        p.push("state");
+        p.push("snapper-tui");
+        return p;
+    }
+    // fallback: current directory
+    PathBuf::from(".snapper-tui")
+}
+
+// Set once from --state-file before anything loads or saves state
+static STATE_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `path` instead of `state.json` in the config dir for this run.
+pub fn set_state_file(path: PathBuf) {
+    let _ = STATE_FILE.set(path);
+}
+
 fn state_path() -> PathBuf {
+    if let Some(path) = STATE_FILE.get() {
+        return path.clone();
+    }
     let mut p = config_dir();
     p.push("state.json");
     p
@@ -38,7 +92,10 @@ impl State {
     pub fn load() -> Self {
         let path = state_path();
         if let Ok(data) = fs::read(&path) {
-            if let Ok(s) = serde_json::from_slice::<State>(&data) {
+            if let Ok(mut s) = serde_json::from_slice::<State>(&data) {
+                if s.use_sudo.take() == Some(true) && s.escalation == Escalation::Off {
+                    s.escalation = Escalation::Sudo;
+                }
                 return s;
             }
         }
@@ -46,9 +103,10 @@ impl State {
     }
 
     pub fn save(&self) {
-        let dir = config_dir();
-        let _ = fs::create_dir_all(&dir);
         let path = state_path();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            let _ = fs::create_dir_all(dir);
+        }
         if let Ok(json) = serde_json::to_vec_pretty(self) {
             if let Ok(mut f) = fs::File::create(path) {
                 let _ = f.write_all(&json);
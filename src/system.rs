@@ -1,32 +1,523 @@
 use anyhow::{Context, Result};
-use std::process::Command;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::audit::{self, AuditEntry};
+use crate::escalation::{self, Escalation};
+
+// Time between SIGTERM and SIGKILL when terminating a process group
+const KILL_GRACE: Duration = Duration::from_secs(3);
 
 pub fn has_cmd(cmd: &str) -> bool {
     which::which(cmd).is_ok()
 }
 
-pub fn run(cmd: &str, args: &[&str], use_sudo: bool) -> Result<std::process::Output> {
-    if use_sudo {
-        let out = Command::new("sudo")
-            .arg("-n")
-            .arg(cmd)
-            .args(args)
-            .output()
-            .with_context(|| format!("Failed to run sudo {cmd}"))?;
-        Ok(out)
+/// Why a command did not run to completion.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum Interrupted {
+    #[error("cancelled")]
+    Cancelled,
+    #[error("timed out after {0}s")]
+    TimedOut(u64),
+}
+
+/// Find an `Interrupted` cause anywhere in an error chain.
+pub fn interrupted(err: &anyhow::Error) -> Option<&Interrupted> {
+    err.chain().find_map(|e| e.downcast_ref::<Interrupted>())
+}
+
+/// A state-changing command was refused because the runner is read-only.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("read-only mode: {0} is not allowed")]
+pub struct ReadOnly(pub String);
+
+// Operations a read-only runner still performs: mounting only exposes a snapshot for browsing
+const READ_ONLY_ALLOWED: [&str; 2] = ["mount", "umount"];
+
+/// The escalation method refused to run without asking for credentials.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("authentication required")]
+pub struct AuthRequired;
+
+pub fn auth_required(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|e| e.downcast_ref::<AuthRequired>().is_some())
+}
+
+// Also catches escalation embedded in `sh -c` pipelines, whatever the runner's method
+fn needs_password(status: &ExitStatus, stderr: &[u8]) -> bool {
+    !status.success() && escalation::needs_auth(&String::from_utf8_lossy(stderr))
+}
+
+/// Validate and cache sudo credentials, feeding the password on stdin (`sudo -S -v`).
+/// The password only lives in memory for the duration of this call.
+pub fn sudo_authenticate(password: &str) -> Result<()> {
+    let mut child = Command::new("sudo")
+        .args(["-S", "-v", "-p", ""])
+        .process_group(0)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to spawn sudo")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let mut line = Vec::with_capacity(password.len() + 1);
+        line.extend_from_slice(password.as_bytes());
+        line.push(b'\n');
+        let _ = stdin.write_all(&line);
+        // best effort: don't leave a copy around in freed memory
+        line.fill(0);
+        // closing stdin makes sudo give up instead of asking again after a wrong password
+    }
+    let out = child
+        .wait_with_output()
+        .context("Failed to wait for sudo")?;
+    if out.status.success() {
+        return Ok(());
+    }
+    let err = String::from_utf8_lossy(&out.stderr);
+    let reason = err
+        .lines()
+        .map(str::trim)
+        .rfind(|l| !l.is_empty())
+        .unwrap_or("authentication failed");
+    anyhow::bail!("{reason}")
+}
+
+#[derive(Debug, Default)]
+struct HandleState {
+    // process groups of children currently running under this handle
+    pgids: Vec<i32>,
+    cancelled: bool,
+    timed_out: bool,
+    deadline: Option<Instant>,
+    timeout: Option<Duration>,
+}
+
+/// Shared handle to the processes of one operation. Cloned into the UI so it can
+/// terminate the whole process group (sudo and its snapper child included).
+#[derive(Clone, Debug, Default)]
+pub struct ProcHandle {
+    state: Arc<Mutex<HandleState>>,
+}
+
+impl ProcHandle {
+    /// A handle whose operation is terminated once `timeout` has elapsed from now.
+    pub fn with_timeout(timeout: Option<Duration>) -> Self {
+        let h = Self::default();
+        if let Some(t) = timeout {
+            let mut st = h.lock();
+            st.deadline = Some(Instant::now() + t);
+            st.timeout = Some(t);
+        }
+        h
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HandleState> {
+        self.state.lock().unwrap_or_else(|p| p.into_inner())
+    }
+
+    /// Terminate everything running under this handle; later spawns fail immediately.
+    pub fn cancel(&self) {
+        let pgids = {
+            let mut st = self.lock();
+            st.cancelled = true;
+            st.pgids.clone()
+        };
+        self.terminate(pgids);
+    }
+
+    fn check(&self) -> Result<(), Interrupted> {
+        let st = self.lock();
+        if st.timed_out {
+            Err(Interrupted::TimedOut(
+                st.timeout.map(|t| t.as_secs()).unwrap_or(0),
+            ))
+        } else if st.cancelled {
+            Err(Interrupted::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn track(&self, child: &Child) {
+        let pgid = child.id() as i32;
+        let (deadline, cancelled) = {
+            let mut st = self.lock();
+            st.pgids.push(pgid);
+            (st.deadline, st.cancelled)
+        };
+        // Cancel raced with spawn: the group wasn't tracked yet when cancel() ran
+        if cancelled {
+            self.terminate(vec![pgid]);
+        }
+        if let Some(deadline) = deadline {
+            let h = self.clone();
+            thread::spawn(move || loop {
+                if !h.lock().pgids.contains(&pgid) {
+                    return;
+                }
+                if Instant::now() >= deadline {
+                    h.lock().timed_out = true;
+                    h.terminate(vec![pgid]);
+                    return;
+                }
+                thread::sleep(Duration::from_millis(100));
+            });
+        }
+    }
+
+    /// Wait for `child` and stop tracking its group before reaping it. Until it is reaped
+    /// the leader is a zombie whose pid can't be reused, so a tracked pgid is always ours.
+    fn wait(&self, child: &mut Child) -> std::io::Result<ExitStatus> {
+        let pid = child.id();
+        loop {
+            // SAFETY: zeroed siginfo_t is a valid out-parameter; WNOWAIT leaves the child unreaped
+            let rc = unsafe {
+                let mut info: libc::siginfo_t = std::mem::zeroed();
+                libc::waitid(
+                    libc::P_PID,
+                    pid as libc::id_t,
+                    &mut info,
+                    libc::WEXITED | libc::WNOWAIT,
+                )
+            };
+            if rc == 0 {
+                break;
+            }
+            let err = std::io::Error::last_os_error();
+            if err.kind() != std::io::ErrorKind::Interrupted {
+                self.untrack(pid as i32);
+                return Err(err);
+            }
+        }
+        self.untrack(pid as i32);
+        child.wait()
+    }
+
+    fn untrack(&self, pgid: i32) {
+        self.lock().pgids.retain(|p| *p != pgid);
+    }
+
+    // Signal the groups in `pgids` that are still tracked. The check and the kill happen under
+    // the lock, so a group untracked (and then reaped) in between is never signalled.
+    fn signal(&self, pgids: &[i32], sig: libc::c_int) {
+        let st = self.lock();
+        for pg in pgids.iter().filter(|p| st.pgids.contains(p)) {
+            // SAFETY: plain syscall; a tracked pgid belongs to a child we have not reaped yet
+            unsafe {
+                libc::killpg(*pg, sig);
+            }
+        }
+    }
+
+    fn terminate(&self, pgids: Vec<i32>) {
+        if pgids.is_empty() {
+            return;
+        }
+        self.signal(&pgids, libc::SIGTERM);
+        // Escalate for anything that ignores SIGTERM
+        let h = self.clone();
+        thread::spawn(move || {
+            thread::sleep(KILL_GRACE);
+            h.signal(&pgids, libc::SIGKILL);
+        });
+    }
+}
+
+/// Command lines a dry run would have executed, in order.
+#[derive(Clone, Debug, Default)]
+pub struct Preview {
+    lines: Arc<Mutex<Vec<String>>>,
+}
+
+impl Preview {
+    fn push(&self, line: String) {
+        self.lines
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .push(line);
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.lock().unwrap_or_else(|p| p.into_inner()).clone()
+    }
+}
+
+/// Execution context for snapper/Limine calls: privilege escalation plus the handle
+/// used to cancel or time out the operation.
+#[derive(Clone, Debug, Default)]
+pub struct Runner {
+    pub escalation: Escalation,
+    pub handle: ProcHandle,
+    // Dry run: mutating commands are recorded here instead of executed
+    pub preview: Option<Preview>,
+    // Refuse every state-changing command except mount/umount
+    pub read_only: bool,
+}
+
+impl Runner {
+    pub fn new(escalation: Escalation, timeout: Option<Duration>) -> Self {
+        Self {
+            escalation,
+            handle: ProcHandle::with_timeout(timeout),
+            preview: None,
+            read_only: false,
+        }
+    }
+
+    /// Same handle without escalation, for probes and pipelines that embed their own prefix.
+    pub fn unprivileged(&self) -> Self {
+        Self {
+            escalation: Escalation::Off,
+            handle: self.handle.clone(),
+            preview: self.preview.clone(),
+            read_only: self.read_only,
+        }
+    }
+
+    /// Full argv as executed, escalation prefix included.
+    pub fn argv(&self, cmd: &str, args: &[&str]) -> Vec<String> {
+        self.escalation
+            .prefix()
+            .iter()
+            .chain([cmd].iter())
+            .chain(args.iter())
+            .map(|a| a.to_string())
+            .collect()
+    }
+
+    fn command(&self, cmd: &str, args: &[&str]) -> Command {
+        let mut c = match self.escalation.prefix() {
+            [prog, flags @ ..] => {
+                let mut c = Command::new(prog);
+                c.args(flags).arg(cmd);
+                c
+            }
+            [] => Command::new(cmd),
+        };
+        // Own process group so cancel can take down sudo and everything below it
+        c.args(args)
+            .process_group(0)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        c
+    }
+
+    fn spawn(&self, cmd: &str, args: &[&str]) -> Result<Child> {
+        self.handle.check()?;
+        let child = self
+            .command(cmd, args)
+            .spawn()
+            .with_context(|| format!("Failed to spawn {cmd}"))?;
+        self.handle.track(&child);
+        Ok(child)
+    }
+}
+
+fn read_all(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut p) = pipe {
+            let _ = p.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+pub fn run(cmd: &str, args: &[&str], runner: &Runner) -> Result<std::process::Output> {
+    let mut child = runner.spawn(cmd, args)?;
+    let out = read_all(child.stdout.take());
+    let err = read_all(child.stderr.take());
+    let status = runner.handle.wait(&mut child);
+    let status = status.with_context(|| format!("Failed to wait for {cmd}"))?;
+    runner.handle.check()?;
+    let stderr = err.join().unwrap_or_default();
+    if needs_password(&status, &stderr) {
+        return Err(AuthRequired.into());
+    }
+    Ok(std::process::Output {
+        status,
+        stdout: out.join().unwrap_or_default(),
+        stderr,
+    })
+}
+
+/// Run a shell command line on the real terminal, unescalated, and wait for it.
+/// The caller must have left raw mode. Ctrl-C reaches the command, not us.
+pub fn run_interactive(command_line: &str) -> Result<ExitStatus> {
+    // SAFETY: only swaps signal dispositions; restored below before returning
+    let (old_int, old_quit) = unsafe {
+        (
+            libc::signal(libc::SIGINT, libc::SIG_IGN),
+            libc::signal(libc::SIGQUIT, libc::SIG_IGN),
+        )
+    };
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command_line);
+    // SAFETY: signal() is async-signal-safe; ignored dispositions would survive exec otherwise
+    unsafe {
+        cmd.pre_exec(|| {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+            libc::signal(libc::SIGQUIT, libc::SIG_DFL);
+            Ok(())
+        });
+    }
+    let status = cmd.status().context("Failed to run sh");
+    unsafe {
+        libc::signal(libc::SIGINT, old_int);
+        libc::signal(libc::SIGQUIT, old_quit);
+    }
+    status
+}
+
+/// Run a state-changing command and record it in the audit log, whatever the outcome.
+/// `op` and `config` only label the entry (e.g. "rollback", "root").
+/// In a dry run the command line is added to the preview and reported as succeeded.
+pub fn run_mutating(
+    op: &str,
+    config: &str,
+    cmd: &str,
+    args: &[&str],
+    runner: &Runner,
+) -> Result<std::process::Output> {
+    // Checked here, below the UI, so no code path can get around it
+    if runner.read_only && !READ_ONLY_ALLOWED.contains(&op) {
+        return Err(ReadOnly(op.to_string()).into());
+    }
+    let argv = runner.argv(cmd, args);
+    if let Some(preview) = &runner.preview {
+        preview.push(command_line(&argv));
+        return Ok(std::process::Output {
+            status: ExitStatus::from_raw(0),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        });
+    }
+    let res = run(cmd, args, runner);
+    // Pipelines run unprivileged and embed the prefix in their `sh -c` script
+    let escalation = if runner.escalation.is_on() {
+        runner.escalation
     } else {
-        Command::new(cmd)
-            .args(args)
-            .output()
-            .with_context(|| format!("Failed to run {cmd}"))
+        let joined = argv.join(" ");
+        Escalation::ALL
+            .into_iter()
+            .find(|e| e.is_on() && joined.contains(&e.shell_prefix()))
+            .unwrap_or_default()
+    };
+    let mut entry = AuditEntry {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        user: audit::current_user(),
+        escalation: escalation.name().to_string(),
+        config: config.to_string(),
+        op: op.to_string(),
+        argv,
+        ..AuditEntry::default()
+    };
+    match &res {
+        Ok(out) => {
+            entry.exit_code = out.status.code();
+            entry.stderr = audit::stderr_excerpt(&out.stderr);
+            if entry.exit_code.is_none() {
+                entry.error = Some("killed by signal".into());
+            }
+        }
+        Err(e) => entry.error = Some(format!("{e:#}")),
     }
+    // Losing an audit line must not turn a completed change into a reported failure
+    let _ = audit::append(&entry);
+    res
 }
 
-pub fn run_string(cmd: &str, args: &[&str], use_sudo: bool) -> Result<String> {
-    let out = run(cmd, args, use_sudo)?;
+/// argv as a copy-pasteable shell command line.
+pub fn command_line(argv: &[String]) -> String {
+    argv.iter()
+        .map(|a| shell_escape::escape(a.as_str().into()).into_owned())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn run_string(cmd: &str, args: &[&str], runner: &Runner) -> Result<String> {
+    let out = run(cmd, args, runner)?;
     if !out.status.success() {
         let err = String::from_utf8_lossy(&out.stderr);
         anyhow::bail!("{} failed: {}", cmd, err.trim());
     }
     Ok(String::from_utf8_lossy(&out.stdout).to_string())
 }
+
+/// `run_string` for state-changing commands; see `run_mutating`.
+pub fn run_string_mutating(
+    op: &str,
+    config: &str,
+    cmd: &str,
+    args: &[&str],
+    runner: &Runner,
+) -> Result<String> {
+    let out = run_mutating(op, config, cmd, args, runner)?;
+    if !out.status.success() {
+        let err = String::from_utf8_lossy(&out.stderr);
+        anyhow::bail!("{} failed: {}", cmd, err.trim());
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).to_string())
+}
+
+/// Result of a streamed command: exit status plus captured stderr.
+pub struct StreamOutput {
+    pub status: ExitStatus,
+    pub stderr: String,
+    // consumer asked to stop early; the child was killed and its status is not meaningful
+    pub stopped: bool,
+}
+
+/// Run a command and hand each stdout line to `on_line` as it arrives.
+/// Returning `false` from `on_line` stops reading and kills the child.
+pub fn stream(
+    cmd: &str,
+    args: &[&str],
+    runner: &Runner,
+    mut on_line: impl FnMut(String) -> bool,
+) -> Result<StreamOutput> {
+    let mut child = runner.spawn(cmd, args)?;
+    // Drain stderr concurrently so a chatty child can't block on a full pipe
+    let err_reader = read_all(child.stderr.take());
+    let mut stopped = false;
+    if let Some(out) = child.stdout.take() {
+        let mut reader = BufReader::new(out);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if buf.last() == Some(&b'\n') {
+                        buf.pop();
+                    }
+                    if !on_line(String::from_utf8_lossy(&buf).into_owned()) {
+                        stopped = true;
+                        runner.handle.terminate(vec![child.id() as i32]);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    let status = runner.handle.wait(&mut child);
+    let status = status.with_context(|| format!("Failed to wait for {cmd}"))?;
+    runner.handle.check()?;
+    let stderr = err_reader.join().unwrap_or_default();
+    if needs_password(&status, &stderr) {
+        return Err(AuthRequired.into());
+    }
+    let stderr = String::from_utf8_lossy(&stderr).into_owned();
+    Ok(StreamOutput {
+        status,
+        stderr,
+        stopped,
+    })
+}
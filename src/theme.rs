@@ -6,8 +6,16 @@ use ratatui::{
         Block, Borders,
     },
 };
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug)]
+use crate::state;
+
+/// Colors of the UI. A color of `Reset` leaves the terminal's own color; where that would make
+/// the muted text or the selection indistinguishable, the styles fall back to dim and reverse.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Theme {
     pub bg: Color,
     pub fg: Color,
@@ -19,10 +27,20 @@ pub struct Theme {
     pub header_fg: Color,
     pub border: Color,
     pub highlight: Color,
+    pub highlight_bg: Color,
 }
 
+/// Built-in themes, in the order the theme action cycles through them.
+pub const BUILTIN: [&str; 4] = ["dark", "light", "high-contrast", "no-color"];
+
 impl Default for Theme {
     fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
         Self {
             bg: Color::Reset,
             fg: Color::Gray,
@@ -34,11 +52,70 @@ impl Default for Theme {
             header_fg: Color::White,
             border: Color::Gray,
             highlight: Color::Cyan,
+            highlight_bg: Color::Reset,
+        }
+    }
+
+    // For light terminal backgrounds: no yellow or cyan text
+    pub fn light() -> Self {
+        Self {
+            bg: Color::Reset,
+            fg: Color::Black,
+            muted: Color::Indexed(244),
+            accent: Color::Blue,
+            warn: Color::Indexed(130),
+            error: Color::Red,
+            header_bg: Color::Indexed(254),
+            header_fg: Color::Black,
+            border: Color::Indexed(244),
+            highlight: Color::White,
+            highlight_bg: Color::Blue,
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            bg: Color::Black,
+            fg: Color::White,
+            muted: Color::Gray,
+            accent: Color::LightCyan,
+            warn: Color::LightYellow,
+            error: Color::LightRed,
+            header_bg: Color::Black,
+            header_fg: Color::LightYellow,
+            border: Color::White,
+            highlight: Color::Black,
+            highlight_bg: Color::LightYellow,
+        }
+    }
+
+    // NO_COLOR: only bold, dim and reverse
+    pub fn no_color() -> Self {
+        Self {
+            bg: Color::Reset,
+            fg: Color::Reset,
+            muted: Color::Reset,
+            accent: Color::Reset,
+            warn: Color::Reset,
+            error: Color::Reset,
+            header_bg: Color::Reset,
+            header_fg: Color::Reset,
+            border: Color::Reset,
+            highlight: Color::Reset,
+            highlight_bg: Color::Reset,
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            "no-color" => Some(Theme::no_color()),
+            _ => None,
         }
     }
-}
 
-impl Theme {
     pub fn block<'a>(&self, title: impl Into<String>) -> Block<'a> {
         Block::default()
             .title(title.into())
@@ -54,6 +131,7 @@ impl Theme {
             .border_type(BorderType::Rounded)
             .padding(Padding::new(2, 2, 1, 1))
             .border_style(Style::default().fg(self.border))
+            .style(self.text_style())
     }
 
     pub fn modal_warn_block<'a>(&self, title: impl Into<String>) -> Block<'a> {
@@ -68,18 +146,46 @@ impl Theme {
 
     // inner_block removed (unused)
 
+    /// Plain text on the theme background.
+    pub fn text_style(&self) -> Style {
+        Style::default().fg(self.fg).bg(self.bg)
+    }
+    /// Field names such as "Date:" in front of a value.
+    pub fn label_style(&self) -> Style {
+        Style::default().fg(self.fg).add_modifier(Modifier::BOLD)
+    }
     pub fn header_style(&self) -> Style {
         Style::default()
             .fg(self.header_fg)
             .add_modifier(Modifier::BOLD)
     }
+    /// Table header rows and other bars.
+    pub fn header_bar_style(&self) -> Style {
+        self.header_style().bg(self.header_bg)
+    }
+    pub fn accent_style(&self) -> Style {
+        Style::default()
+            .fg(self.accent)
+            .add_modifier(Modifier::BOLD)
+    }
     pub fn muted_style(&self) -> Style {
-        Style::default().fg(self.muted)
+        let style = Style::default().fg(self.muted);
+        if self.muted == self.fg {
+            style.add_modifier(Modifier::DIM)
+        } else {
+            style
+        }
     }
     pub fn highlight_style(&self) -> Style {
-        Style::default()
+        let style = Style::default()
             .fg(self.highlight)
-            .add_modifier(Modifier::BOLD)
+            .bg(self.highlight_bg)
+            .add_modifier(Modifier::BOLD);
+        if self.highlight == self.fg && self.highlight_bg == self.bg {
+            style.add_modifier(Modifier::REVERSED)
+        } else {
+            style
+        }
     }
     pub fn warn_style(&self) -> Style {
         Style::default().fg(self.warn)
@@ -87,17 +193,88 @@ impl Theme {
     pub fn error_style(&self) -> Style {
         Style::default().fg(self.error)
     }
+    pub fn scrollbar_track_style(&self) -> Style {
+        Style::default().bg(self.header_bg)
+    }
+    pub fn scrollbar_thumb_style(&self) -> Style {
+        Style::default().fg(self.accent)
+    }
 }
 
-pub static THEME: Theme = Theme {
-    bg: Color::Reset,
-    fg: Color::Gray,
-    muted: Color::DarkGray,
-    accent: Color::Cyan,
-    warn: Color::Yellow,
-    error: Color::Red,
-    header_bg: Color::Reset,
-    header_fg: Color::White,
-    border: Color::Gray,
-    highlight: Color::Cyan,
-};
+/// `NO_COLOR` set to anything but an empty string (https://no-color.org).
+pub fn no_color_requested() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+pub fn themes_dir() -> PathBuf {
+    state::config_dir().join("themes")
+}
+
+/// Built-in theme names, then the user's `themes/<name>.toml` files.
+pub fn available() -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN.iter().map(|n| n.to_string()).collect();
+    let mut files: Vec<String> = fs::read_dir(themes_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "toml"))
+        .filter_map(|p| Some(p.file_stem()?.to_str()?.to_string()))
+        .filter(|n| !names.contains(n))
+        .collect();
+    files.sort();
+    names.extend(files);
+    names
+}
+
+// themes/<name>.toml: any subset of the colors, on top of a built-in `base`
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFile {
+    base: Option<String>,
+    bg: Option<String>,
+    fg: Option<String>,
+    muted: Option<String>,
+    accent: Option<String>,
+    warn: Option<String>,
+    error: Option<String>,
+    header_bg: Option<String>,
+    header_fg: Option<String>,
+    border: Option<String>,
+    highlight: Option<String>,
+    highlight_bg: Option<String>,
+}
+
+/// The theme called `name`: `themes/<name>.toml` in the config dir, else a built-in.
+pub fn load(name: &str) -> Result<Theme, String> {
+    let path = themes_dir().join(format!("{name}.toml"));
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(_) => return Theme::builtin(name).ok_or(format!("unknown theme '{name}'")),
+    };
+    let shown = path.display();
+    let file: ThemeFile = toml::from_str(&text)
+        .map_err(|e| format!("{shown}: {}", e.message().replace('\n', ", ")))?;
+    let base = file.base.as_deref().unwrap_or("dark");
+    let mut theme = Theme::builtin(base).ok_or(format!("{shown}: unknown base theme '{base}'"))?;
+    let fields = [
+        ("bg", &file.bg, &mut theme.bg),
+        ("fg", &file.fg, &mut theme.fg),
+        ("muted", &file.muted, &mut theme.muted),
+        ("accent", &file.accent, &mut theme.accent),
+        ("warn", &file.warn, &mut theme.warn),
+        ("error", &file.error, &mut theme.error),
+        ("header_bg", &file.header_bg, &mut theme.header_bg),
+        ("header_fg", &file.header_fg, &mut theme.header_fg),
+        ("border", &file.border, &mut theme.border),
+        ("highlight", &file.highlight, &mut theme.highlight),
+        ("highlight_bg", &file.highlight_bg, &mut theme.highlight_bg),
+    ];
+    for (key, value, slot) in fields {
+        if let Some(value) = value {
+            *slot = Color::from_str(value)
+                .map_err(|_| format!("{shown}: {key}: unknown color '{value}'"))?;
+        }
+    }
+    Ok(theme)
+}
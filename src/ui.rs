@@ -1,6 +1,7 @@
+use crossterm::event::KeyCode;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Modifier, Style},
+    style::Modifier,
     text::{Line, Span},
     widgets::{
         Block, Borders, Cell, Clear, Paragraph, Row, Scrollbar, ScrollbarOrientation,
@@ -11,28 +12,93 @@ use ratatui::{
 use unicode_width::UnicodeWidthStr;
 
 use crate::app::{App, InputKind, Mode};
-use crate::theme::THEME;
+use crate::filter::Query;
+use crate::hit::{Hit, HitMap, ScrollTarget};
+use crate::jobs::JobState;
+use crate::keymap::{Action, Section};
+use crate::palette;
+use crate::rollback::Step;
+use crate::snapper::{self, DeletePlan};
+use crate::theme::Theme;
+use crate::vi;
+use crate::view::{self, Column, View};
+
+/// Width classes the layout adapts to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Breakpoint {
+    // tmux splits and small windows: short dates, fewer columns, full-width modals
+    Narrow,
+    // 80x24 and up
+    Medium,
+    Wide,
+}
+
+impl Breakpoint {
+    fn of(width: u16) -> Breakpoint {
+        match width {
+            0..=79 => Breakpoint::Narrow,
+            80..=119 => Breakpoint::Medium,
+            _ => Breakpoint::Wide,
+        }
+    }
+}
 
 pub fn draw(frame: &mut Frame, app: &mut App) {
-    // Layout: [tabs][main][status][userdata?]
+    let theme = app.theme;
+    // Layout: [tabs][main][status][userdata?]; the status bar is the message on its top
+    // border and the summary line under it
     let mut constraints: Vec<Constraint> = vec![
         Constraint::Length(3),
         Constraint::Min(1),
-        Constraint::Length(1),
+        Constraint::Length(2),
     ];
-    if app.show_userdata {
-        constraints.push(Constraint::Length(7));
+    // The userdata panel takes a quarter of the height and gives way on short terminals
+    let height = frame.area().height;
+    let show_userdata = app.show_userdata && height >= 18;
+    if show_userdata {
+        constraints.push(Constraint::Length((height / 4).clamp(5, 12)));
     }
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(constraints)
-        .split(frame.size());
+        .split(frame.area());
+
+    // Theme background and text color under everything drawn below
+    frame.render_widget(Block::default().style(theme.text_style()), frame.area());
+
+    let mut hits = HitMap::default();
 
     // Top tabs for configs
-    draw_config_tabs(frame, chunks[0], app);
+    draw_config_tabs(frame, chunks[0], app, &mut hits);
+
+    // Booted from a snapshot: a one-line banner above the table offers to keep it
+    let mut main = chunks[1];
+    if let Some((cfg, s)) = app.booted_snapshot() {
+        let parts = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(1)])
+            .split(main);
+        let banner = Paragraph::new(format!(
+            " Booted from read-only snapshot {cfg} #{} ({}). Changes are lost on reboot · P make this permanent (rollback)",
+            s.id, s.date
+        ))
+        .style(theme.warn_style().add_modifier(Modifier::REVERSED));
+        frame.render_widget(banner, parts[0]);
+        main = parts[1];
+    }
+
+    // The preview pane takes the right part when the table keeps enough room
+    if app.show_preview && main.width >= 100 {
+        let parts = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(1), Constraint::Percentage(40)])
+            .split(main);
+        draw_preview(frame, parts[1], app);
+        main = parts[0];
+    }
 
     // Always show the single snapshots table as main view
-    draw_snapshots_only(frame, chunks[1], app);
+    draw_snapshots_only(frame, main, app, &mut hits);
 
     // Status bar first
     let cfg = app
@@ -43,7 +109,7 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
         .unwrap_or("-");
     let snaps_total = app.snapshots.len();
     let snaps_filtered = app.filtered_snaps.len();
-    let sudo = if app.use_sudo { "sudo:on" } else { "sudo:off" };
+    let sudo = format!("priv:{}", app.escalation.name());
     let filter_hint = if app.filter_text.trim().is_empty() {
         String::new()
     } else {
@@ -54,13 +120,44 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
     } else {
         format!("snaps: {}/{}", snaps_filtered, snaps_total)
     };
-    let left = format!("cfg: {cfg}  {snaps_label}  {sudo}{filter_hint}");
-    let right = "q quit · r refresh · c create · e edit · d delete · Enter details · x diff · m mount · U umount · R rollback · K cleanup · C view-config · g edit-config (form) · Q setup-quota · Y limine-sync · F filter · Tab/Shift-Tab switch-config · [ ] switch-config · u userdata · S sudo · ? help";
-    let status_line = Line::from(vec![
-        Span::styled(left, Style::default()),
-        Span::raw("  |  "),
-        Span::styled(right, Style::default().add_modifier(Modifier::DIM)),
-    ]);
+    let running = app.jobs.running().count();
+    let jobs_hint = if running > 0 {
+        format!("  {} jobs: {} running", spinner(app.tick), running)
+    } else {
+        String::new()
+    };
+    let dry = if app.dry_run { "  DRY-RUN" } else { "" };
+    let ro = if app.read_only { "  READ-ONLY" } else { "" };
+    let marks = if app.marked.is_empty() {
+        String::new()
+    } else {
+        format!("  marked: {}", app.marked.len())
+    };
+    let vi_hint = if app.vi_keys {
+        format!("  VI {}", app.vi.pending())
+    } else {
+        String::new()
+    };
+    let view_hint = match app.active_view_name() {
+        Some(name) if app.view_modified() => format!("  view: {name}*"),
+        Some(name) => format!("  view: {name}"),
+        None => String::new(),
+    };
+    let left = format!(
+        "cfg: {cfg}  {snaps_label}  {sudo}{ro}{dry}{vi_hint}{marks}{view_hint}{filter_hint}{jobs_hint}"
+    );
+    // Key hints that don't fit collapse to where the full list is (? and the palette)
+    let room = usize::from(chunks[2].width).saturating_sub(left.width() + 5);
+    let right = [app.keymap.hints(), app.keymap.short_hints()]
+        .into_iter()
+        .find(|h| h.width() <= room)
+        .unwrap_or_default();
+    let mut status_spans = vec![Span::styled(left, theme.text_style())];
+    if !right.is_empty() {
+        status_spans.push(Span::raw("  |  "));
+        status_spans.push(Span::styled(right, theme.muted_style()));
+    }
+    let status_line = Line::from(status_spans);
     let status = Paragraph::new(status_line).block(
         Block::default()
             .borders(Borders::TOP)
@@ -69,8 +166,8 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
     frame.render_widget(status, chunks[2]);
 
     // Optional bottom userdata bar (like SnapperGUI)
-    if app.show_userdata {
-        let area = chunks.last().copied().unwrap_or_else(|| frame.size());
+    if show_userdata {
+        let area = chunks.last().copied().unwrap_or_else(|| frame.area());
         let mut lines: Vec<Line> = Vec::new();
         if let Some(s_idx) = app.snaps_state.selected {
             if let Some(s) = app.filtered_snaps.get(s_idx) {
@@ -84,19 +181,28 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
                     s.config.as_str()
                 };
                 lines.push(Line::from(vec![
-                    Span::styled("ID: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled("ID: ", theme.label_style()),
                     Span::raw(format!("{}", s.id)),
+                    Span::styled(
+                        match (s.active, s.default) {
+                            (true, true) => " (running, default)",
+                            (true, false) => " (running)",
+                            (false, true) => " (default for next boot)",
+                            (false, false) => "",
+                        },
+                        theme.muted_style(),
+                    ),
                 ]));
                 lines.push(Line::from(vec![
-                    Span::styled("Config: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled("Config: ", theme.label_style()),
                     Span::raw(cfg_name),
                 ]));
                 lines.push(Line::from(vec![
-                    Span::styled("Date: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled("Date: ", theme.label_style()),
                     Span::raw(s.date.clone()),
                 ]));
                 lines.push(Line::from(vec![
-                    Span::styled("User: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled("User: ", theme.label_style()),
                     Span::raw(if s.user.is_empty() {
                         "-"
                     } else {
@@ -104,7 +210,7 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
                     }),
                 ]));
                 lines.push(Line::from(vec![
-                    Span::styled("Type: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled("Type: ", theme.label_style()),
                     Span::raw(if s.kind.is_empty() {
                         "-"
                     } else {
@@ -112,7 +218,7 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
                     }),
                 ]));
                 lines.push(Line::from(vec![
-                    Span::styled("Cleanup: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled("Cleanup: ", theme.label_style()),
                     Span::raw(if s.cleanup.is_empty() {
                         "-"
                     } else {
@@ -120,25 +226,15 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
                     }),
                 ]));
                 lines.push(Line::from(vec![
-                    Span::styled(
-                        "Description: ",
-                        Style::default().add_modifier(Modifier::BOLD),
-                    ),
+                    Span::styled("Description: ", theme.label_style()),
                     Span::raw(s.description.clone()),
                 ]));
                 // Simple hints: mountpoint (best effort) and diff/status ranges
-                if let Some(sel) = app.snaps_state.selected {
-                    let from = if sel > 0 {
-                        app.filtered_snaps.get(sel - 1).map(|p| p.id).unwrap_or(0)
-                    } else {
-                        0
-                    };
+                if app.snaps_state.selected.is_some() {
+                    let from = app.predecessor_id(s.id);
                     let to = s.id;
                     lines.push(Line::from(vec![
-                        Span::styled(
-                            "Compare range: ",
-                            Style::default().add_modifier(Modifier::BOLD),
-                        ),
+                        Span::styled("Compare range: ", theme.label_style()),
                         Span::raw(format!(
                             "{}..{} (Enter to view status, x for diff)",
                             from, to
@@ -161,146 +257,399 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
                     // Prefer detected mountpoint from computed metadata
                     if let Some(mp) = &app.selected_mount_point {
                         lines.push(Line::from(vec![
-                            Span::styled(
-                                "Mountpoint: ",
-                                Style::default().add_modifier(Modifier::BOLD),
-                            ),
+                            Span::styled("Mountpoint: ", theme.label_style()),
                             Span::raw(mp.clone()),
                         ]));
                     } else {
                         // We don't probe the filesystem here to avoid IO in the draw loop; just display candidates
                         lines.push(Line::from(vec![
-                            Span::styled(
-                                "Mountpoints: ",
-                                Style::default().add_modifier(Modifier::BOLD),
-                            ),
+                            Span::styled("Mountpoints: ", theme.label_style()),
                             Span::raw(candidates.join("  ")),
                         ]));
                     }
                 }
                 // Lightweight background summary (first lines of status) if available
-                if let Some(summary) = &app.userdata_summary {
-                    lines.push(Line::from(Span::styled(
-                        "Summary:",
-                        Style::default().add_modifier(Modifier::BOLD),
-                    )));
-                    for l in summary.lines() {
-                        lines.push(Line::from(l.to_string()));
+                match &app.userdata_summary {
+                    Some(Ok(summary)) if !summary.lines.is_empty() => {
+                        lines.push(Line::from(Span::styled("Summary:", theme.label_style())));
+                        for l in summary.lines.iter().take(6) {
+                            lines.push(Line::from(l.clone()));
+                        }
                     }
+                    Some(Err(e)) => lines.push(Line::from(format!("Summary error: {e}"))),
+                    _ => {}
                 }
             } else {
                 lines.push(Line::from(Span::styled(
                     "No snapshot selected",
-                    THEME.muted_style(),
+                    theme.muted_style(),
                 )));
             }
         } else {
             lines.push(Line::from(Span::styled(
                 "No snapshot selected",
-                THEME.muted_style(),
+                theme.muted_style(),
             )));
         }
         let bar = Paragraph::new(lines)
             .wrap(Wrap { trim: true })
-            .block(THEME.block("Userdata"));
+            .block(theme.block("Userdata"));
         frame.render_widget(bar, area);
     }
 
+    // With a modal open, only its own buttons and scrollbars take clicks
+    if !matches!(app.mode, Mode::Normal) {
+        hits.clear();
+        hits.add(frame.area(), Hit::Modal);
+    }
+
     // Then draw modals on top (no dim overlay)
     match &app.mode {
         Mode::Normal => {}
         Mode::Input(kind) => draw_input_modal(frame, app, kind),
-        Mode::ConfirmDelete(id) => draw_confirm_modal(frame, *id),
-        Mode::ConfirmRollback(id) => draw_confirm_rollback(frame, *id),
-        Mode::ConfirmCleanup(alg) => draw_confirm_cleanup(frame, alg),
+        Mode::ConfirmDelete(plan) => draw_confirm_modal(frame, &theme, plan, &mut hits),
+        Mode::Rollback => draw_rollback_wizard(frame, app),
+        Mode::ConfirmCleanup(alg) => draw_confirm_cleanup(frame, &theme, alg, &mut hits),
+        Mode::ConfirmCancel(id, back) => {
+            let id = *id;
+            match **back {
+                Mode::Details => draw_details_modal(frame, app, &mut hits),
+                Mode::Jobs => draw_jobs_panel(frame, app),
+                _ => {}
+            }
+            // Only the confirm buttons are live, not the panel underneath
+            hits.clear();
+            hits.add(frame.area(), Hit::Modal);
+            draw_confirm_cancel(frame, app, id, &mut hits);
+        }
         Mode::Help => draw_help_modal(frame, app),
-        Mode::Details => draw_details_modal(frame, app),
-        Mode::Loading => draw_loading_modal(frame, app),
+        Mode::Details => draw_details_modal(frame, app, &mut hits),
+        Mode::Jobs => draw_jobs_panel(frame, app),
+        Mode::History => draw_history_panel(frame, app),
         Mode::ConfigForm => draw_config_form(frame, app),
+        Mode::Views => draw_views_picker(frame, app),
+        Mode::Columns => draw_columns_editor(frame, app),
+        Mode::Palette => draw_palette(frame, app),
     }
+    app.hits = hits;
 }
 
-fn draw_snapshots_only(frame: &mut Frame, area: Rect, app: &App) {
+// Clickable "[ y Delete ]" buttons centered on the last row of `area`
+fn draw_buttons(
+    frame: &mut Frame,
+    theme: &Theme,
+    area: Rect,
+    buttons: &[(char, &str)],
+    hits: &mut HitMap,
+) {
+    if area.height == 0 {
+        return;
+    }
+    let labels: Vec<String> = buttons
+        .iter()
+        .map(|(key, label)| format!("[ {key} {label} ]"))
+        .collect();
+    let gap = 2;
+    let total: u16 = labels
+        .iter()
+        .map(|l| UnicodeWidthStr::width(l.as_str()) as u16)
+        .sum::<u16>()
+        + gap * labels.len().saturating_sub(1) as u16;
+    let mut x = area.x + area.width.saturating_sub(total) / 2;
+    let y = area.bottom() - 1;
+    for ((key, _), label) in buttons.iter().zip(&labels) {
+        let width =
+            (UnicodeWidthStr::width(label.as_str()) as u16).min(area.right().saturating_sub(x));
+        let rect = Rect::new(x, y, width, 1);
+        frame.render_widget(
+            Paragraph::new(label.as_str()).style(theme.header_bar_style()),
+            rect,
+        );
+        hits.add(rect, Hit::Key(KeyCode::Char(*key)));
+        x = x.saturating_add(width + gap);
+    }
+}
+
+// Change list of the selected snapshot against its pre or previous snapshot, counts on top
+fn draw_preview(frame: &mut Frame, area: Rect, app: &App) {
+    let theme = app.theme;
+    let title = match app.selected_diff_range {
+        Some((from, to)) => format!("Changes {from}..{to}"),
+        None => "Changes".to_string(),
+    };
+    let mut lines: Vec<Line> = Vec::new();
+    match (&app.selected_diff_range, &app.userdata_summary) {
+        (None, _) => lines.push(Line::from(Span::styled(
+            "No snapshot selected",
+            theme.muted_style(),
+        ))),
+        (Some(_), None) => lines.push(Line::from(Span::styled(
+            format!("{} Comparing…", spinner(app.tick)),
+            theme.muted_style(),
+        ))),
+        (Some(_), Some(Err(e))) => {
+            lines.push(Line::from(Span::styled(e.clone(), theme.error_style())))
+        }
+        (Some(_), Some(Ok(summary))) if summary.total == 0 => {
+            lines.push(Line::from(Span::styled("No changes", theme.muted_style())))
+        }
+        (Some(_), Some(Ok(summary))) => {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("+{} created", summary.created),
+                    theme.accent_style(),
+                ),
+                Span::raw("  "),
+                Span::styled(format!("-{} deleted", summary.deleted), theme.error_style()),
+            ]));
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("c{} modified", summary.modified),
+                    theme.warn_style(),
+                ),
+                Span::raw("  "),
+                Span::styled(
+                    format!("{} metadata", summary.metadata),
+                    theme.muted_style(),
+                ),
+            ]));
+            lines.push(Line::from(""));
+            for l in &summary.lines {
+                let (flags, path) = l.split_once(' ').unwrap_or((l.as_str(), ""));
+                let style = match flags.chars().next() {
+                    Some('+') => theme.accent_style(),
+                    Some('-') => theme.error_style(),
+                    Some('c' | 't') => theme.warn_style(),
+                    _ => theme.muted_style(),
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(flags.to_string(), style),
+                    Span::raw(" "),
+                    Span::styled(path.trim_start().to_string(), theme.text_style()),
+                ]));
+            }
+            let hidden = summary.total - summary.lines.len();
+            if hidden > 0 {
+                lines.push(Line::from(Span::styled(
+                    format!("… {hidden} more"),
+                    theme.muted_style(),
+                )));
+            }
+        }
+    }
+    let pane = Paragraph::new(lines).block(theme.block(title));
+    frame.render_widget(pane, area);
+}
+
+fn draw_snapshots_only(frame: &mut Frame, area: Rect, app: &mut App, hits: &mut HitMap) {
+    let theme = app.theme;
     // Minimal size guard: if area too small, show a hint
-    if area.width < 50 || area.height < 5 {
+    if area.width < 30 || area.height < 5 {
         let hint = Paragraph::new("Terminal too small. Recommended ≥ 80x24")
-            .style(THEME.warn_style())
-            .block(THEME.block("Resize terminal"));
+            .style(theme.warn_style())
+            .block(theme.block("Resize terminal"));
         frame.render_widget(hint, area);
         return;
     }
-    let block = THEME.block("Snapshots");
+    let block = theme.block("Snapshots");
 
     if app.filtered_snaps.is_empty() {
         let empty = Paragraph::new("No snapshots")
-            .style(THEME.muted_style())
+            .style(theme.muted_style())
             .block(block);
         frame.render_widget(empty, area);
         return;
     }
 
+    // Compute inner area to decide if scrollbar is needed
+    let inner_area = block.inner(area);
+    let date_width = app
+        .filtered_snaps
+        .iter()
+        .map(|s| s.date.width() as u16)
+        .max()
+        .unwrap_or(0);
+    let (columns, dates) = fit_columns(
+        &app.columns,
+        inner_area.width,
+        Breakpoint::of(area.width),
+        date_width,
+    );
     let rows: Vec<Row> = app
         .filtered_snaps
         .iter()
         .map(|s| {
-            Row::new(vec![
-                Cell::from(format!("{}", s.id)),
-                Cell::from(s.date.clone()),
-                Cell::from(if s.user.is_empty() {
-                    "-".to_string()
-                } else {
-                    s.user.clone()
-                }),
-                Cell::from(s.kind.clone()),
-                Cell::from(s.cleanup.clone()),
-                Cell::from(s.description.clone()),
-            ])
+            // Same markers as `snapper list`: - active, + default, * both
+            let marker = match (s.active, s.default) {
+                (true, true) => "*",
+                (true, false) => "-",
+                (false, true) => "+",
+                (false, false) => "",
+            };
+            let style = match (s.active, s.default) {
+                (_, true) => theme.accent_style(),
+                (true, false) => theme.warn_style().add_modifier(Modifier::BOLD),
+                _ => theme.text_style(),
+            };
+            let style = if app.marked.contains(&s.id) {
+                style.add_modifier(Modifier::REVERSED)
+            } else {
+                style
+            };
+            let cells: Vec<Cell> = columns
+                .iter()
+                .map(|col| {
+                    Cell::from(match col {
+                        Column::Id => format!("{}{marker}", s.id),
+                        Column::Date => dates.format(&s.date),
+                        Column::User if s.user.is_empty() => "-".to_string(),
+                        Column::User => s.user.clone(),
+                        Column::Type => s.kind.clone(),
+                        Column::Cleanup => s.cleanup.clone(),
+                        Column::Description => s.description.clone(),
+                        Column::Size => s.used_space.map(view::format_size).unwrap_or("-".into()),
+                    })
+                })
+                .collect();
+            Row::new(cells).style(style)
         })
         .collect();
-    // Compute inner area to decide if scrollbar is needed
-    let inner_area = block.inner(area);
-    let table = Table::new(
-        rows,
-        [
-            Constraint::Length(6),
-            Constraint::Length(26),
-            Constraint::Length(8),
-            Constraint::Length(10),
-            Constraint::Length(14),
-            Constraint::Min(10),
-        ],
-    )
-    .header(
-        Row::new(vec![
-            Cell::from("#"),
-            Cell::from("Date"),
-            Cell::from("User"),
-            Cell::from("Type"),
-            Cell::from("Cleanup"),
-            Cell::from("Description"),
-        ])
-        .style(THEME.header_style().bg(THEME.header_bg)),
-    )
-    .block(block)
-    .highlight_style(THEME.highlight_style())
-    .highlight_symbol("▶ ");
-    let mut tstate = TableState::default();
-    tstate.select(app.snaps_state.selected);
+    let widths: Vec<Constraint> = columns
+        .iter()
+        .map(|col| match column_width(*col, dates, date_width) {
+            Some(w) => Constraint::Length(w),
+            None => Constraint::Min(10),
+        })
+        .collect();
+    // The sort column's header carries the direction
+    let headers: Vec<Cell> = columns
+        .iter()
+        .map(|col| match (*col == app.sort.column, app.sort.descending) {
+            (true, false) => Cell::from(format!("{} ↑", col.header())),
+            (true, true) => Cell::from(format!("{} ↓", col.header())),
+            (false, _) => Cell::from(col.header()),
+        })
+        .collect();
+    let table = Table::new(rows, widths)
+        .header(Row::new(headers).style(theme.header_bar_style()))
+        .block(block)
+        .highlight_style(theme.highlight_style())
+        .highlight_symbol("▶ ");
+    let mut tstate = TableState::default()
+        .with_offset(app.snaps_state.offset)
+        .with_selected(app.snaps_state.selected);
     frame.render_stateful_widget(table, area, &mut tstate);
+    app.snaps_state.offset = tstate.offset();
 
+    // inner height available for rows = inner.height minus 1 for header
+    let visible_rows: usize = inner_area.height.saturating_sub(1) as usize;
+    app.snaps_page_lines = visible_rows as u16;
+    let total = app.filtered_snaps.len();
+    for (line, idx) in (tstate.offset()..total).take(visible_rows).enumerate() {
+        let row = Rect::new(
+            inner_area.x,
+            inner_area.y + 1 + line as u16,
+            inner_area.width,
+            1,
+        );
+        hits.add(row, Hit::SnapshotRow(idx));
+    }
     if let Some(sel) = app.snaps_state.selected {
-        let total = app.filtered_snaps.len();
-        // inner height available for rows = inner.height minus 1 for header
-        let visible_rows: usize = inner_area.height.saturating_sub(1) as usize;
         if total > visible_rows {
             let mut sb = ScrollbarState::new(total).position(sel);
             let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight);
             frame.render_stateful_widget(scrollbar, area, &mut sb);
+            // The track between the arrows at either end
+            let track = Rect::new(
+                area.right() - 1,
+                area.y + 1,
+                1,
+                area.height.saturating_sub(2),
+            );
+            hits.add(track, Hit::Scrollbar(ScrollTarget::Snapshots));
         }
     }
 }
 
-fn draw_config_tabs(frame: &mut Frame, area: Rect, app: &App) {
+/// How much of a snapshot date fits: snapper's own text, or a parsed and shortened form.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DateStyle {
+    Full,
+    // 2025-01-31 14:05
+    Short,
+    // 01-31 14:05
+    Compact,
+}
+
+impl DateStyle {
+    fn format(self, date: &str) -> String {
+        let pattern = match self {
+            DateStyle::Full => return date.to_string(),
+            DateStyle::Short => "%Y-%m-%d %H:%M",
+            DateStyle::Compact => "%m-%d %H:%M",
+        };
+        snapper::parse_date(date)
+            .map(|d| d.format(pattern).to_string())
+            .unwrap_or_else(|| date.to_string())
+    }
+}
+
+// Fixed width of a column; None for the description, which takes the rest
+fn column_width(col: Column, dates: DateStyle, full_date_width: u16) -> Option<u16> {
+    Some(match col {
+        Column::Id => 6,
+        Column::Date => match dates {
+            DateStyle::Full => full_date_width.clamp(10, 32),
+            DateStyle::Short => 16,
+            DateStyle::Compact => 11,
+        },
+        Column::User => 8,
+        Column::Type => 7,
+        Column::Cleanup => 14,
+        Column::Size => 10,
+        Column::Description => return None,
+    })
+}
+
+/// The columns that fit in `width`, and how to show dates there. The chosen columns stay in
+/// their order; when they don't fit, the least telling ones are dropped first.
+fn fit_columns(
+    columns: &[Column],
+    width: u16,
+    breakpoint: Breakpoint,
+    full_date_width: u16,
+) -> (Vec<Column>, DateStyle) {
+    const DROP_ORDER: [Column; 5] = [
+        Column::Cleanup,
+        Column::User,
+        Column::Size,
+        Column::Type,
+        Column::Date,
+    ];
+    let dates = match breakpoint {
+        Breakpoint::Narrow => DateStyle::Compact,
+        Breakpoint::Medium => DateStyle::Short,
+        Breakpoint::Wide => DateStyle::Full,
+    };
+    let mut shown = columns.to_vec();
+    // Highlight symbol, one space between columns, and at least 10 for the description
+    let needed = |cols: &[Column]| -> u16 {
+        2 + cols
+            .iter()
+            .map(|c| column_width(*c, dates, full_date_width).unwrap_or(10) + 1)
+            .sum::<u16>()
+    };
+    for col in DROP_ORDER {
+        if needed(&shown) <= width || shown.len() <= 1 {
+            break;
+        }
+        shown.retain(|c| *c != col);
+    }
+    (shown, dates)
+}
+
+fn draw_config_tabs(frame: &mut Frame, area: Rect, app: &App, hits: &mut HitMap) {
+    let theme = app.theme;
     let titles: Vec<Line> = if app.configs.is_empty() {
         vec![Line::from("(no configs)")]
     } else {
@@ -314,12 +663,22 @@ fn draw_config_tabs(frame: &mut Frame, area: Rect, app: &App) {
         .selected
         .unwrap_or(0)
         .min(app.configs.len().saturating_sub(1));
+    let block = Block::default().borders(Borders::BOTTOM).title("Configs");
+    // Tabs lay out as " title " with a 3-wide divider between them
+    let inner = block.inner(area);
+    let mut x = inner.x;
+    for (i, c) in app.configs.iter().enumerate() {
+        let width = UnicodeWidthStr::width(c.name.as_str()) as u16 + 2;
+        let width = width.min(inner.right().saturating_sub(x));
+        hits.add(Rect::new(x, inner.y, width, 1), Hit::ConfigTab(i));
+        x = x.saturating_add(width + 3);
+    }
     let tabs = Tabs::new(titles)
         .select(idx)
-        .block(Block::default().borders(Borders::BOTTOM).title("Configs"))
-        .style(Style::default().fg(THEME.fg))
-        .highlight_style(THEME.highlight_style())
-        .divider(Span::styled(" │ ", THEME.muted_style()));
+        .block(block)
+        .style(theme.text_style())
+        .highlight_style(theme.highlight_style())
+        .divider(Span::styled(" │ ", theme.muted_style()));
     frame.render_widget(tabs, area);
 }
 
@@ -328,6 +687,17 @@ fn draw_config_tabs(frame: &mut Frame, area: Rect, app: &App) {
 // draw_left and draw_right removed (legacy, unused)
 
 fn centered_rect(r: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    // Small screens: modals use (almost) all of it rather than a fraction
+    let percent_x = if Breakpoint::of(r.width) == Breakpoint::Narrow {
+        percent_x.max(96)
+    } else {
+        percent_x
+    };
+    let percent_y = if r.height < 30 {
+        percent_y.max(90)
+    } else {
+        percent_y
+    };
     let vert = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -364,21 +734,40 @@ fn centered_rect_fixed(r: Rect, width: u16, height: u16) -> Rect {
 // dim overlay removed per user preference
 
 fn draw_input_modal(frame: &mut Frame, app: &App, kind: &InputKind) {
+    let theme = app.theme;
     // Use a very compact modal for Filter (similar to a password box)
-    let area = centered_rect_fixed(frame.size(), 50, 5);
+    // Filter is wider so the query syntax hint fits
+    let width = if matches!(kind, InputKind::Filter) {
+        84
+    } else {
+        50
+    };
+    let area = centered_rect_fixed(frame.area(), width, 5);
     frame.render_widget(Clear, area); // clear background
     let title = match kind {
         InputKind::Create => "Create snapshot description",
         InputKind::Edit(id) => Box::leak(format!("Edit description for #{}", id).into_boxed_str()),
         InputKind::CleanupAlgorithm => "Cleanup algorithm (number/timeline/empty-pre-post)",
+        InputKind::DetailsSearch if app.vi.backward => "Find in details, backward (?)",
         InputKind::DetailsSearch => "Find in details (/)",
+        InputKind::TableSearch if app.vi.backward => "Find snapshot, backward (?)",
+        InputKind::TableSearch => "Find snapshot (/)",
+        InputKind::Command(_) => "Command (:)",
         InputKind::ConfigFieldEdit(idx) => {
             Box::leak(format!("Edit value for field #{}", idx + 1).into_boxed_str())
         }
         InputKind::Filter => "Filter snapshots",
+        InputKind::AroundCommand => "Command to run between a pre and post snapshot",
+        InputKind::ExportPath => "Export shown snapshots to (.csv, .json or .md)",
+        InputKind::AroundDescription(_) => "Description for the snapshot pair",
+        InputKind::ViewName => "Save view as (same name overwrites)",
+        InputKind::SudoPassword if app.auth_failures > 0 => {
+            Box::leak(format!("sudo password ({} failed)", app.auth_failures).into_boxed_str())
+        }
+        InputKind::SudoPassword => "sudo password",
     };
     let bottom = "Enter · Esc";
-    let block = THEME
+    let block = theme
         .modal_warn_block(title)
         .title_bottom(Line::from(bottom).centered());
     frame.render_widget(block.clone(), area);
@@ -387,23 +776,56 @@ fn draw_input_modal(frame: &mut Frame, app: &App, kind: &InputKind) {
     // We keep a single border for all inputs; labels are implied by the title now.
     // For Filter keep a single border (modal block) to reduce height; otherwise draw an inner input block
     // Keep a single border (modal block) for all inputs for a tighter layout
-    let input_area = content_area;
+    let input_area = Rect {
+        height: content_area.height.min(1),
+        ..content_area
+    };
+    // Filter queries are checked as they are typed; the error sits under the input
+    if matches!(kind, InputKind::Filter) && content_area.height > 2 {
+        let note_area = Rect {
+            y: content_area.y + 2,
+            height: 1,
+            ..content_area
+        };
+        let note = match Query::parse(&app.input) {
+            Err(e) => Span::styled(e.to_string(), theme.error_style()),
+            Ok(_) => Span::styled(
+                "type: cleanup: user: id:1..9 date> age<7d desc:/re/ userdata:k=v · - OR ( )",
+                theme.muted_style(),
+            ),
+        };
+        frame.render_widget(Paragraph::new(Line::from(note)), note_area);
+    }
 
     // Placeholder when empty
     let placeholder = match kind {
         InputKind::Create | InputKind::Edit(_) => "Type description…",
         InputKind::CleanupAlgorithm => "e.g., number, timeline, empty-pre-post",
-        InputKind::DetailsSearch => "Type search text…",
+        InputKind::DetailsSearch | InputKind::TableSearch => "Type search text…",
+        InputKind::Command(vi::Target::Table) => "e.g. delete, mount, 42, filter type:pre, q",
+        InputKind::Command(vi::Target::Details) => "Line number, or q to close",
         InputKind::ConfigFieldEdit(_) => "Type value…",
-        InputKind::Filter => "Type filter…",
+        InputKind::Filter => "e.g. type:pre -user:root age<7d",
+        InputKind::AroundCommand => "e.g., sudo pacman -Syu",
+        InputKind::ExportPath => "e.g., ~/snapshots.md",
+        InputKind::AroundDescription(_) => "Empty uses the command line…",
+        InputKind::ViewName => "e.g., manual only",
+        InputKind::SudoPassword => "Password (not stored)…",
+    };
+    // Never render the password itself, only one mask char per typed char
+    let masked = matches!(kind, InputKind::SudoPassword);
+    let shown = if masked {
+        "•".repeat(app.input.chars().count())
+    } else {
+        app.input.clone()
     };
     let mut paragraph = if app.input.is_empty() {
-        Paragraph::new(Line::from(Span::styled(placeholder, THEME.muted_style())))
+        Paragraph::new(Line::from(Span::styled(placeholder, theme.muted_style())))
     } else {
-        Paragraph::new(app.input.as_str())
+        Paragraph::new(shown.as_str())
     };
     // Horizontal scroll to keep cursor visible
-    let before = app.input.chars().take(app.input_cursor).collect::<String>();
+    let before = shown.chars().take(app.input_cursor).collect::<String>();
     let w = UnicodeWidthStr::width(before.as_str()) as u16;
     let area_w = input_area.width.saturating_sub(1); // leave space for caret
     let hscroll: u16 = if w >= area_w { w - area_w + 1 } else { 0 };
@@ -418,83 +840,78 @@ fn draw_input_modal(frame: &mut Frame, app: &App, kind: &InputKind) {
             cursor_x = right_edge;
         }
         let cursor_y = input_area.y; // single-line input
-        frame.set_cursor(cursor_x, cursor_y);
+        frame.set_cursor_position((cursor_x, cursor_y));
     }
     // Bottom hint is now part of the modal's bottom title
 }
 
-fn draw_details_modal(frame: &mut Frame, app: &mut App) {
-    let area = centered_rect(frame.size(), 80, 70);
+fn draw_details_modal(frame: &mut Frame, app: &mut App, hits: &mut HitMap) {
+    let theme = app.theme;
+    let area = centered_rect(frame.area(), 80, 70);
     frame.render_widget(Clear, area);
 
     // Compute content area first to derive pagination metrics for footer
-    let tmp_block = THEME.modal_block(&app.details_title);
+    let tmp_block = theme.modal_block(&app.details_title);
     let inner = tmp_block.inner(area);
 
-    // Fixed-width prewrap and pagination
-    const FIXED_WRAP_COLS: u16 = 100;
+    // Lines are pre-wrapped by the buffer; only the visible slice is fetched
     let mut content_area = inner;
     if content_area.width > 1 {
         content_area.width -= 1; // reserve rightmost col for scrollbar
     }
-    let rendered_text = normalize_text_for_ui(app.details_text.as_str());
-    let prewrapped = prewrap_text(rendered_text.as_str(), FIXED_WRAP_COLS);
-    let lines: Vec<&str> = prewrapped.lines().collect();
-    let total_lines = lines.len();
+    let total_lines = app.details_buf().len();
     let visible_h = content_area.height as usize;
     // Store actual visible page lines for paging keys
     app.details_page_lines = visible_h as u16;
     let max_scroll = total_lines.saturating_sub(visible_h);
-    let clamped_scroll: usize = app.details_scroll.min(max_scroll as u16) as usize;
-    // Write back clamped value to keep state in sync and avoid perceived freeze at bounds
-    let clamped_u16 = if clamped_scroll > u16::MAX as usize {
-        u16::MAX
-    } else {
-        clamped_scroll as u16
-    };
-    app.details_scroll = clamped_u16;
+    if app.details_follow {
+        app.details_scroll = max_scroll;
+    }
     // Write back the clamped value so input handling doesn't accumulate past the end
     // (prevents feeling of freeze when at the bottom)
-    // SAFETY: casting down is fine since details_scroll is u16 and clamped within max_scroll.
-    // Note: this mutates app during draw; acceptable for UI sync.
-    // Ideally, clamp during input or state update, but this keeps a single source of truth.
-    // If needed later, we can refactor to a setter.
-    // WARNING: Requires &mut App, but draw receives &App. So we cannot write back here.
-    // We'll emulate by bounding the value used below; to prevent perceived freeze, we show 'End' badge.
+    let clamped_scroll = app.details_scroll.min(max_scroll);
+    app.details_scroll = clamped_scroll;
     let start = clamped_scroll;
     let end = (start + visible_h).min(total_lines);
 
     // Footer with pagination and boundary hints
-    let page = if visible_h == 0 {
-        1
-    } else {
-        start / visible_h + 1
-    };
+    let page = start.checked_div(visible_h).unwrap_or(0) + 1;
     let total_pages = if visible_h == 0 {
         1
     } else {
-        total_lines.div_ceil(visible_h)
+        total_lines.div_ceil(visible_h).max(1)
     };
     let at_top = start == 0;
     let at_end = start >= max_scroll;
-    let mut footer = format!(
-        "↑/↓ PageUp/PageDown · Home/End · / find · n/N next/prev · Esc · Page {}/{}",
-        page, total_pages
-    );
-    if at_top {
-        footer.push_str(" · Top");
+    // Narrow screens keep only the position; the keys are in the help
+    let mut footer = if Breakpoint::of(area.width) == Breakpoint::Narrow {
+        format!("Page {page}/{total_pages}")
+    } else {
+        format!(
+            "↑/↓ PageUp/PageDown · Home/End · / find · n/N next/prev · Esc · Page {page}/{total_pages}"
+        )
+    };
+    if app.details_job_running() {
+        footer.push_str(&format!(" · Loading… {} lines · x cancel", total_lines));
+    } else {
+        if at_top {
+            footer.push_str(" · Top");
+        }
+        if at_end {
+            footer.push_str(" · End");
+        }
     }
-    if at_end {
-        footer.push_str(" · End");
+    if app.details_buf().is_spilled() {
+        footer.push_str(" · on disk");
     }
-    let block = THEME
+    let block = theme
         .modal_block(&app.details_title)
         .title_bottom(Line::from(footer).centered());
     frame.render_widget(block.clone(), area);
 
     // Render only visible slice without extra wrapping
-    let content = lines[start..end].join("\n");
-    let para = Paragraph::new(content).style(Style::default().bg(THEME.bg).fg(THEME.fg));
+    let content = app.details_buf().slice(start, end).join("\n");
+    let para = Paragraph::new(content).style(theme.text_style());
     frame.render_widget(para, content_area);
 
     // Scrollbar only when needed
@@ -502,125 +919,334 @@ fn draw_details_modal(frame: &mut Frame, app: &mut App) {
         // Use range (max_scroll + 1) so the thumb can reach the absolute bottom
         let mut sb = ScrollbarState::new(max_scroll + 1).position(clamped_scroll);
         let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
-            .track_style(Style::default().bg(THEME.header_bg))
-            .thumb_style(Style::default().fg(THEME.accent));
+            .track_style(theme.scrollbar_track_style())
+            .thumb_style(theme.scrollbar_thumb_style());
         frame.render_stateful_widget(scrollbar, inner, &mut sb);
+        let track = Rect::new(
+            inner.right() - 1,
+            inner.y + 1,
+            1,
+            inner.height.saturating_sub(2),
+        );
+        hits.add(track, Hit::Scrollbar(ScrollTarget::Details));
     }
 }
-fn draw_confirm_modal(frame: &mut Frame, id: u64) {
-    let area = centered_rect(frame.size(), 50, 25);
+fn draw_confirm_modal(frame: &mut Frame, theme: &Theme, plan: &DeletePlan, hits: &mut HitMap) {
+    let area = centered_rect(frame.area(), 60, 40);
     frame.render_widget(Clear, area);
-    let block = THEME
+    let second_stage = !plan.important.is_empty() && plan.important_acked;
+    let mut hints = if !plan.important.is_empty() && !plan.important_acked {
+        "y continue".to_string()
+    } else {
+        "y to confirm".to_string()
+    };
+    if !plan.orphaned_posts.is_empty() {
+        hints.push_str("  ·  p include pairs");
+    }
+    hints.push_str("  ·  n or Esc to cancel");
+    let block = theme
         .modal_error_block("Confirm delete")
-        .title_bottom(Line::from("y to confirm  ·  n or Esc to cancel").centered());
+        .title_bottom(Line::from(hints).centered());
     frame.render_widget(block.clone(), area);
     let inner = block.inner(area);
-    let text = Paragraph::new(Line::from(vec![
-        Span::raw("Delete snapshot #"),
-        Span::styled(
-            id.to_string(),
-            Style::default().add_modifier(Modifier::BOLD),
-        ),
+    let numbers = |ids: &[u64]| {
+        ids.iter()
+            .map(|id| format!("#{id}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let bold = theme.label_style();
+    let mut lines = vec![Line::from(vec![
+        Span::raw(if plan.ids.len() == 1 {
+            "Delete snapshot "
+        } else {
+            "Delete snapshots "
+        }),
+        Span::styled(numbers(&plan.ids), bold),
         Span::raw("?"),
-    ]))
-    .style(THEME.error_style());
-    frame.render_widget(text, inner);
+    ])];
+    for skipped in &plan.skipped {
+        lines.push(Line::from(Span::styled(
+            format!("Skipped: {skipped}"),
+            theme.muted_style(),
+        )));
+    }
+    for warning in &plan.warnings {
+        lines.push(Line::from(Span::styled(
+            format!("⚠ {warning}"),
+            theme.warn_style(),
+        )));
+    }
+    if !plan.important.is_empty() {
+        lines.push(Line::from(""));
+        let msg = if second_stage {
+            format!(
+                "{} marked important=yes. Press y again to delete anyway.",
+                numbers(&plan.important)
+            )
+        } else {
+            format!("{} marked important=yes.", numbers(&plan.important))
+        };
+        lines.push(Line::from(Span::styled(msg, bold)));
+    }
+    let text = Paragraph::new(lines)
+        .style(theme.error_style())
+        .wrap(Wrap { trim: false });
+    let mut text_area = inner;
+    text_area.height = text_area.height.saturating_sub(2);
+    frame.render_widget(text, text_area);
+    let first_stage = !plan.important.is_empty() && !plan.important_acked;
+    let mut buttons = vec![('y', if first_stage { "Continue" } else { "Delete" })];
+    if !plan.orphaned_posts.is_empty() {
+        buttons.push(('p', "Include pairs"));
+    }
+    buttons.push(('n', "Cancel"));
+    draw_buttons(frame, theme, inner, &buttons, hits);
 }
 
-fn draw_confirm_rollback(frame: &mut Frame, id: u64) {
-    let area = centered_rect(frame.size(), 50, 25);
+fn draw_rollback_wizard(frame: &mut Frame, app: &App) {
+    let theme = app.theme;
+    let Some(w) = &app.rollback else {
+        return;
+    };
+    let area = centered_rect(frame.area(), 70, 60);
     frame.render_widget(Clear, area);
-    let block = THEME
-        .modal_error_block("Confirm rollback")
-        .title_bottom(Line::from("y to confirm  ·  n or Esc to cancel").centered());
+    let hints = match &w.step {
+        Step::Review => "type the snapshot id, Enter to roll back  ·  Esc to cancel",
+        Step::Running(_) => "Esc to hide (the rollback keeps running)",
+        Step::Done(_) => "b update bootloader entries  ·  Enter or Esc to close",
+    };
+    let block = theme
+        .modal_error_block(format!("Rollback {} to #{}", w.config, w.target.id))
+        .title_bottom(Line::from(hints).centered());
     frame.render_widget(block.clone(), area);
     let inner = block.inner(area);
-    let text = Paragraph::new(Line::from(vec![
-        Span::raw("Rollback to snapshot #"),
-        Span::styled(
-            id.to_string(),
-            Style::default().add_modifier(Modifier::BOLD),
-        ),
-        Span::raw("? This will revert the subvolume."),
-    ]))
-    .style(THEME.error_style());
+    let bold = theme.label_style();
+    let mut lines: Vec<Line> = Vec::new();
+    match &w.probe {
+        None => lines.push(Line::from(Span::styled(
+            "Current default: checking…",
+            theme.muted_style(),
+        ))),
+        Some(p) => {
+            let current = match (&p.default_subvol, p.default_snapshot) {
+                (Ok(_), Some(n)) => format!("snapshot #{n}"),
+                (Ok(line), None) => line.clone(),
+                (Err(e), _) => format!("unknown ({e})"),
+            };
+            lines.push(Line::from(vec![
+                Span::raw("Current default: "),
+                Span::styled(current, bold),
+            ]));
+        }
+    }
+    lines.push(Line::from(vec![
+        Span::raw("Target:          "),
+        Span::styled(format!("#{}", w.target.id), bold),
+        Span::raw(format!(
+            "  {} · {} · {}",
+            w.target.kind, w.target.date, w.target.description
+        )),
+    ]));
+    lines.push(Line::from(""));
+    if let Some(warning) = w.probe.as_ref().and_then(|p| p.kernel_warning.as_ref()) {
+        lines.push(Line::from(Span::styled(
+            format!("⚠ {warning}"),
+            theme.warn_style(),
+        )));
+        lines.push(Line::from(""));
+    }
+    match &w.step {
+        Step::Review => {
+            lines.push(Line::from(
+                "snapper will keep a read-only copy of the current system and make a \
+                 writable copy of the target the new default. The change applies on reboot.",
+            ));
+            lines.push(Line::from(""));
+            let style = if w.confirmed() {
+                theme.highlight_style()
+            } else {
+                theme.error_style()
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!("Type {} to confirm: ", w.target.id)),
+                Span::styled(format!("{}▏", w.confirm), style),
+            ]));
+        }
+        Step::Running(job) => {
+            let spin = spinner(app.tick);
+            let last = app
+                .jobs
+                .get(*job)
+                .map(|j| j.last_line())
+                .unwrap_or_default();
+            lines.push(Line::from(format!("{spin} Rolling back… {last}")));
+        }
+        Step::Done(outcome) => {
+            let number = |n: Option<u64>| n.map(|n| format!("#{n}")).unwrap_or("?".into());
+            lines.push(Line::from(vec![
+                Span::raw("Backup of the previous system: "),
+                Span::styled(number(outcome.backup), bold),
+            ]));
+            lines.push(Line::from(vec![
+                Span::raw("New default snapshot:          "),
+                Span::styled(number(outcome.new_default), bold),
+            ]));
+            lines.push(Line::from(""));
+            lines.push(Line::from(
+                "Reboot to start the rolled-back system. Update the bootloader entries first \
+                 if they point at specific snapshots.",
+            ));
+        }
+    }
+    let text = Paragraph::new(lines).wrap(Wrap { trim: false });
     frame.render_widget(text, inner);
 }
 
-fn draw_confirm_cleanup(frame: &mut Frame, alg: &str) {
-    let area = centered_rect(frame.size(), 55, 28);
+fn draw_confirm_cleanup(frame: &mut Frame, theme: &Theme, alg: &str, hits: &mut HitMap) {
+    let area = centered_rect(frame.area(), 55, 28);
     frame.render_widget(Clear, area);
-    let block = THEME
+    let block = theme
         .modal_warn_block("Confirm cleanup")
         .title_bottom(Line::from("y to confirm  ·  n or Esc to cancel").centered());
     frame.render_widget(block.clone(), area);
     let inner = block.inner(area);
     let msg = format!("Run 'snapper cleanup {}' for current config?", alg);
-    let text = Paragraph::new(msg).style(THEME.warn_style());
+    let text = Paragraph::new(msg).style(theme.warn_style());
     frame.render_widget(text, inner);
+    draw_buttons(
+        frame,
+        theme,
+        inner,
+        &[('y', "Run cleanup"), ('n', "Cancel")],
+        hits,
+    );
 }
 
-fn draw_help_modal(frame: &mut Frame, app: &App) {
-    let area = centered_rect(frame.size(), 72, 72);
+fn draw_confirm_cancel(frame: &mut Frame, app: &App, id: u64, hits: &mut HitMap) {
+    let theme = app.theme;
+    let area = centered_rect(frame.area(), 55, 28);
     frame.render_widget(Clear, area);
-    let lines = vec![
-        Line::from(Span::styled("[Help]", THEME.header_style())),
-        Line::from(""),
-        Line::from(Span::styled(
-            "[Basics]",
-            Style::default().add_modifier(Modifier::BOLD),
-        )),
-        Line::from("  q  Quit   ·   r  Refresh   ·   ?  Toggle this help"),
-        Line::from("  S  Toggle sudo (use if snapper needs privileges)"),
-        Line::from(""),
-        Line::from(Span::styled(
-            "[Navigation]",
-            Style::default().add_modifier(Modifier::BOLD),
-        )),
-        Line::from("  Tab / Shift-Tab / ← → / [ ]  Switch config tabs"),
-        Line::from("  ↑/↓  Move selection   ·   PgUp/PgDn  Jump by 10   ·   Home/End  First/Last"),
-        Line::from("  Mouse wheel  Scroll list or details"),
+    let block = theme
+        .modal_warn_block("Cancel running operation?")
+        .title_bottom(Line::from("y to terminate  ·  n or Esc to keep running").centered());
+    frame.render_widget(block.clone(), area);
+    let inner = block.inner(area);
+    let (name, outcome) = app
+        .jobs
+        .get(id)
+        .map(|j| (j.op.name(), j.op.cancel_outcome()))
+        .unwrap_or(("operation", "state unknown"));
+    let text = Paragraph::new(vec![
+        Line::from(vec![
+            Span::raw("Terminate '"),
+            Span::styled(name, theme.label_style()),
+            Span::raw("' while it is running?"),
+        ]),
         Line::from(""),
-        Line::from(Span::styled(
-            "[Snapshots]",
-            Style::default().add_modifier(Modifier::BOLD),
-        )),
-        Line::from("  Enter  Show status (prev..selected)"),
-        Line::from("  x      Show diff (prev..selected)"),
-        Line::from("  m/U    Mount / Unmount"),
-        Line::from("  R      Rollback (confirm)"),
-        Line::from("  Y      Sync to Limine"),
-        Line::from("  K      Cleanup (enter algorithm: number | timeline | empty-pre-post)"),
-        Line::from("  c/e/d  Create / Edit / Delete"),
-        Line::from("  F / Ctrl-F  Filter"),
-        Line::from("  u      Toggle bottom Userdata panel"),
+        Line::from(format!("If interrupted, {outcome}.")),
+    ])
+    .wrap(Wrap { trim: true })
+    .style(theme.warn_style());
+    frame.render_widget(text, inner);
+    draw_buttons(
+        frame,
+        &theme,
+        inner,
+        &[('y', "Terminate"), ('n', "Keep running")],
+        hits,
+    );
+}
+
+fn draw_help_modal(frame: &mut Frame, app: &mut App) {
+    let theme = app.theme;
+    let area = centered_rect(frame.area(), 72, 72);
+    frame.render_widget(Clear, area);
+    let bold = theme.label_style();
+    let mut lines = vec![
+        Line::from(Span::styled("[Help]", theme.header_style())),
         Line::from(""),
-        Line::from(Span::styled(
-            "[Config management]",
-            Style::default().add_modifier(Modifier::BOLD),
+    ];
+    for problem in &app.keymap_problems {
+        lines.push(Line::from(Span::styled(
+            format!("Keymap ignored: {problem}"),
+            theme.error_style(),
+        )));
+    }
+    if !app.keymap_problems.is_empty() {
+        lines.push(Line::from(""));
+    }
+    // Main-view keys come from the keymap, so rebinding shows up here
+    for section in Section::ALL {
+        lines.push(Line::from(Span::styled(
+            format!("[{}]", section.title()),
+            bold,
+        )));
+        for action in Action::ALL.into_iter().filter(|a| a.section() == section) {
+            let keys = app.keymap.label(action);
+            let keys = if keys.is_empty() {
+                "(unbound)".to_string()
+            } else {
+                keys
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!("  {keys:<16} {}", action.label())),
+                // keymap.toml name
+                Span::styled(format!("  {}", action.name()), theme.muted_style()),
+            ]));
+        }
+        lines.push(Line::from(""));
+    }
+    let read_only_keys: Vec<String> = Action::ALL
+        .into_iter()
+        .filter(|a| a.changes_state())
+        .filter_map(|a| app.keymap.first_key(a))
+        .collect();
+    lines.extend([
+        Line::from(Span::styled("[Panels]", bold)),
+        Line::from("  Filter: text, type:/cleanup:/user:, id:1..9, date>2025-01-01, age<7d,"),
+        Line::from("          desc:/regex/, userdata:k=v; -term negates, OR and ( ) combine"),
+        Line::from("  Command palette: fuzzy-find any action; dimmed ones say why they can't run"),
+        Line::from("  Columns: Space show/hide · Shift-↑/↓ or < > move · s sort · r reset"),
+        Line::from("  Views: Enter/1-9 switch · s save current table · d delete"),
+        Line::from("  Jobs: Enter output · x cancel · c clear finished"),
+        Line::from("  History (audit log): c filter config · o filter operation"),
+        Line::from("  Config form: ↑/↓ select · Enter/e edit · s/y save · Esc cancel"),
+        Line::from("  Rollback wizard: type the id to confirm; b updates boot entries after"),
+        Line::from("  Mouse: click rows and tabs, double-click for status, drag scrollbars"),
+        Line::from(format!(
+            "  Vi keys ({}): j/k, gg/G, 5G, Ctrl-d/u, / ? n N, m/' marks, '' back;",
+            app.keymap.label(Action::ViKeys)
         )),
-        Line::from("  C      View config (get-config)"),
         Line::from(
-            "  g      Edit config (form): ↑/↓ select · Enter/e edit · s/y save · Esc cancel",
+            "          they replace g ? m in the table; :name runs an action (:edit-config,",
         ),
-        Line::from("  Q      Setup quota"),
+        Line::from("          :help, :mount), :42 selects #42, :filter <query>, :set novi, :q"),
+        Line::from(format!(
+            "  Read-only mode (--read-only or \"read_only\": true) disables {}",
+            read_only_keys.join("/")
+        )),
         Line::from(""),
+    ]);
+    lines.extend([
         Line::from(Span::styled(
             "[Modals & overlays]",
-            Style::default().add_modifier(Modifier::BOLD),
+            theme.label_style(),
         )),
         Line::from("  Esc    Close/cancel (Help, Input, Confirm, Details)"),
         Line::from("  Details overlay: ↑/↓/PgUp/PgDn/Home/End · '/' find · n/N next/prev · Esc"),
+        Line::from("  Esc leaves a running status/diff in the background; x cancels it"),
         Line::from(""),
         Line::from(Span::styled(
             "[Notes]",
-            Style::default().add_modifier(Modifier::BOLD),
+            theme.label_style(),
         )),
         Line::from(
-            "  If you see permission/DBus errors, toggle sudo (S) or run via 'make sudo-run'.",
+            "  If you see permission/DBus errors, pick an escalation method (S) or run via 'make sudo-run'.",
         ),
-    ];
-    let block = THEME
+        Line::from("  When sudo needs a password you are prompted for it (masked, never stored)."),
+    ]);
+    let block = theme
         .modal_block("Help")
         .title_bottom(Line::from("↑/↓ PgUp/PgDn scroll · Esc to close").centered());
     frame.render_widget(block.clone(), area);
@@ -629,6 +1255,7 @@ fn draw_help_modal(frame: &mut Frame, app: &App) {
     // Scrollable help content
     let total_lines = lines.len().max(1);
     let visible_h = inner.height as usize;
+    app.help_page_lines = inner.height;
     let max_scroll = total_lines.saturating_sub(visible_h);
     let clamped_scroll = app.help_scroll.min(max_scroll as u16);
     let mut para = Paragraph::new(lines).wrap(Wrap { trim: true });
@@ -639,8 +1266,8 @@ fn draw_help_modal(frame: &mut Frame, app: &App) {
     if total_lines > visible_h {
         let mut sb = ScrollbarState::new(total_lines).position(clamped_scroll as usize);
         let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
-            .track_style(Style::default().bg(THEME.header_bg))
-            .thumb_style(Style::default().fg(THEME.accent));
+            .track_style(theme.scrollbar_track_style())
+            .thumb_style(theme.scrollbar_thumb_style());
         frame.render_stateful_widget(scrollbar, inner, &mut sb);
     }
 }
@@ -648,9 +1275,10 @@ fn draw_help_modal(frame: &mut Frame, app: &App) {
 // ConfigPicker removed; 'g' opens form editor directly.
 
 fn draw_config_form(frame: &mut Frame, app: &App) {
-    let area = centered_rect(frame.size(), 80, 70);
+    let theme = app.theme;
+    let area = centered_rect(frame.area(), 80, 70);
     frame.render_widget(Clear, area);
-    let block = THEME.modal_block("Edit Config (Form)").title_bottom(
+    let block = theme.modal_block("Edit Config (Form)").title_bottom(
         Line::from("Up/Down select · Enter/e edit · s or y save · Esc cancel").centered(),
     );
     frame.render_widget(block.clone(), area);
@@ -659,7 +1287,7 @@ fn draw_config_form(frame: &mut Frame, app: &App) {
     let rows: Vec<Row> = if app.cfg_fields.is_empty() {
         vec![Row::new(vec![Cell::from(Span::styled(
             "No config fields",
-            THEME.muted_style(),
+            theme.muted_style(),
         ))])]
     } else {
         app.cfg_fields
@@ -676,99 +1304,372 @@ fn draw_config_form(frame: &mut Frame, app: &App) {
     let widths = [Constraint::Percentage(35), Constraint::Percentage(65)];
     let table = Table::new(rows, widths)
         .header(
-            Row::new(vec![Cell::from("Key"), Cell::from("Value")])
-                .style(THEME.header_style().bg(THEME.header_bg)),
+            Row::new(vec![Cell::from("Key"), Cell::from("Value")]).style(theme.header_bar_style()),
         )
-        .highlight_style(THEME.highlight_style())
+        .highlight_style(theme.highlight_style())
         .highlight_symbol("▶ ");
     let mut state = TableState::default();
     state.select(app.cfg_field_idx);
     frame.render_stateful_widget(table.block(Block::default()), inner, &mut state);
 }
 
-// (Removed duplicate draw_details_modal; single pagination version defined earlier)
-
-// Normalize diff/status text for stable display in a terminal:
-// - Expand tabs to 4 spaces (diffs often contain tabs)
-// - Strip carriage returns (CR) that can cause overwriting artifacts
-// - Remove ANSI escape sequences if any slipped through
-fn normalize_text_for_ui(text: &str) -> String {
-    let mut out = String::with_capacity(text.len());
-    let mut esc = false;
-    let mut ansi = false;
-    for ch in text.chars() {
-        if ansi {
-            // End ANSI on letter (rough heuristic) or 'm'
-            if ch.is_ascii_alphabetic() || ch == 'm' {
-                ansi = false;
-            }
-            continue;
-        }
-        if esc {
-            // Expect '[' then parameters...
-            if ch == '[' {
-                ansi = true;
-                esc = false;
-                continue;
-            }
-            esc = false;
-            continue;
-        }
-        match ch {
-            '\t' => out.push_str("    "),
-            '\r' => {}
-            '\u{1b}' => esc = true, // ESC
-            _ => out.push(ch),
-        }
+fn draw_history_panel(frame: &mut Frame, app: &App) {
+    let theme = app.theme;
+    let area = centered_rect(frame.area(), 90, 70);
+    frame.render_widget(Clear, area);
+    let title = format!(
+        "History · config: {} · op: {}",
+        app.history_config.as_deref().unwrap_or("all"),
+        app.history_op.as_deref().unwrap_or("all")
+    );
+    let block = theme.modal_block(title).title_bottom(
+        Line::from("↑/↓ select · Enter details · c config · o operation · r reload · Esc close")
+            .centered(),
+    );
+    frame.render_widget(block.clone(), area);
+    let inner = block.inner(area);
+
+    let view = app.history_view();
+    if view.is_empty() {
+        let empty = Paragraph::new("No matching audit entries").style(theme.muted_style());
+        frame.render_widget(empty, inner);
+        return;
     }
-    out
+    let rows: Vec<Row> = view
+        .iter()
+        .map(|e| {
+            let when = chrono::DateTime::parse_from_rfc3339(&e.timestamp)
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|_| e.timestamp.clone());
+            let (result, style) = match (&e.error, e.exit_code) {
+                (Some(err), _) => (err.clone(), theme.error_style()),
+                (None, Some(0)) => ("ok".to_string(), theme.text_style()),
+                (None, Some(code)) => (format!("exit {code}"), theme.error_style()),
+                (None, None) => ("?".to_string(), theme.warn_style()),
+            };
+            Row::new(vec![
+                Cell::from(when),
+                Cell::from(if e.config.is_empty() {
+                    "-".to_string()
+                } else {
+                    e.config.clone()
+                }),
+                Cell::from(e.op.clone()),
+                Cell::from(e.escalation.clone()),
+                Cell::from(result).style(style),
+                Cell::from(e.command_line()).style(theme.muted_style()),
+            ])
+        })
+        .collect();
+    let widths = [
+        Constraint::Length(19),
+        Constraint::Length(10),
+        Constraint::Length(14),
+        Constraint::Length(6),
+        Constraint::Length(10),
+        Constraint::Min(10),
+    ];
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec![
+                "Time",
+                "Config",
+                "Operation",
+                "Via",
+                "Result",
+                "Command",
+            ])
+            .style(theme.header_bar_style()),
+        )
+        .highlight_style(theme.highlight_style())
+        .highlight_symbol("▶ ");
+    let mut state = TableState::default();
+    state.select(app.history_sel);
+    frame.render_stateful_widget(table, inner, &mut state);
 }
 
-// Insert soft line breaks so that each line is at most `width` cells wide (unicode-aware)
-fn prewrap_text(text: &str, width: u16) -> String {
-    let maxw = width.max(1) as usize;
-    let mut out = String::with_capacity(text.len());
-    for line in text.lines() {
-        let mut current_width = 0usize;
-        for ch in line.chars() {
-            let cw = UnicodeWidthStr::width(ch.encode_utf8(&mut [0; 4]));
-            if cw == 0 {
-                out.push(ch);
-                continue;
-            }
-            if current_width + cw > maxw {
-                out.push('\n');
-                current_width = 0;
-            }
-            out.push(ch);
-            current_width += cw;
-        }
-        out.push('\n');
-    }
-    out
+fn spinner(tick: u64) -> &'static str {
+    // animate roughly every ~200ms
+    ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"][(tick / 2 % 10) as usize]
 }
 
-fn draw_loading_modal(frame: &mut Frame, app: &App) {
-    let area = centered_rect(frame.size(), 40, 20);
+fn draw_columns_editor(frame: &mut Frame, app: &App) {
+    let theme = app.theme;
+    let area = centered_rect_fixed(frame.area(), 60, 14);
     frame.render_widget(Clear, area);
-    let dots = match (app.tick / 6) % 4 {
-        // animate roughly every ~600ms
-        0 => "",
-        1 => ".",
-        2 => "..",
-        _ => "...",
+    let block = theme.modal_block("Columns").title_bottom(
+        Line::from("Space show/hide · Shift-↑/↓ or < > move · s sort · r reset · Esc close")
+            .centered(),
+    );
+    frame.render_widget(block.clone(), area);
+    let inner = block.inner(area);
+
+    let rows: Vec<Row> = app
+        .column_choices()
+        .into_iter()
+        .map(|col| {
+            let shown = app.columns.contains(&col);
+            let sort = match (col == app.sort.column, app.sort.descending) {
+                (true, false) => "sorted ↑",
+                (true, true) => "sorted ↓",
+                (false, _) => "",
+            };
+            let note = if col == Column::Size {
+                "needs btrfs quota"
+            } else {
+                ""
+            };
+            Row::new(vec![
+                Cell::from(if shown { "[x]" } else { "[ ]" }),
+                Cell::from(col.header()),
+                Cell::from(sort),
+                Cell::from(note).style(theme.muted_style()),
+            ])
+            .style(if shown {
+                theme.text_style()
+            } else {
+                theme.muted_style()
+            })
+        })
+        .collect();
+    let widths = [
+        Constraint::Length(4),
+        Constraint::Length(12),
+        Constraint::Length(9),
+        Constraint::Min(10),
+    ];
+    let table = Table::new(rows, widths)
+        .highlight_style(theme.highlight_style())
+        .highlight_symbol("▶ ");
+    let mut state = TableState::default();
+    state.select(app.columns_sel);
+    frame.render_stateful_widget(table, inner, &mut state);
+}
+
+fn draw_views_picker(frame: &mut Frame, app: &App) {
+    let theme = app.theme;
+    let area = centered_rect(frame.area(), 70, 50);
+    frame.render_widget(Clear, area);
+    let title = format!("Views for {}", app.view_config);
+    let block = theme.modal_block(&title).title_bottom(
+        Line::from("↑/↓ select · Enter/1-9 switch · s save current · d delete · Esc close")
+            .centered(),
+    );
+    frame.render_widget(block.clone(), area);
+    let inner = block.inner(area);
+
+    let active = app.active_view_name();
+    let default_entry = View {
+        name: "default".into(),
+        ..app.default_view.clone()
     };
-    let title = format!("Working{dots}");
-    let block = THEME
-        .modal_block(title)
-        .title_bottom(Line::from("Esc/q to cancel").centered());
+    let rows: Vec<Row> = std::iter::once(&default_entry)
+        .chain(app.config_views())
+        .enumerate()
+        .map(|(i, v)| {
+            let is_active = if i == 0 {
+                active.is_none()
+            } else {
+                active == Some(v.name.as_str())
+            };
+            let key = if i == 0 || i > 9 {
+                String::new()
+            } else {
+                i.to_string()
+            };
+            let sort = format!(
+                "{} {}",
+                v.sort.column.header(),
+                if v.sort.descending { "↓" } else { "↑" }
+            );
+            let mut name = v.name.clone();
+            if is_active && app.view_modified() {
+                name.push_str(" (modified)");
+            }
+            let style = if is_active {
+                theme.header_style()
+            } else {
+                theme.text_style()
+            };
+            Row::new(vec![
+                Cell::from(key),
+                Cell::from(name),
+                Cell::from(sort),
+                Cell::from(if v.show_userdata { "userdata" } else { "" }),
+                Cell::from(v.filter.clone()).style(theme.muted_style()),
+            ])
+            .style(style)
+        })
+        .collect();
+    let widths = [
+        Constraint::Length(2),
+        Constraint::Length(24),
+        Constraint::Length(14),
+        Constraint::Length(9),
+        Constraint::Min(10),
+    ];
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec!["", "View", "Sort", "Panel", "Filter"]).style(theme.header_bar_style()),
+        )
+        .highlight_style(theme.highlight_style())
+        .highlight_symbol("▶ ");
+    let mut state = TableState::default();
+    state.select(app.views_sel);
+    frame.render_stateful_widget(table, inner, &mut state);
+}
+
+fn draw_palette(frame: &mut Frame, app: &App) {
+    let theme = app.theme;
+    let area = centered_rect(frame.area(), 70, 60);
+    frame.render_widget(Clear, area);
+    let block = theme
+        .modal_block("Commands")
+        .title_bottom(Line::from("type to search · ↑/↓ select · Enter run · Esc close").centered());
     frame.render_widget(block.clone(), area);
     let inner = block.inner(area);
-    let msg_text = if app.loading_message.is_empty() {
-        "Please wait…".to_string()
+    if inner.height < 3 {
+        return;
+    }
+    let input_area = Rect { height: 1, ..inner };
+    let list_area = Rect {
+        y: inner.y + 2,
+        height: inner.height - 2,
+        ..inner
+    };
+
+    let prompt = if app.input.is_empty() {
+        Line::from(vec![
+            Span::styled("> ", theme.header_style()),
+            Span::styled("e.g. rollback, quota, limine…", theme.muted_style()),
+        ])
     } else {
-        app.loading_message.clone()
+        Line::from(vec![
+            Span::styled("> ", theme.header_style()),
+            Span::raw(app.input.as_str()),
+        ])
     };
-    let msg = Paragraph::new(msg_text);
-    frame.render_widget(msg, inner);
+    frame.render_widget(Paragraph::new(prompt), input_area);
+    let before: String = app.input.chars().take(app.input_cursor).collect();
+    let cursor_x = input_area.x + 2 + UnicodeWidthStr::width(before.as_str()) as u16;
+    frame.set_cursor_position((
+        cursor_x.min(input_area.right().saturating_sub(1)),
+        input_area.y,
+    ));
+
+    // Disabled actions stay listed, dimmed, with the reason they can't run
+    let entries = palette::search(&app.input);
+    let rows: Vec<Row> = entries
+        .iter()
+        .map(|entry| {
+            let reason = app.action_unavailable(entry.action);
+            let base = if reason.is_some() {
+                theme.muted_style()
+            } else {
+                theme.text_style()
+            };
+            let label: Vec<Span> = entry
+                .action
+                .label()
+                .chars()
+                .enumerate()
+                .map(|(i, c)| {
+                    let style = if entry.matched.contains(&i) {
+                        base.patch(theme.accent_style())
+                    } else {
+                        base
+                    };
+                    Span::styled(c.to_string(), style)
+                })
+                .collect();
+            Row::new(vec![
+                Cell::from(Line::from(label)),
+                Cell::from(app.keymap.label(entry.action)).style(theme.muted_style()),
+                Cell::from(reason.unwrap_or_default()).style(theme.warn_style()),
+            ])
+        })
+        .collect();
+    if rows.is_empty() {
+        frame.render_widget(
+            Paragraph::new(Span::styled("No matching action", theme.muted_style())),
+            list_area,
+        );
+        return;
+    }
+    let widths = [
+        Constraint::Min(30),
+        Constraint::Length(14),
+        Constraint::Length(26),
+    ];
+    let table = Table::new(rows, widths)
+        .highlight_style(theme.highlight_style())
+        .highlight_symbol("▶ ");
+    let mut state = TableState::default();
+    state.select(
+        app.palette_sel
+            .map(|i| i.min(entries.len().saturating_sub(1))),
+    );
+    frame.render_stateful_widget(table, list_area, &mut state);
+}
+
+fn draw_jobs_panel(frame: &mut Frame, app: &App) {
+    let theme = app.theme;
+    let area = centered_rect(frame.area(), 80, 60);
+    frame.render_widget(Clear, area);
+    let block = theme.modal_block("Jobs").title_bottom(
+        Line::from("↑/↓ select · Enter output · x cancel · c clear finished · Esc close")
+            .centered(),
+    );
+    frame.render_widget(block.clone(), area);
+    let inner = block.inner(area);
+
+    let ids = app.jobs_view_ids();
+    if ids.is_empty() {
+        let empty = Paragraph::new("No jobs yet").style(theme.muted_style());
+        frame.render_widget(empty, inner);
+        return;
+    }
+    let rows: Vec<Row> = ids
+        .iter()
+        .filter_map(|id| app.jobs.get(*id))
+        .map(|j| {
+            let state = if j.is_running() {
+                format!("{} {}", spinner(app.tick), j.state_label())
+            } else {
+                j.state_label()
+            };
+            let style = match j.state {
+                JobState::Running => theme.warn_style(),
+                JobState::Succeeded => theme.text_style(),
+                JobState::Failed(_) => theme.error_style(),
+                JobState::Interrupted(_) => theme.muted_style(),
+            };
+            Row::new(vec![
+                Cell::from(format!("#{}", j.id)),
+                Cell::from(j.config.clone()),
+                Cell::from(j.label.clone()),
+                Cell::from(state).style(style),
+                Cell::from(format!("{}s", j.elapsed().as_secs())),
+                Cell::from(j.last_line()).style(theme.muted_style()),
+            ])
+        })
+        .collect();
+    let widths = [
+        Constraint::Length(5),
+        Constraint::Length(10),
+        Constraint::Length(24),
+        Constraint::Length(16),
+        Constraint::Length(6),
+        Constraint::Min(10),
+    ];
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec!["ID", "Config", "Job", "State", "Time", "Last output"])
+                .style(theme.header_bar_style()),
+        )
+        .highlight_style(theme.highlight_style())
+        .highlight_symbol("▶ ");
+    let mut state = TableState::default();
+    state.select(app.jobs_sel);
+    frame.render_stateful_widget(table, inner, &mut state);
 }
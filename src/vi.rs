@@ -0,0 +1,149 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+
+/// Where vi keys are being typed; the same sequence moves the table or scrolls the details.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    Table,
+    Details,
+}
+
+/// A complete vi key sequence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Down(usize),
+    Up(usize),
+    HalfPageDown,
+    HalfPageUp,
+    // gg and G without a count
+    Top,
+    Bottom,
+    // 5G or 5gg: that line (1-based)
+    Line(usize),
+    // / or ?: open the search prompt
+    Search { backward: bool },
+    // n repeats the last search in its direction, N in the other one
+    NextMatch { reverse: bool, count: usize },
+    SetMark(char),
+    // '' (or ``) jumps back to where the last jump started
+    JumpMark(char),
+    // : opens the command line
+    Prompt,
+}
+
+/// What a key did to the pending sequence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Feed {
+    Command(Command),
+    // Part of a sequence (a count, g, m, '), or a sequence cancelled with Esc
+    Consumed,
+    // Not a vi key; handled by the keymap as usual
+    Unhandled,
+}
+
+/// Keys the vi layer takes before the keymap sees them (0 only continues a count).
+pub const KEYS: [&str; 23] = [
+    "j", "k", "g", "G", "m", "'", "`", "/", "?", "n", "N", ":", "Ctrl-d", "Ctrl-u", "1", "2", "3",
+    "4", "5", "6", "7", "8", "9",
+];
+
+// Jump marks are stored under this name
+pub const PREVIOUS: char = '\'';
+
+/// Vi key state: the sequence typed so far, the last search and the marks.
+#[derive(Debug, Clone, Default)]
+pub struct Vi {
+    count: Option<usize>,
+    prefix: Option<char>,
+    // Direction of the last / or ? search, which n keeps
+    pub backward: bool,
+    pub table_query: String,
+    // Snapshot ids per (config, mark) and details lines per mark
+    pub table_marks: BTreeMap<(String, char), u64>,
+    pub details_marks: BTreeMap<char, usize>,
+}
+
+impl Vi {
+    pub fn feed(&mut self, key: &KeyEvent) -> Feed {
+        let count = self.count;
+        if let Some(prefix) = self.prefix.take() {
+            self.count = None;
+            let cmd = match (prefix, key.code) {
+                ('g', KeyCode::Char('g')) => count.map_or(Command::Top, Command::Line),
+                ('m', KeyCode::Char(c)) if c.is_ascii_alphabetic() => Command::SetMark(c),
+                ('\'' | '`', KeyCode::Char(c)) if c.is_ascii_alphabetic() => Command::JumpMark(c),
+                ('\'' | '`', KeyCode::Char('\'' | '`')) => Command::JumpMark(PREVIOUS),
+                // Anything else drops the sequence, like vim does
+                _ => return Feed::Consumed,
+            };
+            return Feed::Command(cmd);
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.count = None;
+            return match key.code {
+                KeyCode::Char('d') => Feed::Command(Command::HalfPageDown),
+                KeyCode::Char('u') => Feed::Command(Command::HalfPageUp),
+                _ => Feed::Unhandled,
+            };
+        }
+        if !(key.modifiers - KeyModifiers::SHIFT).is_empty() {
+            self.count = None;
+            return Feed::Unhandled;
+        }
+        let n = count.unwrap_or(1);
+        let cmd = match key.code {
+            // 0 only continues a count; on its own it is not a vi key here
+            KeyCode::Char(c @ '0'..='9') if c != '0' || count.is_some() => {
+                let digit = c.to_digit(10).unwrap_or(0) as usize;
+                self.count = Some(
+                    count
+                        .unwrap_or(0)
+                        .saturating_mul(10)
+                        .saturating_add(digit)
+                        .min(99_999),
+                );
+                return Feed::Consumed;
+            }
+            KeyCode::Char(c @ ('g' | 'm' | '\'' | '`')) => {
+                self.prefix = Some(c);
+                return Feed::Consumed;
+            }
+            KeyCode::Esc if count.is_some() => {
+                self.count = None;
+                return Feed::Consumed;
+            }
+            KeyCode::Char('j') => Command::Down(n),
+            KeyCode::Char('k') => Command::Up(n),
+            KeyCode::Char('G') => count.map_or(Command::Bottom, Command::Line),
+            KeyCode::Char('/') => Command::Search { backward: false },
+            KeyCode::Char('?') => Command::Search { backward: true },
+            KeyCode::Char('n') => Command::NextMatch {
+                reverse: false,
+                count: n,
+            },
+            KeyCode::Char('N') => Command::NextMatch {
+                reverse: true,
+                count: n,
+            },
+            KeyCode::Char(':') => Command::Prompt,
+            _ => {
+                self.count = None;
+                return Feed::Unhandled;
+            }
+        };
+        self.count = None;
+        Feed::Command(cmd)
+    }
+
+    /// The sequence typed so far ("5", "g", "12m"), for the status bar.
+    pub fn pending(&self) -> String {
+        let mut s = self.count.map(|n| n.to_string()).unwrap_or_default();
+        s.extend(self.prefix);
+        s
+    }
+
+    pub fn reset(&mut self) {
+        self.count = None;
+        self.prefix = None;
+    }
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+use crate::snapper::{self, Snapshot};
+
+/// A column of the snapshot table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Column {
+    Id,
+    Date,
+    User,
+    Type,
+    Cleanup,
+    Description,
+    // Exclusive space, when snapper and btrfs quota can report it
+    Size,
+}
+
+impl Column {
+    pub const ALL: [Column; 7] = [
+        Column::Id,
+        Column::Date,
+        Column::User,
+        Column::Type,
+        Column::Cleanup,
+        Column::Description,
+        Column::Size,
+    ];
+
+    /// Stable name used in state.json and exports.
+    pub fn key(self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::Date => "date",
+            Column::User => "user",
+            Column::Type => "type",
+            Column::Cleanup => "cleanup",
+            Column::Description => "description",
+            Column::Size => "size",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Column> {
+        Column::ALL.into_iter().find(|c| c.key() == key)
+    }
+
+    pub fn header(self) -> &'static str {
+        match self {
+            Column::Id => "#",
+            Column::Date => "Date",
+            Column::User => "User",
+            Column::Type => "Type",
+            Column::Cleanup => "Cleanup",
+            Column::Description => "Description",
+            Column::Size => "Size",
+        }
+    }
+}
+
+/// Snapper's own column order; size is opt-in because it needs quota.
+pub fn default_columns() -> Vec<Column> {
+    Column::ALL
+        .into_iter()
+        .filter(|c| *c != Column::Size)
+        .collect()
+}
+
+/// "1.5 GiB" style size.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sort {
+    pub column: Column,
+    pub descending: bool,
+}
+
+impl Default for Sort {
+    fn default() -> Self {
+        Sort {
+            column: Column::Id,
+            descending: false,
+        }
+    }
+}
+
+fn compare(a: &Snapshot, b: &Snapshot, column: Column) -> Ordering {
+    let text = |x: &str, y: &str| x.to_lowercase().cmp(&y.to_lowercase());
+    match column {
+        Column::Id => a.id.cmp(&b.id),
+        // Unparseable dates sort first, then fall back to the raw text
+        Column::Date => snapper::parse_date(&a.date)
+            .cmp(&snapper::parse_date(&b.date))
+            .then_with(|| a.date.cmp(&b.date)),
+        Column::User => text(&a.user, &b.user),
+        Column::Type => text(&a.kind, &b.kind),
+        Column::Cleanup => text(&a.cleanup, &b.cleanup),
+        Column::Description => text(&a.description, &b.description),
+        Column::Size => a.used_space.cmp(&b.used_space),
+    }
+}
+
+/// Sort in place; ties keep snapshot number order so the result is stable across refreshes.
+pub fn sort(snaps: &mut [Snapshot], sort: Sort) {
+    snaps.sort_by(|a, b| {
+        let ord = compare(a, b, sort.column).then_with(|| a.id.cmp(&b.id));
+        if sort.descending {
+            ord.reverse()
+        } else {
+            ord
+        }
+    });
+}
+
+/// A named bundle of table settings, e.g. "manual only" or "last 7 days".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct View {
+    pub name: String,
+    pub filter: String,
+    pub sort: Sort,
+    pub columns: Vec<Column>,
+    pub show_userdata: bool,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View {
+            name: String::new(),
+            filter: String::new(),
+            sort: Sort::default(),
+            columns: default_columns(),
+            show_userdata: false,
+        }
+    }
+}